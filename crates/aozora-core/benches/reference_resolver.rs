@@ -0,0 +1,46 @@
+//! 前方参照の解決のベンチマーク
+//!
+//! `cargo bench -p aozora-core` で実行します。
+//! 外部のベンチマークフレームワークに依存せず、各入力を繰り返し解析した平均時間を表示します。
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use aozora_core::parser::parse;
+use aozora_core::tokenizer::tokenize;
+
+/// 前方参照を多く含む1行を生成
+///
+/// ルビで区切られたテキストへの参照、ルビにまたがる参照、見つからない参照を繰り返します。
+fn reference_heavy_line(repeat: usize) -> String {
+    (0..repeat)
+        .map(|i| {
+            format!(
+                "第{i}の漢字《かんじ》を含む文章を書く［＃「第{i}」に傍点］\
+                 その字《じ》と文章［＃「字と文章」に傍線］\
+                 ［＃「見つからない語{i}」に傍点］"
+            )
+        })
+        .collect()
+}
+
+/// `f` を最低 `min_time` の間繰り返し実行し、1回あたりの平均時間を返す
+fn measure(min_time: Duration, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while iterations == 0 || start.elapsed() < min_time {
+        f();
+        iterations += 1;
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    for repeat in [10, 100, 400] {
+        let tokens = tokenize(&reference_heavy_line(repeat));
+        let average = measure(Duration::from_secs(1), || {
+            black_box(parse(black_box(&tokens)));
+        });
+        println!("parse_reference_heavy_{repeat}: {average:?}/iter");
+    }
+}
@@ -212,6 +212,15 @@ mod tests {
         assert_eq!(convert_accent("AE&"), "Æ");
     }
 
+    #[test]
+    fn test_mixed_words_keep_spaces_and_punctuation() {
+        assert_eq!(convert_accent("cafe' au lait"), "café au lait");
+        assert_eq!(
+            convert_accent("cafe', s'il vous plait!"),
+            "café, s'il vous plait!"
+        );
+    }
+
     #[test]
     fn test_no_accent() {
         assert_eq!(convert_accent("hello"), "hello");
@@ -114,6 +114,88 @@ impl CharTypeExt for char {
     }
 }
 
+/// 文字列の書記素クラスタ（見た目の1文字）の数を数える
+///
+/// 結合文字（濁点・半濁点を含む）、異体字セレクタ、絵文字の肌色修飾子、
+/// 一部の文字のスペーシングマーク（デーヴァナーガリー・タイ文字の母音記号など）は
+/// 直前の文字と合わせて1文字とし、ゼロ幅接合子でつながった文字も1文字とします。
+/// ハングルの字母（初声・中声・終声）の並びは1音節として数えます。
+/// 外字のように基底文字と結合文字が別々のノードになっている場合も、
+/// 連結したテキストを渡せば正しく数えられます。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::char_type::count_graphemes;
+///
+/// assert_eq!(count_graphemes("カ\u{309A}き"), 2);
+/// assert_eq!(count_graphemes("\u{1100}\u{1161}\u{11A8}"), 1);
+/// ```
+pub fn count_graphemes(s: &str) -> usize {
+    let mut count = 0;
+    let mut joined = false;
+    let mut prev = None;
+    for c in s.chars() {
+        if joined {
+            // ゼロ幅接合子の直後の文字は直前の文字とつながる
+            joined = false;
+        } else if count == 0 || !(is_grapheme_extend(c) || prev.is_some_and(|p| joins_hangul(p, c)))
+        {
+            count += 1;
+        }
+        if c == '\u{200D}' {
+            joined = true;
+        }
+        prev = Some(c);
+    }
+    count
+}
+
+/// 直前の文字と合わせて1文字になる文字かどうか
+fn is_grapheme_extend(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'       // 結合分音記号
+            | '\u{0900}'..='\u{0903}' // デーヴァナーガリーの記号
+            | '\u{093A}'..='\u{093C}'
+            | '\u{093E}'..='\u{094F}' // デーヴァナーガリーの母音記号・ヴィラーマ
+            | '\u{0951}'..='\u{0957}'
+            | '\u{0962}'..='\u{0963}'
+            | '\u{0E31}'               // タイ文字の母音記号・声調記号
+            | '\u{0E34}'..='\u{0E3A}'
+            | '\u{0E47}'..='\u{0E4E}'
+            | '\u{1AB0}'..='\u{1AFF}' // 結合分音記号拡張
+            | '\u{1DC0}'..='\u{1DFF}' // 結合分音記号補助
+            | '\u{200D}'               // ゼロ幅接合子
+            | '\u{20D0}'..='\u{20FF}' // 記号用結合分音記号
+            | '\u{3099}'..='\u{309A}' // 結合用濁点・半濁点
+            | '\u{FE00}'..='\u{FE0F}' // 異体字セレクタ
+            | '\u{FE20}'..='\u{FE2F}' // 半記号
+            | '\u{1F3FB}'..='\u{1F3FF}' // 肌色修飾子
+            | '\u{E0100}'..='\u{E01EF}' // 異体字セレクタ補助
+    )
+}
+
+/// ハングルの字母・音節 `prev` の後の `c` が同じ音節になるかどうか
+fn joins_hangul(prev: char, c: char) -> bool {
+    let is_l = |c| matches!(c, '\u{1100}'..='\u{115F}' | '\u{A960}'..='\u{A97C}');
+    let is_v = |c| matches!(c, '\u{1160}'..='\u{11A7}' | '\u{D7B0}'..='\u{D7C6}');
+    let is_t = |c| matches!(c, '\u{11A8}'..='\u{11FF}' | '\u{D7CB}'..='\u{D7FB}');
+    let syllable = |c: char| ('\u{AC00}'..='\u{D7A3}').contains(&c);
+    // 終声のない音節（LV）
+    let is_lv = |c: char| syllable(c) && (c as u32 - 0xAC00).is_multiple_of(28);
+
+    if is_l(prev) {
+        is_l(c) || is_v(c) || syllable(c)
+    } else if is_v(prev) || is_lv(prev) {
+        is_v(c) || is_t(c)
+    } else if is_t(prev) || syllable(prev) {
+        is_t(c)
+    } else {
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,4 +374,33 @@ mod tests {
         assert_eq!(CharType::classify('，'), CharType::Zenkaku); // U+FF0C FULLWIDTH COMMA
         assert_eq!(CharType::classify('．'), CharType::Zenkaku); // U+FF0E FULLWIDTH FULL STOP
     }
+
+    #[test]
+    fn test_count_graphemes() {
+        assert_eq!(count_graphemes(""), 0);
+        assert_eq!(count_graphemes("傍記"), 2);
+        // カ + 結合用半濁点
+        assert_eq!(count_graphemes("カ\u{309A}き"), 2);
+        // 葛 + 異体字セレクタ
+        assert_eq!(count_graphemes("葛\u{E0100}城"), 2);
+        // ゼロ幅接合子でつながった絵文字
+        assert_eq!(count_graphemes("\u{1F468}\u{200D}\u{1F469}"), 1);
+        // 肌色修飾子
+        assert_eq!(count_graphemes("\u{1F44D}\u{1F3FD}"), 1);
+        // 先頭の結合文字は単独で1文字
+        assert_eq!(count_graphemes("\u{309A}"), 1);
+    }
+
+    #[test]
+    fn test_count_graphemes_hangul_and_spacing_marks() {
+        // 初声・中声・終声の字母の並び（각）と、音節＋終声
+        assert_eq!(count_graphemes("\u{1100}\u{1161}\u{11A8}"), 1);
+        assert_eq!(count_graphemes("\u{AC00}\u{11A8}한"), 2);
+        // 終声のある音節の後の中声は別の文字
+        assert_eq!(count_graphemes("\u{AC01}\u{1161}"), 2);
+        // デーヴァナーガリーの母音記号（スペーシングマーク）
+        assert_eq!(count_graphemes("कि"), 1);
+        // タイ文字の母音記号
+        assert_eq!(count_graphemes("กี"), 1);
+    }
 }
@@ -0,0 +1,225 @@
+//! 対応している注記の一覧
+//!
+//! パーサーが解釈する `［＃...］` 形式の注記を、分類と記述例つきで列挙します。
+//! 対応記法のリファレンスを生成するなど、ドキュメント用のツールから使用します。
+
+use self::CommandCategory::{Block, Inline, Midashi, Other, Style};
+
+/// 注記の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandCategory {
+    /// 装飾（傍点、傍線、太字など）
+    Style,
+    /// 見出し
+    Midashi,
+    /// ブロック（字下げ、地付き、罫囲みなど。行単位のものを含む）
+    Block,
+    /// 行内の要素（縦中横、割り注、ルビ系の注記など）
+    Inline,
+    /// その他（返り点、アキ、挿絵など）
+    Other,
+}
+
+/// 注記の仕様
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    /// 注記の名前（字数などの数値は `Ｎ` で表す）
+    pub name: &'static str,
+    /// 分類
+    pub category: CommandCategory,
+    /// 記述例（`［＃` と `］` を除いた注記の内容）
+    pub example: &'static str,
+}
+
+const fn spec(name: &'static str, category: CommandCategory, example: &'static str) -> CommandSpec {
+    CommandSpec {
+        name,
+        category,
+        example,
+    }
+}
+
+/// 対応している注記
+static SUPPORTED_COMMANDS: &[CommandSpec] = &[
+    // 傍点（右・上）
+    spec("傍点", Style, "「語」に傍点"),
+    spec("白ゴマ傍点", Style, "「語」に白ゴマ傍点"),
+    spec("丸傍点", Style, "「語」に丸傍点"),
+    spec("白丸傍点", Style, "「語」に白丸傍点"),
+    spec("黒三角傍点", Style, "「語」に黒三角傍点"),
+    spec("白三角傍点", Style, "「語」に白三角傍点"),
+    spec("二重丸傍点", Style, "「語」に二重丸傍点"),
+    spec("蛇の目傍点", Style, "「語」に蛇の目傍点"),
+    spec("ばつ傍点", Style, "「語」にばつ傍点"),
+    // 傍点（左・下）
+    spec("左に傍点", Style, "「語」の左に傍点"),
+    spec("左に白ゴマ傍点", Style, "「語」の左に白ゴマ傍点"),
+    spec("左に丸傍点", Style, "「語」の左に丸傍点"),
+    spec("左に白丸傍点", Style, "「語」の左に白丸傍点"),
+    spec("左に黒三角傍点", Style, "「語」の左に黒三角傍点"),
+    spec("左に白三角傍点", Style, "「語」の左に白三角傍点"),
+    spec("左に二重丸傍点", Style, "「語」の左に二重丸傍点"),
+    spec("左に蛇の目傍点", Style, "「語」の左に蛇の目傍点"),
+    spec("左にばつ傍点", Style, "「語」の左にばつ傍点"),
+    // 傍線（右・上）
+    spec("傍線", Style, "「語」に傍線"),
+    spec("二重傍線", Style, "「語」に二重傍線"),
+    spec("鎖線", Style, "「語」に鎖線"),
+    spec("破線", Style, "「語」に破線"),
+    spec("波線", Style, "「語」に波線"),
+    // 傍線（左・下）
+    spec("左に傍線", Style, "「語」の左に傍線"),
+    spec("左に二重傍線", Style, "「語」の左に二重傍線"),
+    spec("左に鎖線", Style, "「語」の左に鎖線"),
+    spec("左に破線", Style, "「語」の左に破線"),
+    spec("左に波線", Style, "「語」の左に波線"),
+    // 文字スタイル
+    spec("太字", Style, "「語」は太字"),
+    spec("斜体", Style, "「語」は斜体"),
+    spec("下付き小文字", Style, "「2」は下付き小文字"),
+    spec("上付き小文字", Style, "「2」は上付き小文字"),
+    spec("行右小書き", Style, "「ト」は行右小書き"),
+    spec("行左小書き", Style, "「ト」は行左小書き"),
+    spec("二倍角", Style, "「語」は二倍角"),
+    // 見出し
+    spec("大見出し", Midashi, "「第一章」は大見出し"),
+    spec("中見出し", Midashi, "「一」は中見出し"),
+    spec("小見出し", Midashi, "「上」は小見出し"),
+    spec("同行大見出し", Midashi, "「第一章」は同行大見出し"),
+    spec("同行中見出し", Midashi, "「一」は同行中見出し"),
+    spec("同行小見出し", Midashi, "「上」は同行小見出し"),
+    spec("窓大見出し", Midashi, "「第一章」は窓大見出し"),
+    spec("窓中見出し", Midashi, "「一」は窓中見出し"),
+    spec("窓小見出し", Midashi, "「上」は窓小見出し"),
+    // ブロック
+    spec("ここからＮ字下げ", Block, "ここから２字下げ"),
+    spec(
+        "ここからＮ字下げ、折り返してＭ字下げ",
+        Block,
+        "ここから１字下げ、折り返して３字下げ",
+    ),
+    spec(
+        "ここから改行天付き、折り返してＮ字下げ",
+        Block,
+        "ここから改行天付き、折り返して２字下げ",
+    ),
+    spec("ここから地付き", Block, "ここから地付き"),
+    spec("ここから地からＮ字上げ", Block, "ここから地から２字上げ"),
+    spec("ここから右寄せ", Block, "ここから右寄せ"),
+    spec("ここから中央寄せ", Block, "ここから中央寄せ"),
+    spec(
+        "ここからページの左右中央",
+        Block,
+        "ここからページの左右中央",
+    ),
+    spec("ここからＮ字詰め", Block, "ここから２０字詰め"),
+    spec("ここから罫囲み", Block, "ここから罫囲み"),
+    spec("ここから横組み", Block, "ここから横組み"),
+    spec("ここから太字", Block, "ここから太字"),
+    spec("ここから斜体", Block, "ここから斜体"),
+    spec(
+        "ここからＮ段階大きな文字",
+        Block,
+        "ここから１段階大きな文字",
+    ),
+    spec(
+        "ここからＮ段階小さな文字",
+        Block,
+        "ここから１段階小さな文字",
+    ),
+    spec("ここで…終わり", Block, "ここで字下げ終わり"),
+    spec("Ｎ字下げ", Block, "３字下げ"),
+    spec("地付き", Block, "地付き"),
+    spec("右寄せ", Block, "右寄せ"),
+    spec("地からＮ字上げ", Block, "地から２字上げ"),
+    // 行内の要素
+    spec("縦中横", Inline, "「10」は縦中横"),
+    spec("割り注", Inline, "割り注"),
+    spec("罫囲み", Inline, "「語」は罫囲み"),
+    spec("横組み", Inline, "「abc」は横組み"),
+    spec("キャプション", Inline, "「図1」はキャプション"),
+    spec("Ｎ段階大きな文字", Inline, "「語」は１段階大きな文字"),
+    spec("Ｎ段階小さな文字", Inline, "「語」は１段階小さな文字"),
+    spec("…終わり", Inline, "傍点終わり"),
+    spec("左ルビ", Inline, "「語」の左に「ご」のルビ"),
+    spec("注記", Inline, "「語」に「ママ」の注記"),
+    spec("注記付き", Inline, "注記付き"),
+    spec("左に注記付き", Inline, "左に注記付き"),
+    spec("注記付き終わり", Inline, "「ママ」の注記付き終わり"),
+    spec("傍記", Inline, "「語」に「×」の傍記"),
+    spec("改行", Inline, "改行"),
+    // その他
+    spec("返り点", Other, "レ"),
+    spec("訓点送り仮名", Other, "（ト）"),
+    spec("全角アキ", Other, "全角アキ"),
+    spec("二分アキ", Other, "二分アキ"),
+    spec("四分アキ", Other, "四分アキ"),
+    spec("全角ダッシュ", Other, "全角ダッシュ"),
+    spec("挿絵", Other, "挿絵（fig01.png、横320×縦240）入る"),
+    spec("Ｎページ", Other, "１２ページ"),
+    spec("丁付け", Other, "丁付け十二ウ"),
+];
+
+/// パーサーが対応している注記の一覧
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::{supported_commands, CommandCategory};
+///
+/// let bouten = supported_commands().iter().find(|c| c.name == "傍点").unwrap();
+/// assert_eq!(bouten.category, CommandCategory::Style);
+/// assert_eq!(bouten.example, "「語」に傍点");
+/// ```
+pub fn supported_commands() -> &'static [CommandSpec] {
+    SUPPORTED_COMMANDS
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::node::{BlockType, MidashiLevel, StyleType};
+    use crate::parser::command_parser::{parse_command, CommandResult};
+
+    #[test]
+    fn test_supported_commands_contains_common_commands() {
+        let names: Vec<&str> = supported_commands().iter().map(|c| c.name).collect();
+        assert!(!names.is_empty());
+        assert!(names.contains(&"傍点"));
+        assert!(names.contains(&"大見出し"));
+    }
+
+    #[test]
+    fn test_supported_command_examples_are_parsed() {
+        for command in supported_commands() {
+            let result = parse_command(command.example);
+            let matches_category = match command.category {
+                CommandCategory::Style => {
+                    StyleType::from_command(command.name).is_some()
+                        && matches!(result, CommandResult::Style { .. })
+                }
+                CommandCategory::Midashi => {
+                    MidashiLevel::from_command(command.name).is_some()
+                        && matches!(result, CommandResult::Midashi { .. })
+                }
+                CommandCategory::Block => matches!(
+                    result,
+                    CommandResult::BlockStart { .. }
+                        | CommandResult::BlockEnd { .. }
+                        | CommandResult::LineIndent { .. }
+                        | CommandResult::LineChitsuki { .. }
+                ),
+                CommandCategory::Inline | CommandCategory::Other => !matches!(
+                    result,
+                    CommandResult::Note(_)
+                        | CommandResult::Unknown(_)
+                        | CommandResult::BlockStart {
+                            block_type: BlockType::Jisage | BlockType::Chitsuki,
+                            ..
+                        }
+                ),
+            };
+            assert!(matches_category, "{command:?} parsed as {result:?}");
+        }
+    }
+}
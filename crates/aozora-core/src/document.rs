@@ -1,14 +1,18 @@
 //! 文書構造の処理
 
+use std::ops::Range;
+
 /// 文書セクションの種類
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SectionType {
-    /// ヘッダー（タイトル、著者名など）
-    Header,
-    /// ヘッダー直後の空行後
+    /// ヘッダーの直後
     AfterHeader,
     /// 注記セクション（---で囲まれた部分）
     Chuuki,
+    /// ---で囲まれていない注記セクション（【...について】から空行まで）
+    UnfencedChuuki,
+    /// 注記セクションの直後（空行の後に---が続けば、続けて置かれた注記セクション）
+    AfterChuuki,
     /// 本文
     Body,
 }
@@ -24,6 +28,8 @@ enum PersonType {
     Editor,
     /// 編訳者
     Henyaku,
+    /// 編者と翻訳者を兼ねる（`編・訳`・`訳編`）
+    EditorTranslator,
 }
 
 /// ヘッダー情報
@@ -58,7 +64,10 @@ impl HeaderInfo {
             parts.push(translator.clone());
         }
         if let Some(editor) = &self.editor {
-            parts.push(editor.clone());
+            // 編者と翻訳者を兼ねる場合は1回だけ出力
+            if self.translator.as_ref() != Some(editor) {
+                parts.push(editor.clone());
+            }
         }
         if let Some(henyaku) = &self.henyaku {
             parts.push(henyaku.clone());
@@ -79,22 +88,150 @@ impl HeaderInfo {
     }
 }
 
+/// 底本情報（奥付）
+///
+/// 各項目は行頭の見出し（`底本：` など）を除いた内容で、
+/// 字下げされた続きの行は改行でつないで同じ項目に含めます。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Colophon {
+    /// 底本
+    pub teihon: Option<String>,
+    /// 底本の親本
+    pub teihon_oyahon: Option<String>,
+    /// 初出
+    pub shoshutsu: Option<String>,
+    /// 入力者
+    pub nyuryoku: Option<String>,
+    /// 校正者
+    pub kousei: Option<String>,
+    /// 上記以外の行（作成日、注意書きなど）
+    pub others: Vec<String>,
+}
+
+/// 底本情報の項目の見出し
+const COLOPHON_PREFIXES: [&str; 5] = ["底本：", "底本の親本：", "初出：", "入力：", "校正："];
+
+impl Colophon {
+    /// 見出しに対応する項目を取得
+    fn field_mut(&mut self, index: usize) -> &mut Option<String> {
+        match index {
+            0 => &mut self.teihon,
+            1 => &mut self.teihon_oyahon,
+            2 => &mut self.shoshutsu,
+            3 => &mut self.nyuryoku,
+            _ => &mut self.kousei,
+        }
+    }
+}
+
+/// ヘッダーの終わりの判定方法
+///
+/// 青空文庫の文書はヘッダー（タイトル・著者名など）の後に空行を置きますが、
+/// 空行のない文書でも本文を取り出せるよう、空行より前でも次の場合はヘッダーを終えます。
+///
+/// - 記法（ルビ・注記）を含む行や `max_line_length` 文字を超える行があれば、その行から本文とする
+///   （1行目がそうであればヘッダーのない文書とする）
+/// - 空行がないまま `max_lines` 行を超えたら、1行目だけをヘッダーとする
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderOptions {
+    /// 空行がない場合にヘッダーとみなす最大行数
+    pub max_lines: usize,
+    /// ヘッダーの行とみなす最大文字数
+    pub max_line_length: usize,
+    /// 記法を含む行を本文とみなす
+    pub detect_markup: bool,
+}
+
+impl Default for HeaderOptions {
+    fn default() -> Self {
+        Self {
+            max_lines: 10,
+            max_line_length: 50,
+            detect_markup: true,
+        }
+    }
+}
+
+impl HeaderOptions {
+    /// 新しいオプションを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 空行だけでヘッダーの終わりを判定するオプションを作成
+    pub fn blank_line_only() -> Self {
+        Self {
+            max_lines: usize::MAX,
+            max_line_length: usize::MAX,
+            detect_markup: false,
+        }
+    }
+
+    /// 空行がない場合にヘッダーとみなす最大行数を設定
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// ヘッダーの行とみなす最大文字数を設定
+    pub fn with_max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// 記法を含む行を本文とみなすかどうかを設定
+    pub fn with_detect_markup(mut self, enabled: bool) -> Self {
+        self.detect_markup = enabled;
+        self
+    }
+
+    /// 本文の行に見えるかどうか
+    fn looks_like_body(&self, line: &str) -> bool {
+        (self.detect_markup && has_body_markup(line)) || line.chars().count() > self.max_line_length
+    }
+}
+
+/// ルビや注記を含むかどうか（外字の注記はタイトルにも使われるため除く）
+fn has_body_markup(line: &str) -> bool {
+    line.contains('《')
+        || line.contains('｜')
+        || line
+            .match_indices("［＃")
+            .any(|(pos, _)| !line[..pos].ends_with('※'))
+}
+
+/// ヘッダーの行数と、ヘッダーの後の行を調べ始める位置を返す
+fn split_header(lines: &[&str], options: &HeaderOptions) -> (usize, usize) {
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            return (i, i + 1);
+        }
+        if options.looks_like_body(line) {
+            return (i, i);
+        }
+        if i >= options.max_lines {
+            return (1, 1);
+        }
+    }
+    (lines.len(), lines.len())
+}
+
 /// ヘッダー行からヘッダー情報を抽出
 ///
 /// 青空文庫のヘッダー形式:
 /// - 1行目: タイトル
 /// - 2行目以降: 著者、副題、原題など（行数によって解釈が変わる）
+///
+/// ヘッダーの終わりは既定の [`HeaderOptions`] で判定します。
 pub fn extract_header_info(lines: &[&str]) -> HeaderInfo {
-    let mut info = HeaderInfo::default();
-    let mut header_lines = Vec::new();
+    extract_header_info_with_options(lines, &HeaderOptions::default())
+}
 
-    // 最初の空行までをヘッダーとして収集
-    for line in lines {
-        if line.is_empty() {
-            break;
-        }
-        header_lines.push(*line);
-    }
+/// ヘッダーの終わりの判定方法を指定してヘッダー情報を抽出
+pub fn extract_header_info_with_options(lines: &[&str], options: &HeaderOptions) -> HeaderInfo {
+    let mut info = HeaderInfo::default();
+    let (header_len, _) = split_header(lines, options);
+    let header_lines = &lines[..header_len];
 
     match header_lines.len() {
         0 => {}
@@ -103,7 +240,12 @@ pub fn extract_header_info(lines: &[&str]) -> HeaderInfo {
         }
         2 => {
             info.title = Some(header_lines[0].to_string());
-            process_person(header_lines[1], &mut info);
+            if !is_anonymous_author(header_lines[1]) && is_subtitle(header_lines[1]) {
+                // 作品名、副題（著者なし）
+                info.subtitle = Some(header_lines[1].to_string());
+            } else {
+                process_person(header_lines[1], &mut info);
+            }
         }
         3 => {
             info.title = Some(header_lines[0].to_string());
@@ -168,24 +310,99 @@ fn process_person(s: &str, info: &mut HeaderInfo) -> PersonType {
         PersonType::Editor => info.editor = Some(s.to_string()),
         PersonType::Translator => info.translator = Some(s.to_string()),
         PersonType::Henyaku => info.henyaku = Some(s.to_string()),
+        PersonType::EditorTranslator => {
+            info.editor = Some(s.to_string());
+            info.translator = Some(s.to_string());
+        }
         PersonType::Author => info.author = Some(s.to_string()),
     }
     person_type
 }
 
+/// 著者が不明であることを示す表記かどうか
+///
+/// `作者不詳`・`無名` などは人名ではないが、著者欄の値として扱う。
+fn is_anonymous_author(s: &str) -> bool {
+    const ANONYMOUS: [&str; 8] = [
+        "作者不詳",
+        "作者未詳",
+        "著者不詳",
+        "著者未詳",
+        "無名",
+        "無名氏",
+        "匿名",
+        "よみ人しらず",
+    ];
+    let s = s
+        .trim()
+        .trim_start_matches(['（', '('])
+        .trim_end_matches(['）', ')']);
+    ANONYMOUS.contains(&s)
+}
+
+/// 副題らしい表記かどうか
+///
+/// ダッシュや波線、括弧で始まる行（`――ある夜の出来事――`、`（上）` など）は
+/// 人名ではなく副題とみなす。
+fn is_subtitle(s: &str) -> bool {
+    s.trim_start().starts_with([
+        '―', '—', '─', '－', '〜', '～', '（', '(', '〔', '「', '『', '〈',
+    ])
+}
+
 /// 人物の種別を判定
+///
+/// 役割の接尾辞は `山田太郎訳` のほか、`山田太郎（訳）`・`山田太郎(訳)`・
+/// `山田太郎 訳`（半角/全角スペース区切り）の形式も受け付ける。
+/// `山田太郎編・訳`・`山田太郎訳編` は編者と翻訳者を兼ねるものとする。
 fn detect_person_type(s: &str) -> PersonType {
-    if s.ends_with("編訳") {
+    const EDITOR_TRANSLATOR: [&str; 9] = [
+        "編・訳",
+        "編･訳",
+        "編／訳",
+        "編/訳",
+        "訳・編",
+        "訳･編",
+        "訳／編",
+        "訳/編",
+        "訳編",
+    ];
+    let role = role_suffix(s);
+    if EDITOR_TRANSLATOR
+        .iter()
+        .any(|suffix| role.ends_with(suffix))
+    {
+        PersonType::EditorTranslator
+    } else if role.ends_with("編訳") {
         PersonType::Henyaku
-    } else if s.ends_with("校訂") || s.ends_with('編') || s.ends_with("編集") {
+    } else if role.ends_with("校訂") || role.ends_with('編') || role.ends_with("編集") {
         PersonType::Editor
-    } else if s.ends_with('訳') {
+    } else if role.ends_with('訳') {
         PersonType::Translator
     } else {
         PersonType::Author
     }
 }
 
+/// 人物名から役割の接尾辞部分を取り出す
+///
+/// 末尾の括弧（`（訳）`/`(訳)`）や空白区切り（` 訳`）を外した役割名を返す。
+/// いずれの形式でもない場合は元の文字列をそのまま返す。
+fn role_suffix(s: &str) -> &str {
+    let s = s.trim_end();
+    for (open, close) in [('（', '）'), ('(', ')')] {
+        if let Some(rest) = s.strip_suffix(close) {
+            if let Some(pos) = rest.rfind(open) {
+                return rest[pos + open.len_utf8()..].trim();
+            }
+        }
+    }
+    if let Some((_, role)) = s.rsplit_once([' ', '　']) {
+        return role;
+    }
+    s
+}
+
 /// 原題かどうかを判定
 ///
 /// 以下の文字のみで構成される場合に原題と判定:
@@ -220,7 +437,8 @@ fn is_original_title(s: &str) -> bool {
 ///
 /// # 文書構造
 /// - 前付け: 最初の空行まで（タイトル、著者名など）
-/// - 注記: 空行後、`---`で囲まれたセクション（【テキスト中に現れる記号について】など）
+/// - 注記: 空行後、`---`で囲まれたセクション（【テキスト中に現れる記号について】など）。
+///   `---`がない場合は、ヘッダー直後の`【...について】`の行（次の行が記号の説明のもの）から次の空行まで
 /// - 本文: 注記後から「底本：」まで
 /// - 後付け: 「底本：」以降（底本情報、入力者情報など）
 ///
@@ -238,49 +456,180 @@ fn is_original_title(s: &str) -> bool {
 /// assert_eq!(body, vec!["本文1行目"]);
 /// ```
 pub fn extract_body_lines<'a>(lines: &[&'a str]) -> Vec<&'a str> {
-    let mut result = Vec::new();
-    let mut section = SectionType::Header;
+    lines[body_line_range(lines)].to_vec()
+}
+
+/// ヘッダーの終わりの判定方法を指定して本文の行を抽出
+pub fn extract_body_lines_with_options<'a>(
+    lines: &[&'a str],
+    options: &HeaderOptions,
+) -> Vec<&'a str> {
+    lines[body_line_range_with_options(lines, options)].to_vec()
+}
+
+/// 文書中の本文行の範囲（行インデックス）を返す
+///
+/// 本文の判定は [`extract_body_lines`] と同じです。
+/// 元の行番号を保ったまま本文を処理したい場合に使用します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::document::body_line_range;
+///
+/// let lines = vec!["タイトル", "", "本文1行目", "本文2行目", "底本：〇〇文庫"];
+/// assert_eq!(body_line_range(&lines), 2..4);
+/// ```
+pub fn body_line_range(lines: &[&str]) -> Range<usize> {
+    body_line_range_with_options(lines, &HeaderOptions::default())
+}
 
-    for line in lines {
+/// ヘッダーの終わりの判定方法を指定して本文行の範囲を返す
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::document::{body_line_range_with_options, HeaderOptions};
+///
+/// // タイトルの直後に空行なしで本文が続く
+/// let lines = vec!["タイトル", "吾輩《わがはい》は猫である。", "底本：〇〇文庫"];
+/// assert_eq!(body_line_range_with_options(&lines, &HeaderOptions::default()), 1..2);
+/// assert_eq!(
+///     body_line_range_with_options(&lines, &HeaderOptions::blank_line_only()),
+///     3..3
+/// );
+/// ```
+pub fn body_line_range_with_options(lines: &[&str], options: &HeaderOptions) -> Range<usize> {
+    let mut start = None;
+    let (_, scan_start) = split_header(lines, options);
+    let mut end = lines.len();
+    let mut section = SectionType::AfterHeader;
+    // ---で囲まれていない注記セクションはヘッダー直後の1つだけ
+    let mut unfenced_chuuki_seen = false;
+
+    for (i, line) in lines.iter().enumerate().skip(scan_start) {
         match section {
-            SectionType::Header => {
-                // 空行でヘッダー終了
-                if line.is_empty() {
-                    section = SectionType::AfterHeader;
-                }
-            }
             SectionType::AfterHeader => {
                 // 空行後、---で始まれば注記セクション、そうでなければ本文
                 if line.starts_with("---") {
                     section = SectionType::Chuuki;
+                } else if !unfenced_chuuki_seen && is_unfenced_chuuki_start(lines, i) {
+                    unfenced_chuuki_seen = true;
+                    section = SectionType::UnfencedChuuki;
                 } else if line.is_empty() {
                     // 連続する空行はスキップ
                 } else {
                     // 本文開始
-                    if line.starts_with("底本：") {
+                    if is_colophon_start(lines, i) {
+                        end = i;
                         break;
                     }
-                    result.push(*line);
+                    start = Some(i);
                     section = SectionType::Body;
                 }
             }
             SectionType::Chuuki => {
                 // ---で注記セクション終了
                 if line.starts_with("---") {
-                    section = SectionType::Body;
+                    section = SectionType::AfterChuuki;
                 }
             }
-            SectionType::Body => {
+            SectionType::AfterChuuki if line.starts_with("---") => {
+                // 続けて置かれた注記セクション（間の空行も本文に含めない）
+                start = None;
+                section = SectionType::Chuuki;
+            }
+            SectionType::UnfencedChuuki => {
+                // 空行で注記セクション終了（続く空行はスキップ）
+                if line.is_empty() {
+                    section = SectionType::AfterHeader;
+                }
+            }
+            SectionType::AfterChuuki | SectionType::Body => {
                 // 底本：または［＃本文終わり］で本文終了
-                if line.starts_with("底本：") || *line == "［＃本文終わり］" {
+                if is_colophon_start(lines, i) || *line == "［＃本文終わり］" {
+                    end = i;
                     break;
                 }
-                result.push(*line);
+                start.get_or_insert(i);
+                if !line.is_empty() {
+                    section = SectionType::Body;
+                }
             }
         }
     }
 
-    result
+    match start {
+        Some(start) => start..end,
+        None => end..end,
+    }
+}
+
+/// 後付け（底本情報）の始まりの行かどうか
+///
+/// 行頭の `底本：` のうち、空行の直後にあるもの、または続く内容が
+/// 書名・版の表記らしい（句点を含まず、閉じ括弧で始まらない）ものを後付けの始まりとみなします。
+/// 本文中で `底本：` という語を引用している行で本文が途切れないようにするためです。
+fn is_colophon_start(lines: &[&str], index: usize) -> bool {
+    let Some(rest) = lines[index].strip_prefix("底本：") else {
+        return false;
+    };
+    if index == 0 || lines[index - 1].is_empty() {
+        return true;
+    }
+    !rest.is_empty() && !rest.contains('。') && !rest.starts_with(['」', '』'])
+}
+
+/// ---で囲まれていない注記セクションの始まりの行かどうか
+///
+/// ヘッダー直後の `【テキスト中に現れる記号について】` のような見出しで、
+/// 次の行が `《》：ルビ` のような記号の説明（`：` を含む行）であるものを対象とします。
+/// `【作品について】` のような見出しで始まる本文を注記セクションとして除かないためです。
+fn is_unfenced_chuuki_start(lines: &[&str], index: usize) -> bool {
+    let line = lines[index];
+    if !(line.starts_with('【') && line.ends_with("について】")) {
+        return false;
+    }
+    lines
+        .get(index + 1)
+        .is_some_and(|next| !next.starts_with('【') && next.contains('：'))
+}
+
+/// 巻ごとに分かれた文書を1つの文書に結合
+///
+/// 最初の巻のヘッダーと最後の巻の後付け（底本情報など）を残し、
+/// 各巻の本文を `separator` の行で区切ってつなげます。
+/// 2巻目以降のヘッダーと、最後の巻以外の後付けは除きます。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::document::join_volumes;
+///
+/// let joined = join_volumes(
+///     &["題名\n\n上巻の本文\n底本：上", "題名\n\n下巻の本文\n底本：下"],
+///     "",
+/// );
+/// assert_eq!(joined, "題名\n\n上巻の本文\n\n下巻の本文\n底本：下\n");
+/// ```
+pub fn join_volumes(volumes: &[&str], separator: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+
+    for (i, volume) in volumes.iter().enumerate() {
+        let lines: Vec<&str> = volume.lines().collect();
+        let range = body_line_range(&lines);
+        if i == 0 {
+            output.extend_from_slice(&lines[..range.end]);
+        } else {
+            output.push(separator);
+            output.extend_from_slice(&lines[range.clone()]);
+        }
+        if i == volumes.len() - 1 {
+            output.extend_from_slice(&lines[range.end..]);
+        }
+    }
+
+    output.iter().map(|line| format!("{line}\n")).collect()
 }
 
 /// 文書から本文終わり後のテキスト（after_text）を抽出
@@ -291,13 +640,13 @@ pub fn extract_after_text_lines<'a>(lines: &[&'a str]) -> Vec<&'a str> {
     let mut result = Vec::new();
     let mut in_after_text = false;
 
-    for line in lines {
+    for (i, line) in lines.iter().enumerate() {
         if *line == "［＃本文終わり］" {
             in_after_text = true;
             continue; // ［＃本文終わり］自体は含めない
         }
         if in_after_text {
-            if line.starts_with("底本：") {
+            if is_colophon_start(lines, i) {
                 break;
             }
             result.push(*line);
@@ -314,8 +663,8 @@ pub fn extract_bibliographical_lines<'a>(lines: &[&'a str]) -> Vec<&'a str> {
     let mut result = Vec::new();
     let mut in_biblio = false;
 
-    for line in lines {
-        if line.starts_with("底本：") {
+    for (i, line) in lines.iter().enumerate() {
+        if !in_biblio && is_colophon_start(lines, i) {
             in_biblio = true;
         }
         if in_biblio {
@@ -326,6 +675,67 @@ pub fn extract_bibliographical_lines<'a>(lines: &[&'a str]) -> Vec<&'a str> {
     result
 }
 
+/// 文書から底本情報を項目ごとに抽出
+///
+/// 「底本：」以降の行（[`extract_bibliographical_lines`]）を対象に、
+/// 底本・底本の親本・初出・入力・校正の各項目を取り出します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::document::extract_colophon;
+///
+/// let lines = vec!["本文", "底本：「全集」筑摩書房", "入力：山田", "校正：鈴木"];
+/// let colophon = extract_colophon(&lines);
+/// assert_eq!(colophon.teihon.as_deref(), Some("「全集」筑摩書房"));
+/// assert_eq!(colophon.nyuryoku.as_deref(), Some("山田"));
+/// assert_eq!(colophon.kousei.as_deref(), Some("鈴木"));
+/// ```
+pub fn extract_colophon(lines: &[&str]) -> Colophon {
+    let mut colophon = Colophon::default();
+    // 続きの行を追加する項目
+    let mut current: Option<usize> = None;
+
+    for line in extract_bibliographical_lines(lines) {
+        if line.is_empty() {
+            current = None;
+            continue;
+        }
+
+        if let Some((index, value)) = COLOPHON_PREFIXES
+            .iter()
+            .enumerate()
+            .find_map(|(i, prefix)| line.strip_prefix(prefix).map(|v| (i, v)))
+        {
+            let field = colophon.field_mut(index);
+            match field {
+                // 同じ項目が複数回ある場合は改行でつなぐ
+                Some(text) => {
+                    text.push('\n');
+                    text.push_str(value);
+                }
+                None => *field = Some(value.to_string()),
+            }
+            current = Some(index);
+            continue;
+        }
+
+        // 字下げされた行は直前の項目の続き
+        if line.starts_with(['　', ' ']) {
+            if let Some(text) = current.and_then(|i| colophon.field_mut(i).as_mut()) {
+                text.push('\n');
+                text.push_str(line.trim_start_matches(['　', ' ']));
+                continue;
+            }
+        }
+
+        current = None;
+        colophon.others.push(line.to_string());
+    }
+
+    colophon
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -409,6 +819,279 @@ mod tests {
         assert_eq!(body, vec!["本文"]);
     }
 
+    #[test]
+    fn test_consecutive_fenced_chuuki() {
+        let lines = vec![
+            "タイトル",
+            "",
+            "---",
+            "【テキスト中に現れる記号について】",
+            "《》：ルビ",
+            "---",
+            "---",
+            "【凡例】",
+            "---",
+            "",
+            "---",
+            "補足",
+            "---",
+            "",
+            "本文",
+            "---",
+            "底本：青空文庫",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(body, vec!["", "本文", "---"]);
+    }
+
+    #[test]
+    fn test_quoted_colophon_in_body() {
+        // 行頭の「底本：」でも、句点を含む文は本文の続き（以前はここで本文が途切れていた）
+        let lines = vec![
+            "タイトル",
+            "",
+            "彼は古い帳面を開いた。",
+            "底本：と書かれた紙を、彼は拾い上げた。",
+            "そこには何も書かれていなかった。",
+            "",
+            "底本：「作品集」青空書房",
+            "入力：山田",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(
+            body,
+            vec![
+                "彼は古い帳面を開いた。",
+                "底本：と書かれた紙を、彼は拾い上げた。",
+                "そこには何も書かれていなかった。",
+                "",
+            ]
+        );
+        assert_eq!(
+            extract_bibliographical_lines(&lines),
+            vec!["底本：「作品集」青空書房", "入力：山田"]
+        );
+
+        // 閉じ括弧が続く場合は会話文の引用
+        let lines = vec![
+            "タイトル",
+            "",
+            "「その本の",
+            "底本：」と言いかけて彼は黙った",
+        ];
+        assert_eq!(
+            extract_body_lines(&lines),
+            vec!["「その本の", "底本：」と言いかけて彼は黙った"]
+        );
+
+        // 空行のない底本情報は、書名・版の表記らしければ後付けとみなす
+        let lines = vec!["タイトル", "", "本文", "底本：「作品集」青空書房"];
+        assert_eq!(extract_body_lines(&lines), vec!["本文"]);
+    }
+
+    #[test]
+    fn test_unfenced_chuuki() {
+        let lines = vec![
+            "タイトル",
+            "著者名",
+            "",
+            "【テキスト中に現れる記号について】",
+            "《》：ルビ",
+            "［＃］：入力者注",
+            "",
+            "本文1行目",
+            "【本文中の記号】",
+            "底本：青空文庫",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(body, vec!["本文1行目", "【本文中の記号】"]);
+    }
+
+    #[test]
+    fn test_body_starting_with_brackets_is_kept() {
+        let lines = vec!["タイトル", "", "【第一章】", "本文", "", "底本：青空文庫"];
+        let body = extract_body_lines(&lines);
+        assert_eq!(body, vec!["【第一章】", "本文", ""]);
+
+        // 本文の途中の【...について】も本文として扱う
+        let lines = vec!["タイトル", "", "本文", "【記号について】", "", "続き"];
+        let body = extract_body_lines(&lines);
+        assert_eq!(body, vec!["本文", "【記号について】", "", "続き"]);
+    }
+
+    #[test]
+    fn test_body_starting_with_about_heading_is_kept() {
+        // 記号の説明が続かない【...について】は本文の見出し
+        let lines = vec![
+            "タイトル",
+            "",
+            "【作品について】",
+            "この作品は晩年に書かれた。",
+            "",
+            "底本：青空文庫",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(
+            body,
+            vec!["【作品について】", "この作品は晩年に書かれた。", ""]
+        );
+
+        // 注記セクションの後の【...について】も本文として扱う
+        let lines = vec![
+            "タイトル",
+            "",
+            "【テキスト中に現れる記号について】",
+            "《》：ルビ",
+            "",
+            "【人物について】",
+            "主人公：太郎",
+            "",
+            "底本：青空文庫",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(body, vec!["【人物について】", "主人公：太郎", ""]);
+    }
+
+    #[test]
+    fn test_headerless_document() {
+        // 1行目から記法を含む本文が始まる
+        let lines = vec![
+            "吾輩《わがはい》は猫である。",
+            "名前はまだ無い。",
+            "",
+            "底本：青空文庫",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(
+            body,
+            vec!["吾輩《わがはい》は猫である。", "名前はまだ無い。", ""]
+        );
+        assert_eq!(extract_header_info(&lines).title, None);
+    }
+
+    #[test]
+    fn test_header_without_blank_separator() {
+        let lines = vec![
+            "タイトル",
+            "著者名",
+            "［＃５字下げ］一［＃「一」は中見出し］",
+            "本文",
+            "",
+            "底本：青空文庫",
+        ];
+        let body = extract_body_lines(&lines);
+        assert_eq!(body[0], "［＃５字下げ］一［＃「一」は中見出し］");
+        let info = extract_header_info(&lines);
+        assert_eq!(info.title.as_deref(), Some("タイトル"));
+        assert_eq!(info.author.as_deref(), Some("著者名"));
+
+        // 長い行も本文とみなす
+        let long_line = "あ".repeat(60);
+        let lines = vec!["タイトル", long_line.as_str(), "本文"];
+        assert_eq!(body_line_range(&lines), 1..3);
+    }
+
+    #[test]
+    fn test_header_max_lines() {
+        let lines: Vec<&str> = vec!["タイトル", "一", "二", "三", "四"];
+        let options = HeaderOptions::default().with_max_lines(2);
+        assert_eq!(body_line_range_with_options(&lines, &options), 1..5);
+        let info = extract_header_info_with_options(&lines, &options);
+        assert_eq!(info.title.as_deref(), Some("タイトル"));
+        assert_eq!(info.author, None);
+    }
+
+    #[test]
+    fn test_header_gaiji_title_is_not_body() {
+        let lines = vec!["※［＃「てへん＋劣」、第3水準1-84-77］記", "", "本文"];
+        assert_eq!(body_line_range(&lines), 2..3);
+    }
+
+    #[test]
+    fn test_header_options_blank_line_only() {
+        let lines = vec!["タイトル", "吾輩《わがはい》は猫である。", "", "本文"];
+        let options = HeaderOptions::blank_line_only();
+        assert_eq!(
+            extract_body_lines_with_options(&lines, &options),
+            vec!["本文"]
+        );
+        let options = HeaderOptions::default().with_detect_markup(false);
+        assert_eq!(body_line_range_with_options(&lines, &options), 3..4);
+        let options = options.with_max_line_length(5);
+        assert_eq!(body_line_range_with_options(&lines, &options), 1..4);
+    }
+
+    #[test]
+    fn test_join_volumes() {
+        let first = "題名\n著者\n\n一の本文\n［＃本文終わり］\n上巻の後書き\n底本：上巻";
+        let second = "題名\n著者\n\n二の本文\n続き\n\n底本：下巻\n入力：山田";
+        let joined = join_volumes(&[first, second], "＊");
+        assert_eq!(
+            joined,
+            "題名\n著者\n\n一の本文\n＊\n二の本文\n続き\n\n底本：下巻\n入力：山田\n"
+        );
+
+        let lines: Vec<&str> = joined.lines().collect();
+        assert_eq!(extract_header_info(&lines).author.as_deref(), Some("著者"));
+        assert_eq!(
+            extract_body_lines(&lines),
+            vec!["一の本文", "＊", "二の本文", "続き", ""]
+        );
+        assert_eq!(join_volumes(&[first], ""), format!("{first}\n"));
+        assert_eq!(join_volumes(&[], ""), "");
+    }
+
+    #[test]
+    fn test_extract_colophon() {
+        let lines = vec![
+            "本文",
+            "",
+            "底本：「夏目漱石全集1」ちくま文庫、筑摩書房",
+            "　　　1987（昭和62）年9月29日第1刷発行",
+            "底本の親本：「筑摩全集類聚版　夏目漱石全集」筑摩書房",
+            "　　　1971（昭和46）年4月～1972（昭和47）年1月",
+            "初出：「ホトトギス」",
+            "入力：柴田卓治",
+            "校正：渡部峰子（一）、おのしげひこ（二）",
+            "1999年9月17日公開",
+            "青空文庫作成ファイル：",
+            "このファイルは、インターネットの図書館、青空文庫で作られました。",
+        ];
+        let colophon = extract_colophon(&lines);
+        assert_eq!(
+            colophon.teihon.as_deref(),
+            Some("「夏目漱石全集1」ちくま文庫、筑摩書房\n1987（昭和62）年9月29日第1刷発行")
+        );
+        assert_eq!(
+            colophon.teihon_oyahon.as_deref(),
+            Some("「筑摩全集類聚版　夏目漱石全集」筑摩書房\n1971（昭和46）年4月～1972（昭和47）年1月")
+        );
+        assert_eq!(colophon.shoshutsu.as_deref(), Some("「ホトトギス」"));
+        assert_eq!(colophon.nyuryoku.as_deref(), Some("柴田卓治"));
+        assert_eq!(
+            colophon.kousei.as_deref(),
+            Some("渡部峰子（一）、おのしげひこ（二）")
+        );
+        assert_eq!(
+            colophon.others,
+            vec![
+                "1999年9月17日公開",
+                "青空文庫作成ファイル：",
+                "このファイルは、インターネットの図書館、青空文庫で作られました。",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_colophon_missing_fields() {
+        let colophon = extract_colophon(&["本文"]);
+        assert_eq!(colophon, Colophon::default());
+
+        let colophon = extract_colophon(&["底本：「作品集」", "入力：山田", "入力：田中"]);
+        assert_eq!(colophon.nyuryoku.as_deref(), Some("山田\n田中"));
+        assert!(colophon.shoshutsu.is_none());
+    }
+
     // ヘッダー情報抽出テスト
 
     #[test]
@@ -427,6 +1110,35 @@ mod tests {
         assert_eq!(info.author, Some("著者名".to_string()));
     }
 
+    #[test]
+    fn test_extract_header_anonymous_author() {
+        for author in ["作者不詳", "無名", "（作者未詳）"] {
+            let lines = vec!["タイトル", author, ""];
+            let info = extract_header_info(&lines);
+            assert_eq!(info.title, Some("タイトル".to_string()));
+            assert_eq!(info.author, Some(author.to_string()));
+            assert_eq!(info.subtitle, None);
+        }
+    }
+
+    #[test]
+    fn test_extract_header_2lines_subtitle() {
+        let lines = vec!["タイトル", "――ある夜の出来事――", ""];
+        let info = extract_header_info(&lines);
+        assert_eq!(info.title, Some("タイトル".to_string()));
+        assert_eq!(info.subtitle, Some("――ある夜の出来事――".to_string()));
+        assert_eq!(info.author, None);
+    }
+
+    #[test]
+    fn test_extract_header_1line_only() {
+        let lines = vec!["作者不詳"];
+        let info = extract_header_info(&lines);
+        assert_eq!(info.title, Some("作者不詳".to_string()));
+        assert_eq!(info.author, None);
+        assert_eq!(info.subtitle, None);
+    }
+
     #[test]
     fn test_extract_header_2lines_translator() {
         let lines = vec!["タイトル", "山田太郎訳", ""];
@@ -540,6 +1252,49 @@ mod tests {
         assert_eq!(detect_person_type("山田太郎編訳"), PersonType::Henyaku);
     }
 
+    #[test]
+    fn test_detect_person_type_separated_suffix() {
+        assert_eq!(detect_person_type("山田太郎（訳）"), PersonType::Translator);
+        assert_eq!(detect_person_type("山田太郎(訳)"), PersonType::Translator);
+        assert_eq!(detect_person_type("山田太郎 訳"), PersonType::Translator);
+        assert_eq!(detect_person_type("山田太郎 編"), PersonType::Editor);
+        assert_eq!(detect_person_type("山田太郎　編集"), PersonType::Editor);
+        assert_eq!(detect_person_type("山田太郎（校訂）"), PersonType::Editor);
+        assert_eq!(detect_person_type("山田太郎 編訳"), PersonType::Henyaku);
+        assert_eq!(detect_person_type("山田太郎訳"), PersonType::Translator);
+        assert_eq!(detect_person_type("山田 太郎"), PersonType::Author);
+    }
+
+    #[test]
+    fn test_detect_person_type_editor_translator() {
+        assert_eq!(
+            detect_person_type("山田太郎編・訳"),
+            PersonType::EditorTranslator
+        );
+        assert_eq!(
+            detect_person_type("山田太郎訳編"),
+            PersonType::EditorTranslator
+        );
+        assert_eq!(
+            detect_person_type("山田太郎（編／訳）"),
+            PersonType::EditorTranslator
+        );
+        assert_eq!(detect_person_type("山田太郎編訳"), PersonType::Henyaku);
+    }
+
+    #[test]
+    fn test_extract_header_editor_translator() {
+        for person in ["山田太郎編・訳", "山田太郎訳編"] {
+            let lines = vec!["タイトル", "著者名", person, "", "本文"];
+            let info = extract_header_info(&lines);
+            assert_eq!(info.author.as_deref(), Some("著者名"));
+            assert_eq!(info.editor.as_deref(), Some(person));
+            assert_eq!(info.translator.as_deref(), Some(person));
+            assert_eq!(info.henyaku, None);
+            assert_eq!(info.html_title(), format!("著者名 {person} タイトル"));
+        }
+    }
+
     #[test]
     fn test_html_title() {
         let info = HeaderInfo {
@@ -2,12 +2,72 @@
 
 use encoding_rs::SHIFT_JIS;
 
+/// 判定されたエンコーディング
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedEncoding {
+    /// UTF-8
+    Utf8,
+    /// Shift_JIS
+    ShiftJis,
+}
+
+/// 出力エンコーディング
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputEncoding {
+    /// UTF-8
+    Utf8,
+    /// Shift_JIS（青空文庫のHTMLの既定）
+    #[default]
+    ShiftJis,
+}
+
+impl OutputEncoding {
+    /// XML宣言・metaタグで使うcharset名
+    pub fn charset(&self) -> &'static str {
+        match self {
+            OutputEncoding::Utf8 => "UTF-8",
+            OutputEncoding::ShiftJis => "Shift_JIS",
+        }
+    }
+}
+
+/// デコード結果の情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeInfo {
+    /// 判定されたエンコーディング
+    pub encoding: DetectedEncoding,
+    /// UTF-8 BOMがあったかどうか
+    pub has_bom: bool,
+    /// デコードできずに置換文字（U+FFFD）にしたバイト列の数
+    pub replacements: usize,
+    /// 最初に判定したエンコーディングでは不自然な文字が多かったため、
+    /// もう一方のエンコーディングでデコードし直した結果を採用したかどうか
+    pub fallback: bool,
+}
+
+impl DecodeInfo {
+    /// デコードで置換文字が生じたかどうか
+    pub fn had_errors(&self) -> bool {
+        self.replacements > 0
+    }
+}
+
 /// バイト列のエンコーディングを判定してUTF-8文字列に変換
 ///
 /// # 判定ロジック
 /// 1. UTF-8 BOMがあればUTF-8
 /// 2. UTF-8として妥当ならUTF-8
 /// 3. それ以外はShift_JIS
+/// 4. BOMがなく、UTF-8として妥当でも仮名を含まない場合は、Shift_JISとしてもデコードし、
+///    仮名を含み文字化けらしい文字（置換文字・C1制御文字・半角カタカナ）がなければShift_JISを採用
+/// 5. Shift_JISのデコード結果に文字化けらしい文字があれば、UTF-8としてもデコードし、
+///    そうした文字が少ない方を採用
+///
+/// ギリシャ文字やキリル文字などは文字化けとはみなさないため、
+/// 仮名を含むUTF-8の文書がShift_JISとして読み直されることはありません。
+///
+/// デコードできなかったバイト列は置換文字（U+FFFD）になります。
+/// 置換の有無を知る必要がある場合は [`decode_to_utf8_checked`] を使用してください。
 ///
 /// # Examples
 ///
@@ -18,21 +78,128 @@ use encoding_rs::SHIFT_JIS;
 /// assert_eq!(decode_to_utf8(utf8_bytes), "こんにちは");
 /// ```
 pub fn decode_to_utf8(bytes: &[u8]) -> String {
+    decode_to_utf8_checked(bytes).0
+}
+
+/// バイト列をUTF-8文字列に変換し、判定したエンコーディングなどの情報も返す
+///
+/// 判定ロジックは [`decode_to_utf8`] と同じです。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::encoding::{decode_to_utf8_checked, DetectedEncoding};
+///
+/// let (text, info) = decode_to_utf8_checked("こんにちは".as_bytes());
+/// assert_eq!(text, "こんにちは");
+/// assert_eq!(info.encoding, DetectedEncoding::Utf8);
+/// assert_eq!(info.replacements, 0);
+/// ```
+pub fn decode_to_utf8_checked(bytes: &[u8]) -> (String, DecodeInfo) {
     // BOMチェック
-    let bytes = if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+    let has_bom = bytes.starts_with(&[0xEF, 0xBB, 0xBF]);
+    let bytes = if has_bom {
         &bytes[3..] // BOMをスキップ
     } else {
         bytes
     };
 
     // UTF-8として妥当かチェック
-    if let Ok(s) = std::str::from_utf8(bytes) {
-        return s.to_owned();
+    let (text, encoding, fallback) = match std::str::from_utf8(bytes) {
+        Ok(s) => {
+            // 仮名のないUTF-8は、Shift_JISとして読むと明らかに日本語らしい場合だけ読み直す
+            let sjis_text = if has_bom || contains_kana(s) {
+                None
+            } else {
+                Some(decode_as(bytes, DetectedEncoding::ShiftJis))
+                    .filter(|t| contains_kana(t) && mojibake_score(t) == 0)
+            };
+            match sjis_text {
+                Some(t) => (t, DetectedEncoding::ShiftJis, true),
+                None => (s.to_owned(), DetectedEncoding::Utf8, false),
+            }
+        }
+        Err(_) => {
+            let text = decode_as(bytes, DetectedEncoding::ShiftJis);
+            let score = mojibake_score(&text);
+            // 文字化けらしい文字があれば、UTF-8（不正なバイトは置換）と比べる
+            let utf8_text = if score > 0 && !has_bom {
+                Some(decode_as(bytes, DetectedEncoding::Utf8)).filter(|t| mojibake_score(t) < score)
+            } else {
+                None
+            };
+            match utf8_text {
+                Some(t) => (t, DetectedEncoding::Utf8, true),
+                None => (text, DetectedEncoding::ShiftJis, false),
+            }
+        }
+    };
+
+    let info = DecodeInfo {
+        encoding,
+        has_bom,
+        replacements: count_replacements(&text),
+        fallback,
+    };
+    (text, info)
+}
+
+/// 指定したエンコーディングでデコード（不正なバイト列は置換文字にする）
+fn decode_as(bytes: &[u8], encoding: DetectedEncoding) -> String {
+    match encoding {
+        DetectedEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        DetectedEncoding::ShiftJis => SHIFT_JIS.decode(bytes).0.into_owned(),
     }
+}
+
+/// 置換文字（U+FFFD）の数
+///
+/// 妥当なUTF-8とShift_JISにはU+FFFDは現れないものとみなし、すべて置換によるものとして数えます。
+fn count_replacements(text: &str) -> usize {
+    text.chars()
+        .filter(|&c| c == char::REPLACEMENT_CHARACTER)
+        .count()
+}
 
-    // Shift_JISとしてデコード
-    let (cow, _, _) = SHIFT_JIS.decode(bytes);
-    cow.into_owned()
+/// 全角の仮名（ひらがな・カタカナ）を含むかどうか
+fn contains_kana(text: &str) -> bool {
+    text.chars().any(|c| ('\u{3041}'..='\u{30FF}').contains(&c))
+}
+
+/// 文字化けらしさ（置換文字・C1制御文字・半角カタカナの数）
+///
+/// 誤ったエンコーディングで読むと、UTF-8はShift_JISの半角カタカナの並びに、
+/// Shift_JISはUTF-8の置換文字やC1制御文字になりやすいため、これらを数えます。
+fn mojibake_score(text: &str) -> usize {
+    text.chars()
+        .filter(|&c| {
+            c == char::REPLACEMENT_CHARACTER
+                || ('\u{80}'..='\u{9F}').contains(&c)
+                || ('\u{FF61}'..='\u{FF9F}').contains(&c)
+        })
+        .count()
+}
+
+/// UTF-8文字列を指定されたエンコーディングのバイト列に変換
+///
+/// Shift_JISで表現できない文字は数値文字参照（`&#NNNN;`）になります。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::encoding::{encode_from_utf8, OutputEncoding};
+///
+/// assert_eq!(encode_from_utf8("あ", OutputEncoding::ShiftJis), vec![0x82, 0xA0]);
+/// assert_eq!(encode_from_utf8("😀", OutputEncoding::ShiftJis), b"&#128512;");
+/// ```
+pub fn encode_from_utf8(text: &str, encoding: OutputEncoding) -> Vec<u8> {
+    match encoding {
+        OutputEncoding::Utf8 => text.as_bytes().to_vec(),
+        OutputEncoding::ShiftJis => {
+            let (encoded, _, _) = SHIFT_JIS.encode(text);
+            encoded.into_owned()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -58,4 +225,123 @@ mod tests {
         let bytes = vec![0x82, 0xB1, 0x82, 0xF1, 0x82, 0xC9, 0x82, 0xBF, 0x82, 0xCD];
         assert_eq!(decode_to_utf8(&bytes), "こんにちは");
     }
+
+    #[test]
+    fn test_checked_utf8() {
+        let (text, info) = decode_to_utf8_checked("こんにちは".as_bytes());
+        assert_eq!(text, "こんにちは");
+        assert_eq!(info.encoding, DetectedEncoding::Utf8);
+        assert!(!info.has_bom);
+        assert_eq!(info.replacements, 0);
+    }
+
+    #[test]
+    fn test_checked_utf8_with_bom() {
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice("こんにちは".as_bytes());
+        let (text, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(text, "こんにちは");
+        assert!(info.has_bom);
+    }
+
+    #[test]
+    fn test_checked_shift_jis() {
+        let bytes = vec![0x82, 0xB1, 0x82, 0xF1, 0x82, 0xC9, 0x82, 0xBF, 0x82, 0xCD];
+        let (text, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(text, "こんにちは");
+        assert_eq!(info.encoding, DetectedEncoding::ShiftJis);
+        assert_eq!(info.replacements, 0);
+        assert!(!info.had_errors());
+    }
+
+    #[test]
+    fn test_checked_invalid_bytes() {
+        // "こ" に続けて Shift_JIS としても不正な2バイト文字
+        let bytes = vec![0x82, 0xB1, 0x85, 0x40, 0xFF];
+        let (_, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(info.encoding, DetectedEncoding::ShiftJis);
+        assert!(info.replacements > 0);
+        assert!(info.had_errors());
+        assert!(!info.fallback);
+    }
+
+    #[test]
+    fn test_checked_shift_jis_valid_as_utf8() {
+        // UTF-8としても妥当だが仮名を含まないShift_JIS
+        let bytes = encode_from_utf8("險シ讀ミ證ス", OutputEncoding::ShiftJis);
+        assert!(std::str::from_utf8(&bytes).is_ok());
+
+        let (text, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(text, "險シ讀ミ證ス");
+        assert_eq!(info.encoding, DetectedEncoding::ShiftJis);
+        assert!(info.fallback);
+        assert!(!info.had_errors());
+    }
+
+    #[test]
+    fn test_checked_no_fallback_for_halfwidth_kana_candidate() {
+        // Shift_JISとして読むと半角カタカナだけになる場合は読み直さない
+        let bytes = encode_from_utf8("ﾏｲﾅｽﾃｽﾄｰ", OutputEncoding::ShiftJis);
+        let (_, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(info.encoding, DetectedEncoding::Utf8);
+        assert!(!info.fallback);
+    }
+
+    #[test]
+    fn test_checked_no_fallback_for_cyrillic_and_greek_utf8() {
+        for text in [
+            "ラスコーリニコフ（Раскольников）は歩いた。",
+            "ギリシャ語のαβγδεは文字である。",
+            "Раскольников",
+            "αβγδε",
+        ] {
+            let (decoded, info) = decode_to_utf8_checked(text.as_bytes());
+            assert_eq!(decoded, text);
+            assert_eq!(info.encoding, DetectedEncoding::Utf8);
+            assert!(!info.fallback);
+        }
+    }
+
+    #[test]
+    fn test_checked_utf8_with_invalid_byte() {
+        // 不正なバイトが1つ混じったUTF-8はShift_JISとして読むより置換文字が少ない
+        let mut bytes = "吾輩《わがはい》は猫である。名前はまだ無い。"
+            .as_bytes()
+            .to_vec();
+        bytes.push(0xFF);
+
+        let (text, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(text, "吾輩《わがはい》は猫である。名前はまだ無い。\u{FFFD}");
+        assert_eq!(info.encoding, DetectedEncoding::Utf8);
+        assert!(info.fallback);
+        assert_eq!(info.replacements, 1);
+    }
+
+    #[test]
+    fn test_checked_no_fallback_for_accented_utf8() {
+        let (text, info) = decode_to_utf8_checked("カフェ（café）".as_bytes());
+        assert_eq!(text, "カフェ（café）");
+        assert_eq!(info.encoding, DetectedEncoding::Utf8);
+        assert!(!info.fallback);
+    }
+
+    #[test]
+    fn test_encode_shift_jis_round_trip() {
+        let text = "<p>吾輩《わがはい》は猫である。</p>\r\n";
+        let bytes = encode_from_utf8(text, OutputEncoding::ShiftJis);
+        assert!(std::str::from_utf8(&bytes).is_err());
+        let (decoded, info) = decode_to_utf8_checked(&bytes);
+        assert_eq!(decoded, text);
+        assert_eq!(info.encoding, DetectedEncoding::ShiftJis);
+    }
+
+    #[test]
+    fn test_encode_unmappable_as_entity() {
+        let bytes = encode_from_utf8("鷗外", OutputEncoding::ShiftJis);
+        assert_eq!(decode_to_utf8(&bytes), "&#40407;外");
+        assert_eq!(
+            encode_from_utf8("鷗外", OutputEncoding::Utf8),
+            "鷗外".as_bytes()
+        );
+    }
 }
@@ -1,13 +1,17 @@
 //! 外字（JIS外文字）の変換
 
+use crate::char_type::CharType;
 use crate::jis_table::{jis_to_unicode, normalize_jis_code};
+use crate::kana::compose_voiced_kana;
 
 /// 外字説明からUnicode文字列に変換
 ///
 /// # 変換優先順位
 /// 1. Unicode直接指定 (U+XXXX)
 /// 2. JISコード指定 (X-XX-XX) → テーブル参照
-/// 3. 変換不能 → 〓（ゲタ記号）
+/// 3. 文字名（「アキュートアクセント付きE小文字」など） → 文字名テーブル参照
+/// 4. 濁点・半濁点付きの仮名（「濁点付き片仮名ヰ」など） → 仮名と濁点・半濁点を合成
+/// 5. 変換不能 → 〓（ゲタ記号）
 ///
 /// # Examples
 ///
@@ -17,20 +21,42 @@ use crate::jis_table::{jis_to_unicode, normalize_jis_code};
 /// assert_eq!(convert_gaiji("「丸印」、U+25CB"), "○");
 /// ```
 pub fn convert_gaiji(description: &str) -> String {
+    // 変換不能の場合はゲタ記号
+    try_convert_gaiji(description).unwrap_or_else(|| "〓".to_string())
+}
+
+/// 外字説明からUnicode文字列に変換（変換できない場合は `None`）
+///
+/// 変換の優先順位は [`convert_gaiji`] と同じです。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::gaiji::try_convert_gaiji;
+///
+/// assert_eq!(try_convert_gaiji("「丸印」、U+25CB").as_deref(), Some("○"));
+/// assert_eq!(try_convert_gaiji("「てへん＋劣」、120-8"), None);
+/// ```
+pub fn try_convert_gaiji(description: &str) -> Option<String> {
     // 1. Unicode直接指定を探す
     if let Some(unicode_char) = extract_unicode(description) {
-        return unicode_char.to_string();
+        return Some(unicode_char.to_string());
     }
 
     // 2. JISコードを探す
     if let Some(jis_code) = extract_jis_code(description) {
         if let Some(unicode) = jis_to_unicode(&jis_code) {
-            return unicode;
+            return Some(unicode);
         }
     }
 
-    // 3. 変換不能
-    "〓".to_string()
+    // 3. 文字名を探す
+    if let Some(unicode) = lookup_gaiji_name(description) {
+        return Some(unicode.to_string());
+    }
+
+    // 4. 濁点・半濁点付きの仮名
+    lookup_voiced_kana(description)
 }
 
 /// 外字変換の結果
@@ -55,16 +81,31 @@ pub enum GaijiResult {
 }
 
 /// 外字説明を解析して結果を返す（HTML変換用）
+///
+/// JISコードとUnicode直接指定（U+XXXX）の両方がある場合は、
+/// 画像に使うJISコードとUnicode直接指定の文字の組（[`GaijiResult::JisConverted`]）を返します。
+/// どちらで出力するかは呼び出し側のオプションで決めます。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::gaiji::{parse_gaiji, GaijiResult};
+///
+/// assert_eq!(
+///     parse_gaiji("「二の字点」、1-2-22、U+303B"),
+///     GaijiResult::JisConverted {
+///         jis_code: "1-02-22".to_string(),
+///         unicode: "〻".to_string(),
+///     }
+/// );
+/// ```
 pub fn parse_gaiji(description: &str) -> GaijiResult {
-    // 1. Unicode直接指定を探す
-    if let Some(unicode_char) = extract_unicode(description) {
-        return GaijiResult::Unicode(unicode_char.to_string());
-    }
+    let explicit_unicode = extract_unicode(description).map(|c| c.to_string());
 
-    // 2. JISコードを探す
+    // 1. JISコードを探す（Unicode直接指定があればその文字を優先）
     if let Some(jis_code) = extract_jis_code(description) {
         let normalized = normalize_jis_code(&jis_code);
-        if let Some(unicode) = jis_to_unicode(&normalized) {
+        if let Some(unicode) = explicit_unicode.or_else(|| jis_to_unicode(&normalized)) {
             return GaijiResult::JisConverted {
                 jis_code: normalized,
                 unicode,
@@ -75,10 +116,106 @@ pub fn parse_gaiji(description: &str) -> GaijiResult {
         };
     }
 
-    // 3. 変換不能
+    // 2. Unicode直接指定を探す
+    if let Some(unicode) = explicit_unicode {
+        return GaijiResult::Unicode(unicode);
+    }
+
+    // 3. 文字名を探す
+    if let Some(unicode) = lookup_gaiji_name(description) {
+        return GaijiResult::Unicode(unicode.to_string());
+    }
+
+    // 4. 濁点・半濁点付きの仮名
+    if let Some(unicode) = lookup_voiced_kana(description) {
+        return GaijiResult::Unicode(unicode);
+    }
+
+    // 5. 変換不能
     GaijiResult::Unconvertible
 }
 
+/// 文字名とUnicode文字の対応表
+///
+/// コードポイントやJISコードを伴わずに文字名だけで外字を説明している場合に使用します。
+/// 青空文庫の入力者が用いる日本語の文字名と、Unicodeの文字名の両方を収録しています。
+const GAIJI_NAME_TABLE: &[(&str, &str)] = &[
+    ("アキュートアクセント付きA小文字", "\u{E1}"),
+    ("アキュートアクセント付きE小文字", "\u{E9}"),
+    ("アキュートアクセント付きI小文字", "\u{ED}"),
+    ("アキュートアクセント付きO小文字", "\u{F3}"),
+    ("アキュートアクセント付きU小文字", "\u{FA}"),
+    ("グレーブアクセント付きA小文字", "\u{E0}"),
+    ("グレーブアクセント付きE小文字", "\u{E8}"),
+    ("サーカムフレックスアクセント付きE小文字", "\u{EA}"),
+    ("ウムラウト付きA小文字", "\u{E4}"),
+    ("ウムラウト付きO小文字", "\u{F6}"),
+    ("ウムラウト付きU小文字", "\u{FC}"),
+    ("セディラ付きC小文字", "\u{E7}"),
+    ("ドイツ語エスツェット", "\u{DF}"),
+    ("LATIN SMALL LETTER A WITH ACUTE", "\u{E1}"),
+    ("LATIN SMALL LETTER E WITH ACUTE", "\u{E9}"),
+    ("LATIN SMALL LETTER A WITH GRAVE", "\u{E0}"),
+    ("LATIN SMALL LETTER E WITH GRAVE", "\u{E8}"),
+    ("LATIN SMALL LETTER A WITH DIAERESIS", "\u{E4}"),
+    ("LATIN SMALL LETTER O WITH DIAERESIS", "\u{F6}"),
+    ("LATIN SMALL LETTER U WITH DIAERESIS", "\u{FC}"),
+    ("LATIN SMALL LETTER C WITH CEDILLA", "\u{E7}"),
+    ("LATIN SMALL LETTER SHARP S", "\u{DF}"),
+    ("LATIN SMALL LETTER AE", "\u{E6}"),
+    ("LATIN SMALL LIGATURE OE", "\u{153}"),
+    ("GREEK SMALL LETTER ALPHA", "\u{3B1}"),
+    ("GREEK SMALL LETTER BETA", "\u{3B2}"),
+    ("WHITE CIRCLE", "\u{25CB}"),
+    ("BLACK CIRCLE", "\u{25CF}"),
+    ("WHITE STAR", "\u{2606}"),
+    ("BLACK STAR", "\u{2605}"),
+    ("DAGGER", "\u{2020}"),
+    ("DOUBLE DAGGER", "\u{2021}"),
+];
+
+/// 濁点・半濁点付きの仮名の外字を合成
+///
+/// `濁点付き片仮名ワ`・`半濁点付き平仮名か` のような説明から、仮名と濁点・半濁点を
+/// 組み合わせた文字列を作ります（[`compose_voiced_kana`]）。
+/// JISコード付きの説明（`濁点付き片仮名ワ、1-07-82` 〜 `濁点付き片仮名ヲ、1-07-85` など）は
+/// この前にJISコードから変換されます。
+fn lookup_voiced_kana(description: &str) -> Option<String> {
+    description
+        .split('、')
+        .map(|part| part.trim().trim_start_matches('「').trim_end_matches('」'))
+        .find_map(|part| {
+            let (rest, handakuten) = match part.strip_prefix("半濁点付き") {
+                Some(rest) => (rest, true),
+                None => (part.strip_prefix("濁点付き")?, false),
+            };
+            let (kana, char_type) = match rest.strip_prefix("片仮名") {
+                Some(kana) => (kana, CharType::Katakana),
+                None => (rest.strip_prefix("平仮名")?, CharType::Hiragana),
+            };
+            let mut chars = kana.chars();
+            let c = chars.next()?;
+            (chars.next().is_none() && CharType::classify(c) == char_type)
+                .then(|| compose_voiced_kana(c, handakuten))
+        })
+}
+
+/// 文字名から外字を探す
+///
+/// 外字説明を「、」で区切った各部分（「」は除く）を文字名テーブルと照合します。
+/// Unicodeの文字名は大文字・小文字を区別しません。
+fn lookup_gaiji_name(description: &str) -> Option<&'static str> {
+    description
+        .split('、')
+        .map(|part| part.trim().trim_start_matches('「').trim_end_matches('」'))
+        .find_map(|part| {
+            GAIJI_NAME_TABLE
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))
+                .map(|&(_, unicode)| unicode)
+        })
+}
+
 /// "U+XXXX" パターンからUnicode文字を抽出
 fn extract_unicode(description: &str) -> Option<char> {
     // "U+XXXX" または "u+XXXX" を探す
@@ -192,6 +329,25 @@ mod tests {
         assert_eq!(convert_gaiji("「丸印」、U+25CB"), "○");
     }
 
+    #[test]
+    fn test_convert_gaiji_voiced_kana() {
+        // JISコード付き（1-07-82〜1-07-85）はJISコードから変換
+        assert_eq!(convert_gaiji("濁点付き片仮名ワ、1-07-82"), "ヷ");
+        assert_eq!(convert_gaiji("濁点付き片仮名ヰ、1-07-83"), "ヸ");
+        assert_eq!(convert_gaiji("濁点付き片仮名ヱ、1-07-84"), "ヹ");
+        assert_eq!(convert_gaiji("濁点付き片仮名ヲ、1-07-85"), "ヺ");
+        // 説明だけの場合は合成
+        assert_eq!(convert_gaiji("濁点付き片仮名ワ"), "ヷ");
+        assert_eq!(convert_gaiji("「濁点付き片仮名ヲ」"), "ヺ");
+        assert_eq!(convert_gaiji("半濁点付き片仮名カ"), "カ\u{309A}");
+        assert_eq!(convert_gaiji("濁点付き平仮名う"), "ゔ");
+        assert_eq!(convert_gaiji("濁点付き片仮名ア"), "ア\u{3099}");
+        assert_eq!(convert_gaiji("半濁点付き平仮名か"), "か\u{309A}");
+        // 片仮名・平仮名の指定と文字が合わないものは変換しない
+        assert_eq!(convert_gaiji("濁点付き平仮名ア"), "〓");
+        assert_eq!(convert_gaiji("濁点付き片仮名アイ"), "〓");
+    }
+
     #[test]
     fn test_convert_gaiji_unknown() {
         assert_eq!(convert_gaiji("不明な外字"), "〓");
@@ -234,4 +390,65 @@ mod tests {
             _ => panic!("Expected JisConverted"),
         }
     }
+
+    #[test]
+    fn test_parse_gaiji_jis_and_unicode() {
+        // 両方ある場合はJISコードとUnicode直接指定の文字を組にする
+        assert_eq!(
+            parse_gaiji("「文字」、1-2-22、U+303B"),
+            GaijiResult::JisConverted {
+                jis_code: "1-02-22".to_string(),
+                unicode: "〻".to_string(),
+            }
+        );
+        // 表にないJISコードでもUnicode直接指定の文字を使う
+        assert_eq!(
+            parse_gaiji("「架空の字」、1-95-01、U+25CB"),
+            GaijiResult::JisConverted {
+                jis_code: "1-95-01".to_string(),
+                unicode: "○".to_string(),
+            }
+        );
+        // Unicode変換は従来どおりU+を優先
+        assert_eq!(convert_gaiji("「架空の字」、1-95-01、U+25CB"), "○");
+    }
+
+    #[test]
+    fn test_parse_gaiji_by_name() {
+        assert_eq!(
+            parse_gaiji("アキュートアクセント付きE小文字"),
+            GaijiResult::Unicode("é".to_string())
+        );
+        assert_eq!(
+            parse_gaiji("「ウムラウト付きU小文字」、ドイツ語"),
+            GaijiResult::Unicode("ü".to_string())
+        );
+        assert_eq!(
+            parse_gaiji("Latin Small Letter Sharp S"),
+            GaijiResult::Unicode("ß".to_string())
+        );
+        assert_eq!(convert_gaiji("WHITE STAR"), "☆");
+    }
+
+    #[test]
+    fn test_parse_gaiji_name_lower_priority() {
+        // コードポイント・JISコードの指定が優先される
+        assert_eq!(
+            parse_gaiji("WHITE STAR、U+2605"),
+            GaijiResult::Unicode("★".to_string())
+        );
+        assert!(matches!(
+            parse_gaiji("アキュートアクセント付きE小文字、1-09-63"),
+            GaijiResult::JisConverted { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_gaiji_unknown_name() {
+        assert_eq!(parse_gaiji("不明な外字"), GaijiResult::Unconvertible);
+        assert_eq!(
+            parse_gaiji("LATIN SMALL LETTER UNKNOWN"),
+            GaijiResult::Unconvertible
+        );
+    }
 }
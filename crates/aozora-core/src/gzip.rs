@@ -0,0 +1,67 @@
+//! gzip ファイル処理
+//!
+//! gzip 圧縮されたテキスト（`.txt.gz` など）を展開する機能を提供します。
+
+use std::io::{self, Read};
+
+use flate2::read::MultiGzDecoder;
+
+/// バイト列が gzip 形式かどうかをマジックバイトで判定
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::gzip::is_gzip_file;
+///
+/// assert!(is_gzip_file(b"\x1f\x8b\x08\x00"));
+/// assert!(!is_gzip_file(b"not gzip"));
+/// ```
+pub fn is_gzip_file(bytes: &[u8]) -> bool {
+    // gzipマジックバイト: 1F 8B
+    bytes.starts_with(&[0x1f, 0x8b])
+}
+
+/// gzip 圧縮されたバイト列を展開
+///
+/// 複数メンバーを連結した gzip にも対応します。
+pub fn decompress_gzip(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = MultiGzDecoder::new(bytes);
+    let mut out = Vec::new();
+    decoder
+        .read_to_end(&mut out)
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to decompress gzip input: {e}")))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_is_gzip_file() {
+        assert!(is_gzip_file(&[0x1f, 0x8b]));
+        assert!(!is_gzip_file(&[0x1f]));
+        assert!(!is_gzip_file(b""));
+        assert!(!is_gzip_file(b"PK\x03\x04"));
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all("吾輩《わがはい》は猫である".as_bytes())
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let out = decompress_gzip(&compressed).unwrap();
+        assert_eq!(out, "吾輩《わがはい》は猫である".as_bytes());
+    }
+
+    #[test]
+    fn test_decompress_invalid_gzip() {
+        assert!(decompress_gzip(&[0x1f, 0x8b, 0x00]).is_err());
+    }
+}
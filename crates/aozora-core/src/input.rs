@@ -0,0 +1,172 @@
+//! 入力ファイルの読み込み
+//!
+//! 通常のテキスト、ZIP、gzip の各形式の入力をバイト列として読み込みます。
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::document::join_volumes;
+use crate::encoding::decode_to_utf8;
+use crate::gzip::{decompress_gzip, is_gzip_file};
+use crate::zip::{is_zip_file, read_all_txt_from_zip, read_first_txt_from_zip};
+
+/// 入力を読み込んでバイト列を返す
+///
+/// - `zip` が指定された場合は ZIP ファイル内の最初の `.txt` を読み込みます（`path` 必須）
+/// - `path` が `None` の場合は標準入力から読み込みます
+/// - gzip 形式（マジックバイト `1F 8B` または拡張子 `.gz`）の場合は展開します
+/// - `zip` を指定せずに ZIP ファイルを渡した場合はエラーを返します
+///
+/// # Examples
+///
+/// ```no_run
+/// use aozora_core::input::read_input;
+/// use std::path::Path;
+///
+/// let bytes = read_input(Some(Path::new("example.txt.gz")), false).unwrap();
+/// ```
+pub fn read_input(path: Option<&Path>, zip: bool) -> io::Result<Vec<u8>> {
+    if zip {
+        let path = path.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ZIP mode requires an input file",
+            )
+        })?;
+        return read_first_txt_from_zip(path);
+    }
+
+    let bytes = match path {
+        Some(path) => fs::read(path)?,
+        None => {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf)?;
+            buf
+        }
+    };
+
+    // ZIPファイルの誤用を検出
+    if is_zip_file(&bytes) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "input appears to be a ZIP file; use --zip option",
+        ));
+    }
+
+    let has_gz_extension = path
+        .and_then(Path::extension)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+    if is_gzip_file(&bytes) || has_gz_extension {
+        return decompress_gzip(&bytes);
+    }
+
+    Ok(bytes)
+}
+
+/// ZIP ファイル内のすべての .txt ファイルを1つの文書として読み込む
+///
+/// 各ファイルをエントリ名の順に読み込み、[`join_volumes`] で空行を区切りとして結合した
+/// UTF-8 のバイト列を返します。
+pub fn read_zip_volumes(path: &Path) -> io::Result<Vec<u8>> {
+    let volumes: Vec<String> = read_all_txt_from_zip(path)?
+        .iter()
+        .map(|bytes| decode_to_utf8(bytes))
+        .collect();
+    let volumes: Vec<&str> = volumes.iter().map(String::as_str).collect();
+    Ok(join_volumes(&volumes, "").into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::extract_body_lines;
+    use crate::encoding::decode_to_utf8;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_read_gzip_matches_plain() {
+        let text =
+            "吾輩は猫である\n夏目漱石\n\n吾輩《わがはい》は猫である。\n\n底本：「夏目漱石全集」";
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let plain_path = dir.join(format!("aozora-input-{id}.txt"));
+        let gz_path = dir.join(format!("aozora-input-{id}.txt.gz"));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        fs::write(&gz_path, encoder.finish().unwrap()).unwrap();
+        fs::write(&plain_path, text).unwrap();
+
+        let plain = read_input(Some(&plain_path), false).unwrap();
+        let gz = read_input(Some(&gz_path), false).unwrap();
+        assert_eq!(gz, plain);
+
+        let plain_text = decode_to_utf8(&plain);
+        let gz_text = decode_to_utf8(&gz);
+        let plain_lines: Vec<&str> = plain_text.lines().collect();
+        let gz_lines: Vec<&str> = gz_text.lines().collect();
+        assert_eq!(
+            extract_body_lines(&gz_lines),
+            extract_body_lines(&plain_lines)
+        );
+
+        fs::remove_file(&plain_path).unwrap();
+        fs::remove_file(&gz_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_zip_without_flag() {
+        let path = std::env::temp_dir().join(format!("aozora-input-{}.bin", std::process::id()));
+        fs::write(&path, b"PK\x03\x04dummy").unwrap();
+
+        let err = read_input(Some(&path), false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_zip_volumes() {
+        use zip::write::SimpleFileOptions;
+
+        let path = std::env::temp_dir().join(format!("aozora-volumes-{}.zip", std::process::id()));
+        let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        // エントリ名の順に結合される（格納順ではない）
+        for (name, text) in [
+            ("work_2.txt", "題名\n\n下巻の本文\n底本：下巻"),
+            ("readme.html", "<p>ignored</p>"),
+            ("work_1.txt", "題名\n\n上巻の本文\n底本：上巻"),
+        ] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer
+                .write_all(&crate::encoding::encode_from_utf8(
+                    text,
+                    crate::encoding::OutputEncoding::ShiftJis,
+                ))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        let bytes = read_zip_volumes(&path).unwrap();
+        let text = decode_to_utf8(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            extract_body_lines(&lines),
+            vec!["上巻の本文", "", "下巻の本文"]
+        );
+        assert!(text.ends_with("底本：下巻\n"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_zip_mode_requires_path() {
+        let err = read_input(None, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
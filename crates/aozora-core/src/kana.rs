@@ -0,0 +1,221 @@
+//! 半角カタカナの正規化
+//!
+//! Shift_JIS の入力に含まれる半角カタカナ（ｱｲｳ）を全角カタカナに変換します。
+//! NFKC 正規化とは異なり、全角英数字などの他の文字は変更しません。
+
+use std::borrow::Cow;
+
+/// 半角カタカナ（U+FF61〜U+FF9F）に対応する全角文字
+const FULLWIDTH_KANA: [char; 63] = [
+    '。', '「', '」', '、', '・', 'ヲ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ッ', 'ー',
+    'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ', 'タ',
+    'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ', 'マ', 'ミ',
+    'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン', '゛', '゜',
+];
+
+/// 半角の濁点
+const HALFWIDTH_DAKUTEN: char = '\u{FF9E}';
+/// 半角の半濁点
+const HALFWIDTH_HANDAKUTEN: char = '\u{FF9F}';
+
+/// 半角カタカナかどうかを判定
+pub fn is_halfwidth_kana(ch: char) -> bool {
+    ('\u{FF61}'..='\u{FF9F}').contains(&ch)
+}
+
+/// 半角カタカナを全角に変換
+///
+/// 後続の半角濁点・半濁点は直前の文字と合成します（ｶﾞ → ガ、ﾊﾟ → パ）。
+/// 半角カタカナを含まない場合は入力をそのまま返します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::kana::normalize_halfwidth_kana;
+///
+/// assert_eq!(normalize_halfwidth_kana("ｶﾀｶﾅ"), "カタカナ");
+/// assert_eq!(normalize_halfwidth_kana("ｶﾞｯﾊﾟ"), "ガッパ");
+/// assert_eq!(normalize_halfwidth_kana("ＡＢＣ"), "ＡＢＣ");
+/// ```
+pub fn normalize_halfwidth_kana(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_halfwidth_kana) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if !is_halfwidth_kana(ch) {
+            output.push(ch);
+            continue;
+        }
+
+        let kana = FULLWIDTH_KANA[(ch as u32 - 0xFF61) as usize];
+        let combined = match chars.peek() {
+            Some(&HALFWIDTH_DAKUTEN) => with_dakuten(kana),
+            Some(&HALFWIDTH_HANDAKUTEN) => with_handakuten(kana),
+            _ => None,
+        };
+
+        match combined {
+            Some(combined) => {
+                chars.next();
+                output.push(combined);
+            }
+            None => output.push(kana),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// 仮名に濁点（`handakuten` なら半濁点）を付けた文字列を返す
+///
+/// 合成済みの文字（`ガ`、`ヷ`、`ぷ` など）があればそれを使い、
+/// なければ結合用の濁点（U+3099）・半濁点（U+309A）を後ろに付けます。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::kana::compose_voiced_kana;
+///
+/// assert_eq!(compose_voiced_kana('ヰ', false), "ヸ");
+/// assert_eq!(compose_voiced_kana('カ', true), "カ\u{309A}");
+/// ```
+pub fn compose_voiced_kana(kana: char, handakuten: bool) -> String {
+    let (composed, mark) = if handakuten {
+        (with_handakuten(kana), '\u{309A}')
+    } else {
+        (with_dakuten(kana), '\u{3099}')
+    };
+    match composed {
+        Some(c) => c.to_string(),
+        None => [kana, mark].iter().collect(),
+    }
+}
+
+/// 仮名と結合用の濁点（U+3099）・半濁点（U+309A）の組を合成済みの文字にまとめる
+///
+/// 対象は清音の仮名（ひらがな・カタカナ、`ゝ`・`ヽ`、`ワ`・`ヰ`・`ヱ`・`ヲ`）と
+/// 濁点・半濁点の組だけで、合成済みの文字がない組（`カ゚` など）はそのまま残します。
+/// それ以外の結合文字（アクセント記号など）は合成しないため、NFC正規化とは異なります。
+/// 結合用の濁点・半濁点を含まない場合は入力をそのまま返します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::kana::compose_voiced_marks;
+///
+/// assert_eq!(compose_voiced_marks("ハ\u{309A}ン"), "パン");
+/// assert_eq!(compose_voiced_marks("カ\u{309A}"), "カ\u{309A}");
+/// ```
+pub fn compose_voiced_marks(text: &str) -> Cow<'_, str> {
+    if !text.contains(['\u{3099}', '\u{309A}']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        let combined = match chars.peek() {
+            Some('\u{3099}') => with_dakuten(ch),
+            Some('\u{309A}') => with_handakuten(ch),
+            _ => None,
+        };
+        match combined {
+            Some(combined) => {
+                chars.next();
+                output.push(combined);
+            }
+            None => output.push(ch),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// 濁点付きの文字を取得
+fn with_dakuten(kana: char) -> Option<char> {
+    match kana {
+        'ウ' => Some('ヴ'),
+        'う' => Some('ゔ'),
+        'ワ' | 'ヰ' | 'ヱ' | 'ヲ' => char::from_u32(kana as u32 + 8),
+        'カ' | 'キ' | 'ク' | 'ケ' | 'コ' | 'サ' | 'シ' | 'ス' | 'セ' | 'ソ' | 'タ' | 'チ'
+        | 'ツ' | 'テ' | 'ト' | 'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' | 'か' | 'き' | 'く' | 'け'
+        | 'こ' | 'さ' | 'し' | 'す' | 'せ' | 'そ' | 'た' | 'ち' | 'つ' | 'て' | 'と' | 'は'
+        | 'ひ' | 'ふ' | 'へ' | 'ほ' | 'ゝ' | 'ヽ' => char::from_u32(kana as u32 + 1),
+        _ => None,
+    }
+}
+
+/// 半濁点付きの文字を取得
+fn with_handakuten(kana: char) -> Option<char> {
+    match kana {
+        'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' | 'は' | 'ひ' | 'ふ' | 'へ' | 'ほ' => {
+            char::from_u32(kana as u32 + 2)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_conversion() {
+        assert_eq!(normalize_halfwidth_kana("ｶﾀｶﾅ"), "カタカナ");
+        assert_eq!(
+            normalize_halfwidth_kana("ｧｨｩｪｫｬｭｮｯｰ"),
+            "ァィゥェォャュョッー"
+        );
+        assert_eq!(normalize_halfwidth_kana("｢ｱ､ｲ｡｣･"), "「ア、イ。」・");
+    }
+
+    #[test]
+    fn test_voiced_marks() {
+        assert_eq!(normalize_halfwidth_kana("ｶﾞｷﾞﾄﾞﾊﾞﾎﾞ"), "ガギドバボ");
+        assert_eq!(normalize_halfwidth_kana("ﾊﾟﾋﾟﾌﾟﾍﾟﾎﾟ"), "パピプペポ");
+        assert_eq!(normalize_halfwidth_kana("ｳﾞﾜﾞｦﾞ"), "ヴヷヺ");
+        // 合成できない濁点・半濁点は単独の文字にする
+        assert_eq!(normalize_halfwidth_kana("ｱﾞｶﾟﾞ"), "ア゛カ゜゛");
+    }
+
+    #[test]
+    fn test_compose_voiced_kana() {
+        assert_eq!(compose_voiced_kana('ワ', false), "ヷ");
+        assert_eq!(compose_voiced_kana('ヰ', false), "ヸ");
+        assert_eq!(compose_voiced_kana('ヱ', false), "ヹ");
+        assert_eq!(compose_voiced_kana('ヲ', false), "ヺ");
+        assert_eq!(compose_voiced_kana('う', false), "ゔ");
+        assert_eq!(compose_voiced_kana('ぱ', false), "ぱ\u{3099}");
+        assert_eq!(compose_voiced_kana('ほ', true), "ぽ");
+        assert_eq!(compose_voiced_kana('セ', true), "セ\u{309A}");
+    }
+
+    #[test]
+    fn test_other_characters_unchanged() {
+        let text = "ＡＢＣ１２３abc漢字ひらがなカタカナ①";
+        assert!(matches!(normalize_halfwidth_kana(text), Cow::Borrowed(_)));
+        assert_eq!(normalize_halfwidth_kana("ＡＢＣｱ１"), "ＡＢＣア１");
+    }
+
+    #[test]
+    fn test_compose_voiced_marks() {
+        assert_eq!(
+            compose_voiced_marks("カ\u{3099}ハ\u{309A}ワ\u{3099}ゝ\u{3099}"),
+            "ガパヷゞ"
+        );
+        // 合成済みの文字がない組はそのまま
+        assert_eq!(
+            compose_voiced_marks("ア\u{3099}カ\u{309A}"),
+            "ア\u{3099}カ\u{309A}"
+        );
+        assert!(matches!(
+            compose_voiced_marks("ガパ"),
+            Cow::Borrowed("ガパ")
+        ));
+    }
+}
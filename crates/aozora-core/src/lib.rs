@@ -20,17 +20,22 @@
 //! assert_eq!(tokens.len(), 2);
 //! ```
 //!
-//! # パーサーの使い方
-//!
-//! ```
-//! use aozora_core::tokenizer::tokenize;
-//! use aozora_core::parser::parse;
-//! use aozora_core::node::Node;
-//!
-//! let tokens = tokenize("｜東京《とうきょう》");
-//! let nodes = parse(&tokens);
-//! assert_eq!(nodes.len(), 1);
-//! ```
+#![cfg_attr(
+    feature = "std",
+    doc = r#"
+# パーサーの使い方
+
+```
+use aozora_core::tokenizer::tokenize;
+use aozora_core::parser::parse;
+use aozora_core::node::Node;
+
+let tokens = tokenize("｜東京《とうきょう》");
+let nodes = parse(&tokens);
+assert_eq!(nodes.len(), 1);
+```
+"#
+)]
 //!
 //! # モジュール構成
 //!
@@ -39,43 +44,91 @@
 //! - `tokenizer` - 字句解析（トークナイザ）
 //! - `node` - ASTノード型の定義
 //! - `parser` - 構文解析（パーサー）
+//! - `command_spec` - 対応している注記の一覧
 //! - `char_type` - 文字種別判定
 //! - `gaiji` - 外字変換
 //! - `accent` - アクセント記号変換
+//! - `kana` - 半角カタカナの正規化
+//! - `limits` - 入力サイズの上限
+//! - `lint` - 記法チェック
+//! - `toc` - 目次生成
 //! - `document` - 文書構造解析
 //! - `encoding` - エンコーディング検出・変換
 //! - `zip` - ZIPファイル処理
+//! - `gzip` - gzipファイル処理
+//! - `input` - 入力ファイルの読み込み
+//!
+//! # `no_std` での利用
+//!
+//! 既定で有効な `std` フィーチャーを無効にすると（`default-features = false`）、
+//! `alloc` のみで動作する `tokenizer`・`token`・`char_type`・`delimiters`・`limits`・
+//! `command_spec` だけを提供します。パーサー以降の機能には `std` フィーチャーが必要です。
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
 pub mod accent;
 pub mod char_type;
+pub mod command_spec;
 pub mod delimiters;
+#[cfg(feature = "std")]
 pub mod document;
+#[cfg(feature = "std")]
 pub mod encoding;
+#[cfg(feature = "std")]
 pub mod gaiji;
+#[cfg(feature = "std")]
+pub mod gzip;
+#[cfg(feature = "std")]
+pub mod input;
+#[cfg(feature = "std")]
 pub mod jis_table;
+#[cfg(feature = "std")]
+pub mod kana;
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
 pub mod node;
+#[cfg(feature = "std")]
 pub mod parser;
+#[cfg(feature = "std")]
+pub mod toc;
 pub mod token;
 pub mod tokenizer;
+#[cfg(feature = "std")]
 pub mod zip;
 
 // Re-exports for convenience
 pub use char_type::{CharType, CharTypeExt};
+pub use command_spec::{supported_commands, CommandCategory, CommandSpec};
 pub use delimiters::{
     ACCENT_BEGIN, ACCENT_END, ACCENT_MARKS, COMMAND_BEGIN, COMMAND_END, GAIJI_MARK, IGETA,
     RUBY_BEGIN, RUBY_END, RUBY_PREFIX,
 };
+#[cfg(feature = "std")]
 pub use document::{
-    extract_bibliographical_lines, extract_body_lines, extract_header_info, HeaderInfo,
+    body_line_range, body_line_range_with_options, extract_bibliographical_lines,
+    extract_body_lines, extract_body_lines_with_options, extract_colophon, extract_header_info,
+    extract_header_info_with_options, join_volumes, Colophon, HeaderInfo, HeaderOptions,
 };
+pub use limits::Limits;
+#[cfg(feature = "std")]
+pub use lint::{lint, LintIssue, Severity};
+#[cfg(feature = "std")]
 pub use node::{
     BlockParams, BlockType, MidashiLevel, MidashiStyle, Node, RubyDirection, StyleType,
 };
-pub use parser::parse;
+#[cfg(feature = "std")]
+pub use parser::{parse, parse_with_options, ParseError, ParseOptions};
+#[cfg(feature = "std")]
+pub use toc::{build_toc, TocEntry};
 pub use token::Token;
-pub use tokenizer::{tokenize, Tokenizer};
+pub use tokenizer::{tokenize, tokenize_debug, tokenize_with_limits, Tokenizer};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -105,4 +158,33 @@ mod tests {
         use encoding::decode_to_utf8;
         assert_eq!(decode_to_utf8("こんにちは".as_bytes()), "こんにちは");
     }
+
+    #[test]
+    fn test_pathological_brackets_complete() {
+        use crate::limits::DEFAULT_MAX_LINE_LENGTH;
+        use crate::node::StyleType;
+
+        // 上限を超えた部分は記法として解釈せず、テキストのまま残る
+        let line = format!("{}［＃「「」に傍点］", "「".repeat(100_000));
+        let tokens = tokenize(&line);
+        assert_eq!(tokens.len(), 2);
+        assert!(
+            matches!(&tokens[0], Token::Text(s) if s.chars().count() == DEFAULT_MAX_LINE_LENGTH)
+        );
+        let text: String = parse(&tokens).iter().map(|n| n.to_text()).collect();
+        assert_eq!(text, line);
+
+        // 上限内では、大量の括弧があっても直前の「「」だけに傍点を付ける
+        let line = format!("{}［＃「「」に傍点］", "「".repeat(60_000));
+        let tokens = tokenize(&line);
+        assert_eq!(tokens.len(), 2);
+        let nodes = parse(&tokens);
+        assert_eq!(nodes.len(), 2);
+        assert!(matches!(&nodes[0], Node::Text(s) if s.chars().count() == 59_999));
+        assert!(matches!(
+            &nodes[1],
+            Node::Style { children, style_type: StyleType::SesameDot, .. }
+                if children.as_slice() == [Node::text("「")]
+        ));
+    }
 }
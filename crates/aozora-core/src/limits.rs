@@ -0,0 +1,87 @@
+//! 入力サイズの上限
+//!
+//! 信頼できない入力を処理する際に、極端に長い行や大量の記法を含む行で
+//! 処理時間が膨らまないよう、字句解析の対象範囲に上限を設けます。
+//! 上限を超えた部分は記法として解釈せず、プレーンテキストとして扱います。
+
+/// 1行あたりの最大文字数（デフォルト）
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 65_536;
+
+/// 1行あたりの最大トークン数（デフォルト）
+pub const DEFAULT_MAX_TOKENS: usize = 8_192;
+
+/// 字句解析・構文解析の上限設定
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::limits::Limits;
+/// use aozora_core::tokenizer::Tokenizer;
+///
+/// // 7文字目以降は記法として解釈しない
+/// let limits = Limits::new().with_max_line_length(7);
+/// let tokens = Tokenizer::with_limits("漢字《かんじ》［＃「漢字」に傍点］", &limits).tokenize();
+/// assert_eq!(tokens.len(), 3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// 記法として解釈する最大文字数（これを超えた部分はテキスト扱い）
+    pub max_line_length: usize,
+    /// 1行から生成する最大トークン数（これを超えた部分はテキスト扱い）
+    pub max_tokens: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+}
+
+impl Limits {
+    /// デフォルトの上限で作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 上限なしで作成
+    pub fn unlimited() -> Self {
+        Self {
+            max_line_length: usize::MAX,
+            max_tokens: usize::MAX,
+        }
+    }
+
+    /// 最大文字数を設定
+    pub fn with_max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = max;
+        self
+    }
+
+    /// 最大トークン数を設定
+    pub fn with_max_tokens(mut self, max: usize) -> Self {
+        self.max_tokens = max;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_limits() {
+        let limits = Limits::default();
+        assert_eq!(limits.max_line_length, DEFAULT_MAX_LINE_LENGTH);
+        assert_eq!(limits.max_tokens, DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_builder() {
+        let limits = Limits::new().with_max_line_length(10).with_max_tokens(3);
+        assert_eq!(limits.max_line_length, 10);
+        assert_eq!(limits.max_tokens, 3);
+    }
+}
@@ -0,0 +1,361 @@
+//! 青空文庫形式の記法チェック
+//!
+//! 入力ファイルの記法上の問題（閉じていないルビ、対応のないブロック、
+//! 未知のコマンド、見つからない参照対象など）を検出します。
+
+use std::fmt;
+
+use crate::delimiters::{
+    ACCENT_BEGIN, COMMAND_BEGIN, GAIJI_MARK, IGETA, RUBY_BEGIN, RUBY_END, RUBY_PREFIX,
+};
+use crate::document::body_line_range;
+use crate::node::{BlockType, Node};
+use crate::parser::{parse, parse_command, CommandResult};
+use crate::token::Token;
+use crate::tokenizer::tokenize;
+
+/// 問題の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// 記法の誤り
+    Error,
+    /// 解釈できないが変換は継続できるもの
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// 検出された問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// 行番号（1始まり）
+    pub line: usize,
+    /// 行内の位置（バイトオフセット）
+    pub column: usize,
+    /// 重大度
+    pub severity: Severity,
+    /// メッセージ
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )
+    }
+}
+
+/// 開いているブロック
+struct OpenBlock {
+    block_type: BlockType,
+    line: usize,
+    column: usize,
+}
+
+/// 青空文庫形式のテキストの記法をチェック
+///
+/// 本文（前付け・注記・後付けを除く）を対象に、以下を報告します：
+///
+/// - 対応の取れていない `《》`（閉じたルビの直後の余分な `》` は、読みの一部として
+///   扱われるため警告）
+/// - 終わりのない `［＃ここから...］` ブロック、対応する開始のない `［＃ここで...終わり］`
+/// - 解釈できないコマンド（注記として出力されるもの）
+/// - 参照対象が直前に見つからない `［＃「...」に傍点］` などの参照
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::lint::{lint, Severity};
+///
+/// let issues = lint("タイトル\n\n漢字《かんじ");
+/// assert_eq!(issues.len(), 1);
+/// assert_eq!(issues[0].line, 3);
+/// assert_eq!(issues[0].column, "漢字".len());
+/// assert_eq!(issues[0].severity, Severity::Error);
+/// ```
+pub fn lint(input: &str) -> Vec<LintIssue> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut issues = Vec::new();
+    let mut open_blocks: Vec<OpenBlock> = Vec::new();
+
+    for index in body_line_range(&lines) {
+        let line = lines[index];
+        let line_no = index + 1;
+        check_ruby_brackets(line, line_no, &mut issues);
+        check_commands(line, line_no, &mut open_blocks, &mut issues);
+    }
+
+    for block in open_blocks {
+        issues.push(LintIssue {
+            line: block.line,
+            column: block.column,
+            severity: Severity::Error,
+            message: format!("block {:?} is never closed", block.block_type),
+        });
+    }
+
+    issues.sort_by_key(|issue| (issue.line, issue.column));
+    issues
+}
+
+/// `《》` の対応をチェック
+fn check_ruby_brackets(line: &str, line_no: usize, issues: &mut Vec<LintIssue>) {
+    let mut open: Option<usize> = None;
+    // ルビを閉じた後、他の記法がまだ始まっていないか
+    // （この間の 》 はトークナイザが読みの一部として扱う）
+    let mut after_ruby_end = false;
+
+    for (pos, ch) in line.char_indices() {
+        match ch {
+            RUBY_BEGIN => {
+                if let Some(prev) = open {
+                    issues.push(unclosed_ruby(line_no, prev));
+                }
+                open = Some(pos);
+                after_ruby_end = false;
+            }
+            RUBY_END if open.is_some() => {
+                open = None;
+                after_ruby_end = true;
+            }
+            RUBY_END if after_ruby_end => issues.push(LintIssue {
+                line: line_no,
+                column: pos,
+                severity: Severity::Warning,
+                message: format!("unmatched ruby end {RUBY_END} is treated as part of the reading"),
+            }),
+            RUBY_END => issues.push(LintIssue {
+                line: line_no,
+                column: pos,
+                severity: Severity::Error,
+                message: format!("unmatched ruby end {RUBY_END}"),
+            }),
+            RUBY_PREFIX | COMMAND_BEGIN | GAIJI_MARK | ACCENT_BEGIN => after_ruby_end = false,
+            _ => {}
+        }
+    }
+
+    if let Some(pos) = open {
+        issues.push(unclosed_ruby(line_no, pos));
+    }
+}
+
+fn unclosed_ruby(line_no: usize, column: usize) -> LintIssue {
+    LintIssue {
+        line: line_no,
+        column,
+        severity: Severity::Error,
+        message: format!("ruby {RUBY_BEGIN} is not closed with {RUBY_END}"),
+    }
+}
+
+/// 行内のコマンドをチェック
+fn check_commands(
+    line: &str,
+    line_no: usize,
+    open_blocks: &mut Vec<OpenBlock>,
+    issues: &mut Vec<LintIssue>,
+) {
+    let tokens = tokenize(line);
+    let mut cursor = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Command { content } = token else {
+            continue;
+        };
+
+        // コマンドの位置を行内から探す
+        let needle = format!("{COMMAND_BEGIN}{IGETA}{content}");
+        let column = match line[cursor..].find(&needle) {
+            Some(offset) => cursor + offset,
+            None => cursor,
+        };
+        cursor = column + needle.len();
+
+        let issue = |severity, message| LintIssue {
+            line: line_no,
+            column,
+            severity,
+            message,
+        };
+
+        match parse_command(content) {
+            CommandResult::BlockStart { block_type, params } if params.is_block => {
+                open_blocks.push(OpenBlock {
+                    block_type,
+                    line: line_no,
+                    column,
+                });
+            }
+            CommandResult::BlockEnd { block_type } if content.trim().starts_with("ここで") => {
+                let pos = open_blocks.iter().rposition(|b| {
+                    b.block_type == block_type
+                        || (block_type == BlockType::Jisage && b.block_type == BlockType::Burasage)
+                });
+                match pos {
+                    Some(pos) => {
+                        open_blocks.remove(pos);
+                    }
+                    None => issues.push(issue(
+                        Severity::Error,
+                        format!("block end {block_type:?} has no matching start"),
+                    )),
+                }
+            }
+            CommandResult::Note(text) | CommandResult::Unknown(text) => {
+                issues.push(issue(
+                    Severity::Warning,
+                    format!("unknown command ［＃{text}］"),
+                ));
+            }
+            result => {
+                let Some(target) = reference_target(&result) else {
+                    continue;
+                };
+                if !preceding_text(&tokens[..i]).contains(target) {
+                    issues.push(issue(
+                        Severity::Error,
+                        format!("reference target 「{target}」 not found before command"),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 参照コマンドの対象テキストを取得
+fn reference_target(result: &CommandResult) -> Option<&str> {
+    match result {
+        CommandResult::Style { target, .. }
+        | CommandResult::Midashi { target, .. }
+        | CommandResult::FontSize { target, .. }
+        | CommandResult::AnnotationRuby { target, .. }
+        | CommandResult::InlineTcy { target }
+        | CommandResult::InlineKeigakomi { target }
+        | CommandResult::InlineYokogumi { target }
+        | CommandResult::InlineCaption { target }
+        | CommandResult::SideNote { target, .. } => Some(target),
+        _ => None,
+    }
+}
+
+/// トークン列のプレーンテキストを取得
+fn preceding_text(tokens: &[Token]) -> String {
+    parse(tokens).iter().map(Node::to_text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_body(body: &str) -> Vec<LintIssue> {
+        lint(&format!("タイトル\n\n{body}"))
+    }
+
+    #[test]
+    fn test_clean_document() {
+        let issues = lint_body("吾輩《わがはい》は猫である［＃「猫」に傍点］");
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_ruby() {
+        let issues = lint_body("本文\n漢字《かんじ");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 4);
+        assert_eq!(issues[0].column, "漢字".len());
+        assert_eq!(issues[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_unmatched_ruby_end() {
+        let issues = lint_body("漢字かんじ》");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].column, "漢字かんじ".len());
+    }
+
+    #[test]
+    fn test_stray_ruby_end_in_reading() {
+        let issues = lint_body("漢字《よ》み》です");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].column, "漢字《よ》み".len());
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert!(issues[0].message.contains("part of the reading"));
+
+        // 別の記法を挟んだ後の 》 は読みに含まれないため誤り
+        let issues = lint_body("漢字《かんじ》［＃傍点］かんじ》");
+        assert!(issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error && issue.message.contains("unmatched")));
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let issues = lint_body("本文［＃謎の指定］");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].column, "本文".len());
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert!(issues[0].message.contains("謎の指定"));
+    }
+
+    #[test]
+    fn test_unmatched_block_end() {
+        let issues = lint_body("本文\n［＃ここで字下げ終わり］");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 4);
+        assert_eq!(issues[0].column, 0);
+        assert_eq!(issues[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_block_end_does_not_match_other_block() {
+        let issues =
+            lint_body("［＃ここから罫囲み］\n［＃ここで字下げ終わり］\n［＃ここで罫囲み終わり］");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 4);
+        assert!(issues[0].message.contains("Jisage has no matching start"));
+    }
+
+    #[test]
+    fn test_unclosed_block() {
+        let issues = lint_body("［＃ここから2字下げ］\n本文");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 3);
+        assert!(issues[0].message.contains("never closed"));
+    }
+
+    #[test]
+    fn test_matched_block() {
+        let issues = lint_body("［＃ここから2字下げ］\n本文\n［＃ここで字下げ終わり］");
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_reference_target_not_found() {
+        let issues = lint_body("吾輩は猫である［＃「犬」に傍点］");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].column, "吾輩は猫である".len());
+        assert!(issues[0].message.contains("犬"));
+    }
+
+    #[test]
+    fn test_display() {
+        let issue = LintIssue {
+            line: 3,
+            column: 6,
+            severity: Severity::Warning,
+            message: "unknown command".to_string(),
+        };
+        assert_eq!(issue.to_string(), "3:6: warning: unknown command");
+    }
+}
@@ -9,6 +9,10 @@ pub enum BlockType {
     Jisage,
     /// 地付き
     Chitsuki,
+    /// 中央寄せ
+    Chuuou,
+    /// ページの左右中央・天地中央（扉など）
+    PageCenter,
     /// 字詰め
     Jizume,
     /// 罫囲み
@@ -41,7 +45,42 @@ pub enum BlockType {
     LeftAnnotationRange,
 }
 
+/// 割り注のコマンドとして受け付ける表記（古い表記を含む）
+///
+/// 開始は表記そのもの（「割り注始め」のように「始め」を付けたものも可）、
+/// 終了は表記に「終わり」を付けたものです。
+pub const WARIGAKI_COMMANDS: &[&str] = &["割り注", "割注", "わりちゅう"];
+
+/// 縦中横のコマンドとして受け付ける表記（古い表記を含む）
+///
+/// 開始は表記そのもの、終了は表記に「終わり」を付けたものです。
+pub const TCY_COMMANDS: &[&str] = &["縦中横", "縦中横き"];
+
 impl BlockType {
+    /// 割り注・縦中横の開始コマンドからブロックタイプを取得
+    ///
+    /// 割り注は「割り注始め」のように「始め」を付けた表記も受け付けます。
+    pub fn from_inline_start_command(command: &str) -> Option<Self> {
+        match Self::from_inline_command(command) {
+            Some(block_type) => Some(block_type),
+            None => command
+                .strip_suffix("始め")
+                .filter(|spelling| WARIGAKI_COMMANDS.contains(spelling))
+                .map(|_| BlockType::Warigaki),
+        }
+    }
+
+    /// 割り注・縦中横のコマンド（終了コマンドは「終わり」を除いたもの）からブロックタイプを取得
+    pub fn from_inline_command(command: &str) -> Option<Self> {
+        if WARIGAKI_COMMANDS.contains(&command) {
+            Some(BlockType::Warigaki)
+        } else if TCY_COMMANDS.contains(&command) {
+            Some(BlockType::Tcy)
+        } else {
+            None
+        }
+    }
+
     /// コマンド名からブロックタイプを取得
     pub fn from_command(command: &str) -> Option<Self> {
         // 折り返しがある場合はBurasage（コマンドパーサーで先に処理されるが念のため）
@@ -49,7 +88,16 @@ impl BlockType {
             Some(BlockType::Burasage)
         } else if command.contains("字下げ") {
             Some(BlockType::Jisage)
+        } else if command.contains("左右中央") || command.contains("天地中央") {
+            Some(BlockType::PageCenter)
+        } else if command.contains("中央寄せ")
+            || command.contains("中央揃え")
+            || command.contains("センタリング")
+        {
+            Some(BlockType::Chuuou)
         } else if command.contains("地付き")
+            || command.contains("地寄せ")
+            || command.contains("右寄せ")
             || command.contains("地から")
             || command.contains("字上げ")
         {
@@ -70,11 +118,14 @@ impl BlockType {
             Some(BlockType::FontDai)
         } else if command.contains("小さな文字") {
             Some(BlockType::FontSho)
-        } else if command.contains("縦中横") {
+        } else if TCY_COMMANDS.iter().any(|tcy| command.contains(tcy)) {
             Some(BlockType::Tcy)
         } else if command.contains("キャプション") {
             Some(BlockType::Caption)
-        } else if command.contains("割り注") {
+        } else if WARIGAKI_COMMANDS
+            .iter()
+            .any(|warigaki| command.contains(warigaki))
+        {
             Some(BlockType::Warigaki)
         } else {
             None
@@ -115,6 +166,22 @@ mod tests {
     fn test_block_type_from_command() {
         assert_eq!(BlockType::from_command("2字下げ"), Some(BlockType::Jisage));
         assert_eq!(BlockType::from_command("地付き"), Some(BlockType::Chitsuki));
+        assert_eq!(BlockType::from_command("地寄せ"), Some(BlockType::Chitsuki));
+        assert_eq!(BlockType::from_command("右寄せ"), Some(BlockType::Chitsuki));
+        assert_eq!(BlockType::from_command("中央寄せ"), Some(BlockType::Chuuou));
+        assert_eq!(BlockType::from_command("中央揃え"), Some(BlockType::Chuuou));
+        assert_eq!(
+            BlockType::from_command("センタリング"),
+            Some(BlockType::Chuuou)
+        );
+        assert_eq!(
+            BlockType::from_command("ページの左右中央"),
+            Some(BlockType::PageCenter)
+        );
+        assert_eq!(
+            BlockType::from_command("ページの天地中央"),
+            Some(BlockType::PageCenter)
+        );
         assert_eq!(BlockType::from_command("太字"), Some(BlockType::Futoji));
     }
 }
@@ -5,10 +5,12 @@
 mod block;
 mod midashi;
 mod style;
+mod visitor;
 
-pub use block::{BlockParams, BlockType};
+pub use block::{BlockParams, BlockType, TCY_COMMANDS, WARIGAKI_COMMANDS};
 pub use midashi::{MidashiLevel, MidashiStyle};
 pub use style::StyleType;
+pub use visitor::{fold_children, walk_node, Fold, Visitor};
 
 use crate::char_type::CharType;
 
@@ -128,7 +130,25 @@ pub enum Node {
     Kaeriten(String),
 
     /// 訓点送り仮名
-    Okurigana(String),
+    Okurigana {
+        /// 送り仮名
+        text: String,
+        /// 送る側（`［＃左に（ニ）］` なら左）
+        direction: RubyDirection,
+    },
+
+    /// 行内の改行（`［＃改行］`）
+    LineBreak,
+
+    /// 底本の位置（`［＃12ページ］`・`［＃丁付け十二ウ］` など）
+    ///
+    /// 本文としては出力せず、続く内容の底本での位置を表します。
+    Position {
+        /// ページ番号
+        page: Option<u32>,
+        /// 丁付け（`十二ウ` など）
+        chou: Option<String>,
+    },
 
     /// ブロック開始
     BlockStart {
@@ -169,12 +189,6 @@ pub enum Node {
         /// 接続詞（に、は、の）
         connector: String,
     },
-
-    /// 濁点カタカナ参照
-    DakutenKatakana {
-        /// JISコードの末尾番号
-        num: String,
-    },
 }
 
 /// ルビの方向
@@ -270,8 +284,10 @@ impl Node {
             }
             Node::FontSize { children, .. } => children.iter().map(|n| n.to_text()).collect(),
             Node::Kaeriten(s) => s.clone(),
-            Node::Okurigana(s) => s.clone(),
-            Node::BlockStart { .. }
+            Node::Okurigana { text, .. } => text.clone(),
+            Node::LineBreak
+            | Node::Position { .. }
+            | Node::BlockStart { .. }
             | Node::BlockEnd { .. }
             | Node::Note(_)
             | Node::AnnotationEnd { .. } => String::new(),
@@ -282,13 +298,6 @@ impl Node {
             } => {
                 format!("［＃「{target}」{connector}{spec}］")
             }
-            Node::DakutenKatakana { num } => match num.as_str() {
-                "2" => "ワ゛".to_string(),
-                "3" => "ヰ゛".to_string(),
-                "4" => "ヱ゛".to_string(),
-                "5" => "ヲ゛".to_string(),
-                _ => String::new(),
-            },
         }
     }
 
@@ -305,7 +314,6 @@ impl Node {
             }),
             Node::Gaiji { .. } => Some(CharType::Kanji),
             Node::Accent { .. } => Some(CharType::Hankaku),
-            Node::DakutenKatakana { .. } => Some(CharType::Katakana),
             _ => None,
         }
     }
@@ -1,7 +1,7 @@
 //! 装飾タイプ定義
 
 /// 装飾タイプ
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StyleType {
     // 傍点系（右・上）
     SesameDot,
@@ -44,6 +44,13 @@ pub enum StyleType {
     Italic,
     Subscript,
     Superscript,
+
+    // 行右・行左の小書き（縦書きで行の脇に添える小さな文字）
+    GyouMigiKogaki,
+    GyouHidariKogaki,
+
+    // 二倍角（文字を2字分の幅で表示）
+    Nibai,
 }
 
 impl StyleType {
@@ -85,8 +92,11 @@ impl StyleType {
             // 文字スタイル
             "太字" => Some(StyleType::Bold),
             "斜体" => Some(StyleType::Italic),
-            "下付き小文字" | "行左小書き" => Some(StyleType::Subscript),
-            "上付き小文字" | "行右小書き" => Some(StyleType::Superscript),
+            "下付き小文字" => Some(StyleType::Subscript),
+            "上付き小文字" => Some(StyleType::Superscript),
+            "行右小書き" => Some(StyleType::GyouMigiKogaki),
+            "行左小書き" => Some(StyleType::GyouHidariKogaki),
+            "二倍角" => Some(StyleType::Nibai),
             _ => None,
         }
     }
@@ -115,6 +125,22 @@ impl StyleType {
         }
     }
 
+    /// 傍点（左の傍点を含む）かどうか
+    pub fn is_bouten(self) -> bool {
+        matches!(
+            self.to_after_variant(),
+            StyleType::SesameDotAfter
+                | StyleType::WhiteSesameDotAfter
+                | StyleType::BlackCircleAfter
+                | StyleType::WhiteCircleAfter
+                | StyleType::BlackTriangleAfter
+                | StyleType::WhiteTriangleAfter
+                | StyleType::BullseyeAfter
+                | StyleType::FisheyeAfter
+                | StyleType::SaltireAfter
+        )
+    }
+
     /// コマンド名を取得
     pub fn command_name(&self) -> &'static str {
         match self {
@@ -150,6 +176,9 @@ impl StyleType {
             StyleType::Italic => "斜体",
             StyleType::Subscript => "下付き小文字",
             StyleType::Superscript => "上付き小文字",
+            StyleType::GyouMigiKogaki => "行右小書き",
+            StyleType::GyouHidariKogaki => "行左小書き",
+            StyleType::Nibai => "二倍角",
         }
     }
 }
@@ -164,4 +193,25 @@ mod tests {
         assert_eq!(StyleType::from_command("太字"), Some(StyleType::Bold));
         assert_eq!(StyleType::from_command("未知"), None);
     }
+
+    #[test]
+    fn test_gyou_kogaki_from_command() {
+        assert_eq!(
+            StyleType::from_command("行右小書き"),
+            Some(StyleType::GyouMigiKogaki)
+        );
+        assert_eq!(
+            StyleType::from_command("行左小書き"),
+            Some(StyleType::GyouHidariKogaki)
+        );
+        assert_eq!(StyleType::GyouMigiKogaki.command_name(), "行右小書き");
+    }
+
+    #[test]
+    fn test_is_bouten() {
+        assert!(StyleType::SesameDot.is_bouten());
+        assert!(StyleType::WhiteCircleAfter.is_bouten());
+        assert!(!StyleType::UnderlineSolid.is_bouten());
+        assert!(!StyleType::Bold.is_bouten());
+    }
 }
@@ -0,0 +1,326 @@
+//! ノードの走査・変換
+//!
+//! AST を走査する `Visitor` と、AST を作り直す `Fold` を定義します。
+//! どちらも既定の実装で子ノードを再帰的にたどるため、
+//! 関心のあるノード種別のメソッドだけを実装すれば済みます。
+
+use super::{BlockParams, BlockType, MidashiLevel, MidashiStyle, Node, RubyDirection, StyleType};
+
+/// ノードの走査
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::node::Visitor;
+/// use aozora_core::{parse, tokenize};
+///
+/// #[derive(Default)]
+/// struct TextLength(usize);
+///
+/// impl Visitor for TextLength {
+///     fn visit_text(&mut self, text: &str) {
+///         self.0 += text.chars().count();
+///     }
+/// }
+///
+/// let nodes = parse(&tokenize("吾輩《わがはい》は猫である"));
+/// let mut counter = TextLength::default();
+/// counter.walk(&nodes);
+/// // 親文字とルビの両方を数える
+/// assert_eq!(counter.0, 11);
+/// ```
+pub trait Visitor {
+    /// ノード列を順に走査
+    fn walk(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.visit_node(node);
+        }
+    }
+
+    /// 単一ノードを走査（既定では種別ごとの `visit_*` に振り分ける）
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    /// テキスト
+    fn visit_text(&mut self, _text: &str) {}
+
+    /// ルビ
+    fn visit_ruby(&mut self, children: &[Node], ruby: &[Node], _direction: RubyDirection) {
+        self.walk(children);
+        self.walk(ruby);
+    }
+
+    /// 装飾
+    fn visit_style(&mut self, children: &[Node], _style_type: StyleType) {
+        self.walk(children);
+    }
+
+    /// 見出し
+    fn visit_midashi(&mut self, children: &[Node], _level: MidashiLevel, _style: MidashiStyle) {
+        self.walk(children);
+    }
+
+    /// 外字
+    fn visit_gaiji(&mut self, _description: &str, _unicode: Option<&str>, _jis_code: Option<&str>) {
+    }
+
+    /// 子ノードを持つその他のノード（縦中横、罫囲み、横組み、キャプション、フォントサイズ）
+    fn visit_container(&mut self, _node: &Node, children: &[Node]) {
+        self.walk(children);
+    }
+
+    /// 割書き
+    fn visit_warigaki(&mut self, upper: &[Node], lower: &[Node]) {
+        self.walk(upper);
+        self.walk(lower);
+    }
+
+    /// ブロック開始
+    fn visit_block_start(&mut self, _block_type: BlockType, _params: &BlockParams) {}
+
+    /// ブロック終了
+    fn visit_block_end(&mut self, _block_type: BlockType, _params: &BlockParams) {}
+
+    /// 注記
+    fn visit_note(&mut self, _text: &str) {}
+
+    /// 注記付き範囲の終了マーカー
+    fn visit_annotation_end(&mut self, content: &[Node]) {
+        self.walk(content);
+    }
+
+    /// 子ノードを持たないその他のノード（アクセント、画像、返り点など）
+    fn visit_leaf(&mut self, _node: &Node) {}
+}
+
+/// ノードの種別に応じて `Visitor` のメソッドを呼び出す
+///
+/// `Visitor::visit_node` を上書きした場合に、既定の振り分けを行うために使用します。
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    match node {
+        Node::Text(text) => visitor.visit_text(text),
+        Node::Ruby {
+            children,
+            ruby,
+            direction,
+        } => visitor.visit_ruby(children, ruby, *direction),
+        Node::Style {
+            children,
+            style_type,
+            ..
+        } => visitor.visit_style(children, *style_type),
+        Node::Midashi {
+            children,
+            level,
+            style,
+        } => visitor.visit_midashi(children, *level, *style),
+        Node::Gaiji {
+            description,
+            unicode,
+            jis_code,
+        } => visitor.visit_gaiji(description, unicode.as_deref(), jis_code.as_deref()),
+        Node::Tcy { children }
+        | Node::Keigakomi { children }
+        | Node::Yokogumi { children }
+        | Node::Caption { children }
+        | Node::FontSize { children, .. } => visitor.visit_container(node, children),
+        Node::Warigaki { upper, lower } => visitor.visit_warigaki(upper, lower),
+        Node::BlockStart { block_type, params } => visitor.visit_block_start(*block_type, params),
+        Node::BlockEnd { block_type, params } => visitor.visit_block_end(*block_type, params),
+        Node::Note(text) => visitor.visit_note(text),
+        Node::AnnotationEnd { content, .. } => visitor.visit_annotation_end(content),
+        Node::Accent { .. }
+        | Node::Img { .. }
+        | Node::Kaeriten(_)
+        | Node::Okurigana { .. }
+        | Node::LineBreak
+        | Node::Position { .. }
+        | Node::UnresolvedReference { .. } => visitor.visit_leaf(node),
+    }
+}
+
+/// ノードの変換
+///
+/// `fold_node` は1つのノードを0個以上のノードに置き換えます。
+/// 既定では子ノードを再帰的に変換したノードをそのまま返します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::node::{fold_children, Fold, Node};
+/// use aozora_core::{parse, tokenize};
+///
+/// struct Uppercase;
+///
+/// impl Fold for Uppercase {
+///     fn fold_node(&mut self, node: Node) -> Vec<Node> {
+///         match node {
+///             Node::Text(text) => vec![Node::Text(text.to_uppercase())],
+///             other => vec![fold_children(self, other)],
+///         }
+///     }
+/// }
+///
+/// let nodes = Uppercase.fold_nodes(parse(&tokenize("aozora")));
+/// assert_eq!(nodes, vec![Node::text("AOZORA")]);
+/// ```
+pub trait Fold {
+    /// ノード列を変換
+    fn fold_nodes(&mut self, nodes: Vec<Node>) -> Vec<Node> {
+        let mut result = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            result.extend(self.fold_node(node));
+        }
+        result
+    }
+
+    /// 単一ノードを変換
+    fn fold_node(&mut self, node: Node) -> Vec<Node> {
+        vec![fold_children(self, node)]
+    }
+}
+
+/// ノードの子ノードを `Fold` で変換したノードを返す
+pub fn fold_children<F: Fold + ?Sized>(folder: &mut F, node: Node) -> Node {
+    match node {
+        Node::Ruby {
+            children,
+            ruby,
+            direction,
+        } => Node::Ruby {
+            children: folder.fold_nodes(children),
+            ruby: folder.fold_nodes(ruby),
+            direction,
+        },
+        Node::Style {
+            children,
+            style_type,
+            class_name,
+        } => Node::Style {
+            children: folder.fold_nodes(children),
+            style_type,
+            class_name,
+        },
+        Node::Midashi {
+            children,
+            level,
+            style,
+        } => Node::Midashi {
+            children: folder.fold_nodes(children),
+            level,
+            style,
+        },
+        Node::Tcy { children } => Node::Tcy {
+            children: folder.fold_nodes(children),
+        },
+        Node::Keigakomi { children } => Node::Keigakomi {
+            children: folder.fold_nodes(children),
+        },
+        Node::Yokogumi { children } => Node::Yokogumi {
+            children: folder.fold_nodes(children),
+        },
+        Node::Caption { children } => Node::Caption {
+            children: folder.fold_nodes(children),
+        },
+        Node::Warigaki { upper, lower } => Node::Warigaki {
+            upper: folder.fold_nodes(upper),
+            lower: folder.fold_nodes(lower),
+        },
+        Node::FontSize {
+            children,
+            size_type,
+            level,
+        } => Node::FontSize {
+            children: folder.fold_nodes(children),
+            size_type,
+            level,
+        },
+        Node::AnnotationEnd {
+            prefix,
+            content,
+            suffix,
+        } => Node::AnnotationEnd {
+            prefix,
+            content: folder.fold_nodes(content),
+            suffix,
+        },
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenize;
+
+    #[derive(Default)]
+    struct RubyCounter {
+        count: usize,
+    }
+
+    impl Visitor for RubyCounter {
+        fn visit_ruby(&mut self, children: &[Node], ruby: &[Node], _direction: RubyDirection) {
+            self.count += 1;
+            self.walk(children);
+            self.walk(ruby);
+        }
+    }
+
+    struct RemoveNotes;
+
+    impl Fold for RemoveNotes {
+        fn fold_node(&mut self, node: Node) -> Vec<Node> {
+            match node {
+                Node::Note(_) => Vec::new(),
+                other => vec![fold_children(self, other)],
+            }
+        }
+    }
+
+    #[test]
+    fn test_visitor_counts_ruby() {
+        let nodes = parse(&tokenize(
+            "吾輩《わがはい》は猫《ねこ》である［＃「である」に傍点］。｜東京《とうきょう》",
+        ));
+        let mut counter = RubyCounter::default();
+        counter.walk(&nodes);
+        assert_eq!(counter.count, 3);
+    }
+
+    #[test]
+    fn test_visitor_enters_nested_nodes() {
+        let nodes = parse(&tokenize(
+            "猫《ねこ》である［＃「猫《ねこ》である」に傍点］",
+        ));
+        let mut counter = RubyCounter::default();
+        counter.walk(&nodes);
+        assert_eq!(counter.count, 1);
+    }
+
+    #[test]
+    fn test_fold_removes_notes() {
+        let nodes = parse(&tokenize("本文［＃謎の注記］続き"));
+        assert!(nodes.iter().any(|n| matches!(n, Node::Note(_))));
+
+        let folded = RemoveNotes.fold_nodes(nodes);
+        assert!(!folded.iter().any(|n| matches!(n, Node::Note(_))));
+        let text: String = folded.iter().map(Node::to_text).collect();
+        assert_eq!(text, "本文続き");
+    }
+
+    #[test]
+    fn test_fold_removes_nested_notes() {
+        let nodes = vec![Node::Keigakomi {
+            children: vec![Node::text("囲み"), Node::Note("注".to_string())],
+        }];
+        let folded = RemoveNotes.fold_nodes(nodes);
+        assert_eq!(
+            folded,
+            vec![Node::Keigakomi {
+                children: vec![Node::text("囲み")],
+            }]
+        );
+    }
+}
@@ -10,8 +10,10 @@ use super::utils::extract_number;
 /// ブロック開始を解析
 pub fn parse_block_start(content: &str) -> CommandResult {
     let content = content.trim_start_matches("ここから");
-    let mut params = BlockParams::default();
-    params.is_block = true; // ここから pattern is block-level
+    let mut params = BlockParams {
+        is_block: true, // ここから pattern is block-level
+        ..Default::default()
+    };
 
     // ぶら下げパターン: 「N字下げ、折り返してM字下げ」または「改行天付き、折り返してN字下げ」
     if content.contains("折り返して") {
@@ -90,13 +92,15 @@ pub fn parse_block_end(content: &str) -> CommandResult {
 pub fn parse_inline_end(content: &str) -> CommandResult {
     let content = content.trim_end_matches("終わり");
 
-    // 固定パターン
-    match content {
-        "縦中横" => return CommandResult::TcyEnd,
-        "割り注" => return CommandResult::WarigakiEnd,
-        "キャプション" => return CommandResult::CaptionEnd,
+    // 固定パターン（縦中横・割り注は古い表記を含む）
+    match BlockType::from_inline_command(content) {
+        Some(BlockType::Tcy) => return CommandResult::TcyEnd,
+        Some(BlockType::Warigaki) => return CommandResult::WarigakiEnd,
         _ => {}
     }
+    if content == "キャプション" {
+        return CommandResult::CaptionEnd;
+    }
 
     // 装飾終了
     if let Some(style_type) = StyleType::from_command(content) {
@@ -121,9 +125,10 @@ pub fn try_parse_line_indent(content: &str) -> Option<CommandResult> {
     Some(CommandResult::LineIndent { width })
 }
 
-/// 行単位地付き/地からを解析
+/// 行単位地付き/地からを解析（右寄せは地付きとして扱う）
 pub fn try_parse_line_chitsuki(content: &str) -> Option<CommandResult> {
-    if content.contains("地付き") {
+    if content.contains("地付き") || content.contains("地寄せ") || content.contains("右寄せ")
+    {
         return Some(CommandResult::LineChitsuki { width: 0 });
     }
 
@@ -139,9 +144,11 @@ pub fn try_parse_line_chitsuki(content: &str) -> Option<CommandResult> {
 pub fn try_parse_midashi_start(content: &str) -> Option<CommandResult> {
     let level = MidashiLevel::from_command(content)?;
     let style = MidashiStyle::from_command(content);
-    let mut params = BlockParams::default();
-    params.level = Some(level);
-    params.midashi_style = Some(style);
+    let params = BlockParams {
+        level: Some(level),
+        midashi_style: Some(style),
+        ..Default::default()
+    };
     Some(CommandResult::BlockStart {
         block_type: BlockType::Midashi,
         params,
@@ -151,8 +158,10 @@ pub fn try_parse_midashi_start(content: &str) -> Option<CommandResult> {
 /// インラインフォントサイズ開始を解析
 pub fn try_parse_font_size_start(content: &str) -> Option<CommandResult> {
     let (size_type, level) = FontSizeType::from_command(content)?;
-    let mut params = BlockParams::default();
-    params.font_size = Some(level);
+    let params = BlockParams {
+        font_size: Some(level),
+        ..Default::default()
+    };
     Some(CommandResult::BlockStart {
         block_type: match size_type {
             FontSizeType::Dai => BlockType::FontDai,
@@ -206,6 +215,87 @@ mod tests {
 
         let result = try_parse_line_chitsuki("地から3字上げ");
         assert_eq!(result, Some(CommandResult::LineChitsuki { width: 3 }));
+
+        let result = try_parse_line_chitsuki("地寄せ");
+        assert_eq!(result, Some(CommandResult::LineChitsuki { width: 0 }));
+
+        let result = try_parse_line_chitsuki("右寄せ");
+        assert_eq!(result, Some(CommandResult::LineChitsuki { width: 0 }));
+    }
+
+    #[test]
+    fn test_parse_block_chitsuki() {
+        for command in ["ここから地付き", "ここから地寄せ", "ここから右寄せ"] {
+            match parse_block_start(command) {
+                CommandResult::BlockStart { block_type, params } => {
+                    assert_eq!(block_type, BlockType::Chitsuki);
+                    assert!(params.is_block);
+                }
+                other => panic!("unexpected result for {command}: {other:?}"),
+            }
+        }
+
+        for command in [
+            "ここで地付き終わり",
+            "ここで地寄せ終わり",
+            "ここで右寄せ終わり",
+        ] {
+            assert_eq!(
+                parse_block_end(command),
+                CommandResult::BlockEnd {
+                    block_type: BlockType::Chitsuki,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_block_chuuou() {
+        for command in [
+            "ここから中央寄せ",
+            "ここから中央揃え",
+            "ここからセンタリング",
+        ] {
+            assert_eq!(
+                parse_block_start(command),
+                CommandResult::BlockStart {
+                    block_type: BlockType::Chuuou,
+                    params: BlockParams {
+                        is_block: true,
+                        ..Default::default()
+                    },
+                }
+            );
+        }
+        assert_eq!(
+            parse_block_end("ここで中央寄せ終わり"),
+            CommandResult::BlockEnd {
+                block_type: BlockType::Chuuou,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_block_page_center() {
+        for command in ["ここからページの左右中央", "ここからページの天地中央"]
+        {
+            assert_eq!(
+                parse_block_start(command),
+                CommandResult::BlockStart {
+                    block_type: BlockType::PageCenter,
+                    params: BlockParams {
+                        is_block: true,
+                        ..Default::default()
+                    },
+                }
+            );
+        }
+        assert_eq!(
+            parse_block_end("ここでページの左右中央終わり"),
+            CommandResult::BlockEnd {
+                block_type: BlockType::PageCenter,
+            }
+        );
     }
 
     #[test]
@@ -2,13 +2,17 @@
 //!
 //! `［＃...］` 形式のコマンド内容を解析し、適切なノードまたはコマンド情報を返します。
 
-use crate::node::{BlockParams, BlockType, FontSizeType, MidashiLevel, MidashiStyle, StyleType};
+use crate::node::{
+    BlockParams, BlockType, FontSizeType, MidashiLevel, MidashiStyle, RubyDirection, StyleType,
+};
 
 use super::block_parser::{
     parse_block_end, parse_block_start, parse_inline_end, try_parse_font_size_start,
     try_parse_line_chitsuki, try_parse_line_indent, try_parse_midashi_start,
 };
-use super::content_parser::{is_kaeriten, try_parse_image, try_parse_okurigana};
+use super::content_parser::{
+    is_kaeriten, try_parse_character, try_parse_image, try_parse_okurigana, try_parse_position,
+};
 use super::reference_parser::{try_parse_left_ruby, try_parse_reference};
 
 /// コマンド解析結果
@@ -65,7 +69,26 @@ pub enum CommandResult {
     Kaeriten(String),
 
     /// 訓点送り仮名
-    Okurigana(String),
+    Okurigana {
+        /// 送り仮名
+        text: String,
+        /// 送る側
+        direction: RubyDirection,
+    },
+
+    /// 文字に置き換えるコマンド（四分アキ、全角ダッシュなど）
+    Character(String),
+
+    /// 底本の位置（ページ番号・丁付け）
+    Position {
+        /// ページ番号
+        page: Option<u32>,
+        /// 丁付け（`十二ウ` など）
+        chou: Option<String>,
+    },
+
+    /// 行内の改行
+    LineBreak,
 
     /// 縦中横開始
     TcyStart,
@@ -184,9 +207,24 @@ pub fn parse_command(content: &str) -> CommandResult {
         return CommandResult::Kaeriten(content.to_string());
     }
 
-    // 9. 訓点送り仮名
-    if let Some(okurigana) = try_parse_okurigana(content) {
-        return CommandResult::Okurigana(okurigana);
+    // 9. 訓点送り仮名（「左に」「右に」で送る側を指定できる）
+    if let Some(result) = try_parse_okurigana_command(content) {
+        return result;
+    }
+
+    // 9.5. 空白（アキ）・ダッシュ
+    if let Some(text) = try_parse_character(content) {
+        return CommandResult::Character(text);
+    }
+
+    // 9.6. 行内の改行
+    if content == "改行" {
+        return CommandResult::LineBreak;
+    }
+
+    // 9.7. 底本のページ番号・丁付け
+    if let Some(result) = try_parse_position(content) {
+        return result;
     }
 
     // 10. 訓点送り仮名（説明付き）
@@ -201,14 +239,11 @@ pub fn parse_command(content: &str) -> CommandResult {
         }
     }
 
-    // 12. 縦中横
-    if content == "縦中横" {
-        return CommandResult::TcyStart;
-    }
-
-    // 13. 割り注
-    if content == "割り注" {
-        return CommandResult::WarigakiStart;
+    // 12-13. 縦中横・割り注（古い表記を含む）
+    match BlockType::from_inline_start_command(content) {
+        Some(BlockType::Tcy) => return CommandResult::TcyStart,
+        Some(BlockType::Warigaki) => return CommandResult::WarigakiStart,
+        _ => {}
     }
 
     // 13.5. 罫囲み（インライン）
@@ -293,12 +328,9 @@ fn try_parse_side_note(content: &str) -> Option<CommandResult> {
 
     let rest = content.trim_end_matches("の傍記");
 
-    // 「対象」に「注記」 形式を解析
+    // 「対象」に「注記」 形式を解析（対象には外字表記の「」が入れ子になることがある）
     let first_start = rest.find('「')?;
-    let first_end = rest.find('」')?;
-    if first_end <= first_start {
-        return None;
-    }
+    let first_end = first_start + find_closing_bracket(&rest[first_start..])?;
 
     let target = &rest[first_start + '「'.len_utf8()..first_end];
 
@@ -317,6 +349,39 @@ fn try_parse_side_note(content: &str) -> Option<CommandResult> {
     })
 }
 
+/// 訓点送り仮名を解析（`（ニ）`、`左に（ニ）`、`右に（ニ）`）
+fn try_parse_okurigana_command(content: &str) -> Option<CommandResult> {
+    let (rest, direction) = if let Some(rest) = content.strip_prefix("左に") {
+        (rest, RubyDirection::Left)
+    } else {
+        (
+            content.strip_prefix("右に").unwrap_or(content),
+            RubyDirection::Right,
+        )
+    };
+
+    let text = try_parse_okurigana(rest)?;
+    Some(CommandResult::Okurigana { text, direction })
+}
+
+/// 先頭の「に対応する」の位置を返す（入れ子の「」を考慮）
+fn find_closing_bracket(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '「' => depth += 1,
+            '」' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
 /// 「...」の内容を抽出
 fn extract_bracket_content(s: &str) -> Option<&str> {
     let start = s.find('「')?;
@@ -330,6 +395,31 @@ fn extract_bracket_content(s: &str) -> Option<&str> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::{TCY_COMMANDS, WARIGAKI_COMMANDS};
+
+    #[test]
+    fn test_parse_okurigana_direction() {
+        let okurigana = |text: &str, direction| CommandResult::Okurigana {
+            text: text.to_string(),
+            direction,
+        };
+        assert_eq!(
+            parse_command("（ニ）"),
+            okurigana("ニ", RubyDirection::Right)
+        );
+        assert_eq!(
+            parse_command("右に（ニ）"),
+            okurigana("ニ", RubyDirection::Right)
+        );
+        assert_eq!(
+            parse_command("左に（ヲ）"),
+            okurigana("ヲ", RubyDirection::Left)
+        );
+        assert!(!matches!(
+            parse_command("左にニ"),
+            CommandResult::Okurigana { .. }
+        ));
+    }
 
     #[test]
     fn test_parse_style_bouten() {
@@ -428,6 +518,55 @@ mod tests {
         assert_eq!(parse_command("割り注終わり"), CommandResult::WarigakiEnd);
     }
 
+    #[test]
+    fn test_parse_warigaki_variants() {
+        for spelling in WARIGAKI_COMMANDS {
+            assert_eq!(
+                parse_command(spelling),
+                CommandResult::WarigakiStart,
+                "{spelling}"
+            );
+            assert_eq!(
+                parse_command(&format!("{spelling}終わり")),
+                CommandResult::WarigakiEnd,
+                "{spelling}終わり"
+            );
+        }
+        // 「割り注始め」は開始のみの表記で、対応する終了は「割り注終わり」
+        assert_eq!(parse_command("割り注始め"), CommandResult::WarigakiStart);
+        assert_eq!(parse_command("割注始め"), CommandResult::WarigakiStart);
+        assert_ne!(
+            parse_command("割り注始め終わり"),
+            CommandResult::WarigakiEnd
+        );
+        assert!(matches!(
+            parse_command("割り注の説明"),
+            CommandResult::Note(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_tcy_variants() {
+        for spelling in TCY_COMMANDS {
+            assert_eq!(
+                parse_command(spelling),
+                CommandResult::TcyStart,
+                "{spelling}"
+            );
+            assert_eq!(
+                parse_command(&format!("{spelling}終わり")),
+                CommandResult::TcyEnd,
+                "{spelling}終わり"
+            );
+        }
+        assert_eq!(
+            parse_command("「12」は縦中横き"),
+            CommandResult::InlineTcy {
+                target: "12".to_string()
+            }
+        );
+    }
+
     #[test]
     fn test_parse_unknown() {
         let result = parse_command("改ページ");
@@ -467,4 +606,90 @@ mod tests {
         let result = parse_command("地から3字上げ");
         assert_eq!(result, CommandResult::LineChitsuki { width: 3 });
     }
+
+    #[test]
+    fn test_parse_bold_italic_inline_range() {
+        for (command, style_type) in [("太字", StyleType::Bold), ("斜体", StyleType::Italic)] {
+            assert_eq!(
+                parse_command(command),
+                CommandResult::StyleStart { style_type }
+            );
+            assert_eq!(
+                parse_command(&format!("{command}終わり")),
+                CommandResult::StyleEnd { style_type }
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_bold_italic_block() {
+        for (command, block_type) in [("太字", BlockType::Futoji), ("斜体", BlockType::Shatai)]
+        {
+            match parse_command(&format!("ここから{command}")) {
+                CommandResult::BlockStart {
+                    block_type: actual,
+                    params,
+                } => {
+                    assert_eq!(actual, block_type);
+                    assert!(params.is_block);
+                }
+                other => panic!("unexpected result for {command}: {other:?}"),
+            }
+            assert_eq!(
+                parse_command(&format!("ここで{command}終わり")),
+                CommandResult::BlockEnd { block_type }
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_character_commands() {
+        assert_eq!(
+            parse_command("四分アキ"),
+            CommandResult::Character("\u{2005}".to_string())
+        );
+        assert_eq!(
+            parse_command("全角ダッシュ"),
+            CommandResult::Character("―".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_line_break() {
+        assert_eq!(parse_command("改行"), CommandResult::LineBreak);
+        // 改行天付きはぶら下げの指定
+        assert!(matches!(
+            parse_command("ここから改行天付き、折り返して2字下げ"),
+            CommandResult::BlockStart { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_nibai() {
+        assert_eq!(
+            parse_command("二倍角"),
+            CommandResult::StyleStart {
+                style_type: StyleType::Nibai
+            }
+        );
+        assert_eq!(
+            parse_command("「―」は二倍角"),
+            CommandResult::Style {
+                target: "―".to_string(),
+                connector: "は".to_string(),
+                style_type: StyleType::Nibai,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_side_note_with_gaiji_target() {
+        assert_eq!(
+            parse_command("「※［＃「半濁点」、U+309A］き」に「・」の傍記"),
+            CommandResult::SideNote {
+                target: "※［＃「半濁点」、U+309A］き".to_string(),
+                annotation: "・".to_string(),
+            }
+        );
+    }
 }
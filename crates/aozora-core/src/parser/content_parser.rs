@@ -3,6 +3,7 @@
 //! 画像、返り点、送り仮名などの特殊コマンドを解析します。
 
 use super::command_parser::CommandResult;
+use super::utils::extract_number;
 
 /// 画像コマンドを解析
 pub fn try_parse_image(content: &str) -> Option<CommandResult> {
@@ -106,23 +107,108 @@ pub fn is_kaeriten(content: &str) -> bool {
     content.chars().all(|c| KAERITEN_CHARS.contains(&c))
 }
 
+/// 文字に置き換えるコマンドと対応する文字
+///
+/// | コマンド | 文字 |
+/// |----------|------|
+/// | 全角アキ | U+3000 IDEOGRAPHIC SPACE |
+/// | 二分アキ | U+2002 EN SPACE |
+/// | 四分アキ | U+2005 FOUR-PER-EM SPACE |
+/// | 全角ダッシュ | U+2015 HORIZONTAL BAR（青空文庫の「―」） |
+const CHARACTER_COMMANDS: [(&str, &str); 4] = [
+    ("全角アキ", "\u{3000}"),
+    ("二分アキ", "\u{2002}"),
+    ("四分アキ", "\u{2005}"),
+    ("全角ダッシュ", "\u{2015}"),
+];
+
+/// 空白（アキ）・ダッシュなど、文字に置き換えるコマンドを解析
+pub fn try_parse_character(content: &str) -> Option<String> {
+    CHARACTER_COMMANDS
+        .iter()
+        .find(|(command, _)| *command == content)
+        .map(|(_, text)| text.to_string())
+}
+
 /// 訓点送り仮名を解析
 pub fn try_parse_okurigana(content: &str) -> Option<String> {
     // （...）形式をチェック
     if content.starts_with('（') && content.ends_with('）') {
         let inner = &content['（'.len_utf8()..content.len() - '）'.len_utf8()];
         let char_count = inner.chars().count();
-        if char_count >= 1 && char_count <= 10 && !inner.is_empty() {
+        if (1..=10).contains(&char_count) && !inner.is_empty() {
             return Some(inner.to_string());
         }
     }
     None
 }
 
+/// 底本の位置（ページ番号・丁付け）を解析
+///
+/// `12ページ`・`ページ番号12`・`丁付け十二ウ`・`丁付け十二ウ、24ページ` の形式を受け付けます。
+pub fn try_parse_position(content: &str) -> Option<CommandResult> {
+    if let Some(page) = parse_page_number(content) {
+        return Some(CommandResult::Position {
+            page: Some(page),
+            chou: None,
+        });
+    }
+
+    let rest = content.strip_prefix("丁付け")?.trim_start_matches('：');
+    let (chou, page) = match rest.split_once('、') {
+        Some((chou, page)) => (chou, Some(parse_page_number(page)?)),
+        None => (rest, None),
+    };
+    if chou.is_empty() {
+        return None;
+    }
+    Some(CommandResult::Position {
+        page,
+        chou: Some(chou.to_string()),
+    })
+}
+
+/// `12ページ`・`ページ番号12` からページ番号を取り出す
+fn parse_page_number(s: &str) -> Option<u32> {
+    let digits = s.strip_suffix("ページ").or_else(|| {
+        s.strip_prefix("ページ番号")
+            .map(|n| n.trim_start_matches('：'))
+    })?;
+    let is_digit = |c: char| c.is_ascii_digit() || ('０'..='９').contains(&c);
+    if digits.is_empty() || !digits.chars().all(is_digit) {
+        return None;
+    }
+    extract_number(digits)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_try_parse_position() {
+        let position = |page: Option<u32>, chou: Option<&str>| {
+            Some(CommandResult::Position {
+                page,
+                chou: chou.map(str::to_string),
+            })
+        };
+        assert_eq!(try_parse_position("12ページ"), position(Some(12), None));
+        assert_eq!(try_parse_position("１２ページ"), position(Some(12), None));
+        assert_eq!(try_parse_position("ページ番号：3"), position(Some(3), None));
+        assert_eq!(
+            try_parse_position("丁付け十二ウ"),
+            position(None, Some("十二ウ"))
+        );
+        assert_eq!(
+            try_parse_position("丁付け：十二ウ、24ページ"),
+            position(Some(24), Some("十二ウ"))
+        );
+        assert_eq!(try_parse_position("改ページ"), None);
+        assert_eq!(try_parse_position("丁付け"), None);
+        assert_eq!(try_parse_position("丁付け一オ、次ページ"), None);
+    }
+
     #[test]
     fn test_is_kaeriten() {
         assert!(is_kaeriten("一"));
@@ -134,6 +220,16 @@ mod tests {
         assert!(!is_kaeriten("一二三四五"));
     }
 
+    #[test]
+    fn test_try_parse_character() {
+        assert_eq!(
+            try_parse_character("四分アキ"),
+            Some("\u{2005}".to_string())
+        );
+        assert_eq!(try_parse_character("全角ダッシュ"), Some("―".to_string()));
+        assert_eq!(try_parse_character("三分アキ"), None);
+    }
+
     #[test]
     fn test_try_parse_okurigana() {
         assert_eq!(try_parse_okurigana("（ノ）"), Some("ノ".to_string()));
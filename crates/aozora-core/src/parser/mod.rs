@@ -10,13 +10,19 @@ pub mod reference_resolver;
 pub mod ruby_parser;
 mod utils;
 
+use std::fmt;
+
+use crate::delimiters::{RUBY_BEGIN, RUBY_END};
 use crate::node::{
     BlockParams, BlockType, FontSizeType, MidashiLevel, MidashiStyle, Node, RubyDirection,
 };
 use crate::token::Token;
 
 pub use command_parser::{parse_command, CommandResult};
-pub use reference_resolver::{resolve_inline_ruby, resolve_references};
+pub use reference_resolver::{
+    resolve_inline_ruby, resolve_inline_ruby_with_options, resolve_references,
+    resolve_references_with_options,
+};
 pub use ruby_parser::extract_ruby_base;
 
 /// トークン列をノード列にパース
@@ -45,26 +51,202 @@ pub fn parse(tokens: &[Token]) -> Vec<Node> {
     nodes
 }
 
-/// 直前のノードがテキストで `（` で終わるかチェック
-fn has_open_paren_before(nodes: &[Node]) -> bool {
-    nodes.last().map_or(false, |node| {
-        if let Node::Text(s) = node {
-            s.ends_with('（')
-        } else {
-            false
+/// パースオプション
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// 解釈できないコマンドを注記にせずエラーとして報告する
+    pub strict: bool,
+    /// `｜` のないルビの親文字を文字種別で自動抽出する（無効の場合は直前の1文字）
+    pub auto_ruby_base: bool,
+    /// `｜` のない `《》` の中身がこの文字数を超えるか、文章らしい記号を含む場合に、
+    /// ルビではなく文字どおりの括弧として扱う（`None` の場合は常にルビ）
+    pub literal_ruby_threshold: Option<usize>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            auto_ruby_base: true,
+            literal_ruby_threshold: None,
         }
-    })
+    }
 }
 
-/// 直後のトークンがテキストで `）` で始まるかチェック
-fn has_close_paren_after(tokens: &[Token], current_index: usize) -> bool {
-    tokens.get(current_index + 1).map_or(false, |token| {
-        if let Token::Text(s) = token {
-            s.starts_with('）')
-        } else {
-            false
+impl ParseOptions {
+    /// 新しいオプションを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 厳格モードを設定
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// ルビ親文字の自動抽出を設定
+    ///
+    /// 無効にすると、`｜` のない `《》` は直前の1文字だけを親文字とします。
+    /// `｜` で親文字を明示している文書で、自動抽出が親文字を取りすぎるのを防ぎます。
+    pub fn with_auto_ruby_base(mut self, enabled: bool) -> Self {
+        self.auto_ruby_base = enabled;
+        self
+    }
+
+    /// 括弧として扱う `《》` の判定しきい値を設定
+    ///
+    /// 古い文書では `《》` がルビではなく二重山括弧として使われていることがあります。
+    /// しきい値を設定すると、`｜` のない `《》` の中身が `threshold` 文字を超えるか、
+    /// 空白や句読点などの読みには現れない記号を含む場合に、`《…》` をそのまま本文とします。
+    pub fn with_literal_ruby_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.literal_ruby_threshold = threshold;
+        self
+    }
+}
+
+/// 厳格モードで検出された解釈できないコマンド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// コマンドのトークン位置
+    pub token_index: usize,
+    /// コマンドの内容（`［＃` と `］` を除く）
+    pub command: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown command: ［＃{}］", self.command)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// オプションを指定してトークン列をノード列にパース
+///
+/// 厳格モードでは、通常は注記（`Node::Note`）として出力される解釈できないコマンドを
+/// エラーとして集め、1つでもあれば `Err` で返します。
+/// 厳格モードでない場合は `Ok` で返します（既定のオプションでは [`parse`] と同じ結果）。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::tokenizer::tokenize;
+/// use aozora_core::parser::{parse_with_options, ParseOptions};
+///
+/// let tokens = tokenize("本文［＃改ページ］");
+/// let options = ParseOptions::new().with_strict(true);
+/// let errors = parse_with_options(&tokens, &options).unwrap_err();
+/// assert_eq!(errors[0].command, "改ページ");
+/// ```
+pub fn parse_with_options(
+    tokens: &[Token],
+    options: &ParseOptions,
+) -> Result<Vec<Node>, Vec<ParseError>> {
+    let mut nodes = Vec::new();
+    let mut errors = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if let Token::Command { content } = token {
+            if options.strict
+                && matches!(
+                    parse_command(content),
+                    CommandResult::Note(_) | CommandResult::Unknown(_)
+                )
+            {
+                errors.push(ParseError {
+                    token_index: i,
+                    command: content.clone(),
+                });
+                continue;
+            }
+        }
+        if let Token::Ruby { children } = token {
+            if options
+                .literal_ruby_threshold
+                .is_some_and(|threshold| is_literal_quote(children, threshold))
+            {
+                nodes.push(Node::text(RUBY_BEGIN.to_string()));
+                nodes.extend(parse_tokens(children));
+                nodes.push(Node::text(RUBY_END.to_string()));
+                continue;
+            }
         }
-    })
+        let parsed = parse_token_with_context(token, &nodes, tokens, i);
+        nodes.extend(parsed);
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // 前方参照の解決
+    resolve_references_with_options(&mut nodes, options);
+
+    Ok(nodes)
+}
+
+/// `《》` の中身がルビの読みではなく引用文らしいかチェック
+fn is_literal_quote(children: &[Token], threshold: usize) -> bool {
+    let text: String = children
+        .iter()
+        .filter_map(|token| match token {
+            Token::Text(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect();
+    text.chars().count() > threshold
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '、' | '。' | '「' | '」' | '！' | '？'))
+}
+
+/// 直前のテキストが `（` で終わるかチェック
+///
+/// 間にある装飾開始などの文字を出力しないノードは読み飛ばします。
+fn has_open_paren_before(nodes: &[Node]) -> bool {
+    nodes
+        .iter()
+        .rev()
+        .find(|node| match node {
+            Node::BlockStart { .. } | Node::BlockEnd { .. } => false,
+            Node::Text(s) => !s.is_empty(),
+            _ => true,
+        })
+        .is_some_and(|node| matches!(node, Node::Text(s) if s.ends_with('（')))
+}
+
+/// 直後のテキストが `）` で始まるかチェック
+///
+/// 間にある装飾終了や前方参照などの文字を出力しないコマンドは読み飛ばします。
+fn has_close_paren_after(tokens: &[Token], current_index: usize) -> bool {
+    tokens
+        .iter()
+        .skip(current_index + 1)
+        .find(|token| match token {
+            Token::Command { content } => !is_zero_width_command(content),
+            Token::Text(s) => !s.is_empty(),
+            _ => true,
+        })
+        .is_some_and(|token| matches!(token, Token::Text(s) if s.starts_with('）')))
+}
+
+/// その位置に文字を出力しないコマンドかどうか
+fn is_zero_width_command(content: &str) -> bool {
+    matches!(
+        parse_command(content),
+        CommandResult::Style { .. }
+            | CommandResult::Midashi { .. }
+            | CommandResult::FontSize { .. }
+            | CommandResult::InlineTcy { .. }
+            | CommandResult::InlineKeigakomi { .. }
+            | CommandResult::InlineYokogumi { .. }
+            | CommandResult::InlineCaption { .. }
+            | CommandResult::BlockEnd { .. }
+            | CommandResult::StyleEnd { .. }
+            | CommandResult::TcyEnd
+            | CommandResult::CaptionEnd
+    )
 }
 
 /// コンテキスト付きでトークンをパース
@@ -251,7 +433,13 @@ fn parse_command_to_node(content: &str) -> Node {
 
         CommandResult::Kaeriten(s) => Node::Kaeriten(s),
 
-        CommandResult::Okurigana(s) => Node::Okurigana(s),
+        CommandResult::Okurigana { text, direction } => Node::Okurigana { text, direction },
+
+        CommandResult::Character(s) => Node::Text(s),
+
+        CommandResult::LineBreak => Node::LineBreak,
+
+        CommandResult::Position { page, chou } => Node::Position { page, chou },
 
         CommandResult::TcyStart => Node::BlockStart {
             block_type: BlockType::Tcy,
@@ -387,8 +575,10 @@ fn parse_command_to_node_with_context(
 
     match parse_command(content) {
         CommandResult::WarigakiStart => {
-            let mut params = BlockParams::default();
-            params.has_open_paren = has_open_paren_before(nodes);
+            let params = BlockParams {
+                has_open_paren: has_open_paren_before(nodes),
+                ..Default::default()
+            };
             Node::BlockStart {
                 block_type: BlockType::Warigaki,
                 params,
@@ -396,8 +586,10 @@ fn parse_command_to_node_with_context(
         }
 
         CommandResult::WarigakiEnd => {
-            let mut params = BlockParams::default();
-            params.has_close_paren = has_close_paren_after(tokens, current_index);
+            let params = BlockParams {
+                has_close_paren: has_close_paren_after(tokens, current_index),
+                ..Default::default()
+            };
             Node::BlockEnd {
                 block_type: BlockType::Warigaki,
                 params,
@@ -513,4 +705,174 @@ mod tests {
             panic!("Expected Gaiji node");
         }
     }
+
+    #[test]
+    fn test_parse_position() {
+        let tokens = tokenize("［＃丁付け十二ウ、24ページ］本文［＃25ページ］続き");
+        let nodes = parse(&tokens);
+        assert_eq!(
+            nodes,
+            vec![
+                Node::Position {
+                    page: Some(24),
+                    chou: Some("十二ウ".to_string()),
+                },
+                Node::text("本文"),
+                Node::Position {
+                    page: Some(25),
+                    chou: None,
+                },
+                Node::text("続き"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_strict_reports_unknown_commands() {
+        let tokens = tokenize("本文［＃改ページ］続き［＃ほげ］［＃「本文」に傍点］");
+        let options = ParseOptions::new().with_strict(true);
+        let errors = parse_with_options(&tokens, &options).unwrap_err();
+
+        let commands: Vec<&str> = errors.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["改ページ", "ほげ"]);
+        assert_eq!(errors[0].token_index, 1);
+        assert_eq!(errors[0].to_string(), "unknown command: ［＃改ページ］");
+    }
+
+    #[test]
+    fn test_parse_lenient_keeps_unknown_commands_as_notes() {
+        let tokens = tokenize("本文［＃改ページ］");
+        let nodes = parse_with_options(&tokens, &ParseOptions::default()).unwrap();
+        assert_eq!(nodes, parse(&tokens));
+        assert_eq!(nodes[1], Node::Note("改ページ".to_string()));
+    }
+
+    #[test]
+    fn test_parse_strict_without_errors() {
+        let tokens = tokenize("本文［＃「本文」に傍点］");
+        let options = ParseOptions::new().with_strict(true);
+        assert_eq!(parse_with_options(&tokens, &options), Ok(parse(&tokens)));
+    }
+
+    #[test]
+    fn test_parse_auto_ruby_base() {
+        let tokens = tokenize("前の漢字《よみ》");
+        let ruby = |base: &str| Node::Ruby {
+            children: vec![Node::text(base)],
+            ruby: vec![Node::text("よみ")],
+            direction: RubyDirection::Right,
+        };
+
+        // 既定: 文字種別で親文字を抽出
+        let greedy = parse_with_options(&tokens, &ParseOptions::default()).unwrap();
+        assert_eq!(greedy, vec![Node::text("前の"), ruby("漢字")]);
+        assert_eq!(greedy, parse(&tokens));
+
+        // 自動抽出なし: 直前の1文字のみ
+        let options = ParseOptions::new().with_auto_ruby_base(false);
+        let single = parse_with_options(&tokens, &options).unwrap();
+        assert_eq!(single, vec![Node::text("前の漢"), ruby("字")]);
+
+        // ｜で明示した親文字は変わらない
+        let tokens = tokenize("前の｜漢字《よみ》");
+        assert_eq!(
+            parse_with_options(&tokens, &options).unwrap(),
+            vec![Node::text("前の"), ruby("漢字")]
+        );
+    }
+
+    #[test]
+    fn test_parse_literal_ruby_threshold() {
+        let options = ParseOptions::new().with_literal_ruby_threshold(Some(10));
+        let ruby = Node::Ruby {
+            children: vec![Node::text("漢字")],
+            ruby: vec![Node::text("かんじ")],
+            direction: RubyDirection::Right,
+        };
+
+        // 短い読みはルビのまま
+        let tokens = tokenize("漢字《かんじ》");
+        assert_eq!(parse_with_options(&tokens, &options).unwrap(), vec![ruby]);
+
+        // 長い中身は括弧のまま本文に残す
+        let tokens = tokenize("彼は《長い引用文がここに入る》と言った");
+        let nodes = parse_with_options(&tokens, &options).unwrap();
+        let text: String = nodes
+            .iter()
+            .map(|node| match node {
+                Node::Text(s) => s.as_str(),
+                _ => panic!("Expected only Text nodes: {node:?}"),
+            })
+            .collect();
+        assert_eq!(text, "彼は《長い引用文がここに入る》と言った");
+
+        // 短くても句読点や空白を含めば括弧
+        let tokens = tokenize("彼は《はい、そう》と言った");
+        assert!(!parse_with_options(&tokens, &options)
+            .unwrap()
+            .iter()
+            .any(|node| matches!(node, Node::Ruby { .. })));
+
+        // ｜で親文字を明示したルビはしきい値の対象外
+        let tokens = tokenize("｜漢字《とても長いふりがなをつけたもの》");
+        assert!(matches!(
+            parse_with_options(&tokens, &options).unwrap()[..],
+            [Node::Ruby { .. }]
+        ));
+
+        // 既定では常にルビ
+        let tokens = tokenize("彼は《長い引用文がここに入る》と言った");
+        assert!(parse_with_options(&tokens, &ParseOptions::default())
+            .unwrap()
+            .iter()
+            .any(|node| matches!(node, Node::Ruby { .. })));
+    }
+
+    fn warigaki_parens(input: &str) -> (bool, bool) {
+        let nodes = parse(&tokenize(input));
+        let open = nodes.iter().find_map(|node| match node {
+            Node::BlockStart {
+                block_type: BlockType::Warigaki,
+                params,
+            } => Some(params.has_open_paren),
+            _ => None,
+        });
+        let close = nodes.iter().find_map(|node| match node {
+            Node::BlockEnd {
+                block_type: BlockType::Warigaki,
+                params,
+            } => Some(params.has_close_paren),
+            _ => None,
+        });
+        (open.unwrap(), close.unwrap())
+    }
+
+    #[test]
+    fn test_warigaki_parens_adjacent() {
+        assert_eq!(
+            warigaki_parens("前（［＃割り注］注［＃割り注終わり］）後"),
+            (true, true)
+        );
+        assert_eq!(
+            warigaki_parens("［＃割り注］注［＃割り注終わり］後"),
+            (false, false)
+        );
+    }
+
+    #[test]
+    fn test_warigaki_parens_across_commands() {
+        assert_eq!(
+            warigaki_parens("前（［＃割り注］注記［＃割り注終わり］［＃「記」に傍点］）後"),
+            (true, true)
+        );
+        assert_eq!(
+            warigaki_parens("前（［＃太字］［＃割り注］注［＃割り注終わり］［＃太字終わり］）後"),
+            (true, true)
+        );
+        // 文字を出力するコマンドをまたいだ括弧は対応させない
+        assert_eq!(
+            warigaki_parens("（［＃割り注］注［＃割り注終わり］［＃全角アキ］）"),
+            (true, false)
+        );
+    }
 }
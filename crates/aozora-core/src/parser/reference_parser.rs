@@ -2,7 +2,7 @@
 //!
 //! 「対象」に/は/の 装飾 形式のコマンドを解析します。
 
-use crate::node::{FontSizeType, MidashiLevel, MidashiStyle, StyleType};
+use crate::node::{FontSizeType, MidashiLevel, MidashiStyle, StyleType, TCY_COMMANDS};
 
 use super::command_parser::CommandResult;
 
@@ -120,10 +120,14 @@ fn parse_connector<'a>(_target: &str, rest: &'a str) -> Option<(&'static str, &'
 
 /// インライン要素（縦中横、罫囲み、横組み、キャプション）を解析
 fn try_parse_inline_element(target: &str, spec: &str) -> Option<CommandResult> {
-    match spec {
-        "縦中横" => Some(CommandResult::InlineTcy {
+    // 縦中横（古い表記を含む）
+    if TCY_COMMANDS.contains(&spec) {
+        return Some(CommandResult::InlineTcy {
             target: target.to_string(),
-        }),
+        });
+    }
+
+    match spec {
         "罫囲み" => Some(CommandResult::InlineKeigakomi {
             target: target.to_string(),
         }),
@@ -3,24 +3,38 @@
 //! 青空文庫形式の「〇〇」に傍点 のようなパターンを解決します。
 //! これらのコマンドは前方のテキストを参照し、装飾を適用します。
 
+use crate::char_type::count_graphemes;
 use crate::node::{
     BlockType, FontSizeType, MidashiLevel, MidashiStyle, Node, RubyDirection, StyleType,
 };
-use crate::parser::ruby_parser::extract_ruby_base_from_nodes;
+use crate::parser::ruby_parser::{
+    split_ruby_base_from_nodes, split_single_ruby_base_from_nodes, RubyBaseSplit,
+};
+use crate::parser::ParseOptions;
 use crate::tokenizer::tokenize;
 
 /// ノード列の前方参照を解決
 ///
 /// ルビの親文字抽出と、「〇〇」に傍点 形式の装飾コマンドを解決します。
 pub fn resolve_references(nodes: &mut Vec<Node>) {
+    resolve_references_with_options(nodes, &ParseOptions::default());
+}
+
+/// オプションを指定してノード列の前方参照を解決
+///
+/// ルビの親文字の抽出方法は [`ParseOptions::auto_ruby_base`] に従います。
+pub fn resolve_references_with_options(nodes: &mut Vec<Node>, options: &ParseOptions) {
     // 1. ルビの親文字を解決
-    resolve_ruby_bases(nodes);
+    resolve_ruby_bases(nodes, ruby_base_extractor(options));
 
     // 2. 注記付き範囲を解決（BlockStart/BlockEnd → Ruby）
     resolve_annotation_ranges(nodes);
 
     // 3. 装飾の前方参照を解決
     resolve_style_references(nodes);
+
+    // 4. 改行位置の指定された割り注を解決（BlockStart/BlockEnd → Warigaki）
+    resolve_warigaki(nodes);
 }
 
 /// 行内でのルビ親文字解決
@@ -28,6 +42,14 @@ pub fn resolve_references(nodes: &mut Vec<Node>) {
 /// 「漢字《かんじ》」形式のルビの親文字を解決します。
 /// 外字ノードも漢字として親文字に含めます。
 pub fn resolve_inline_ruby(nodes: &mut Vec<Node>) {
+    resolve_inline_ruby_with_options(nodes, &ParseOptions::default());
+}
+
+/// オプションを指定して行内でのルビ親文字を解決
+///
+/// 親文字の抽出方法は [`ParseOptions::auto_ruby_base`] に従います。
+pub fn resolve_inline_ruby_with_options(nodes: &mut Vec<Node>, options: &ParseOptions) {
+    let extract_base = ruby_base_extractor(options);
     let mut i = 0;
     while i < nodes.len() {
         if let Node::Ruby {
@@ -41,20 +63,14 @@ pub fn resolve_inline_ruby(nodes: &mut Vec<Node>) {
                 let direction_clone = *direction;
 
                 // 直前のノード列から親文字を抽出（外字も含む）
-                let preceding_nodes: Vec<Node> = nodes[..i].to_vec();
-                if let Some((remaining, base)) = extract_ruby_base_from_nodes(&preceding_nodes) {
-                    // 残りのノード数を計算
-                    let nodes_to_remove = preceding_nodes.len() - remaining.len();
-
-                    // 前半を残りのノードで置き換え
-                    let start_idx = i - nodes_to_remove;
-                    nodes.splice(start_idx..i, std::iter::empty());
-
-                    // 新しいインデックスを計算
-                    let new_i = start_idx;
-
-                    // 前半部分を挿入
-                    nodes.splice(..new_i, remaining.into_iter());
+                if let Some(RubyBaseSplit {
+                    end,
+                    remaining,
+                    base,
+                }) = extract_base(&nodes[..i])
+                {
+                    // 親文字になった部分を残りの部分で置き換え
+                    nodes.splice(end..i, remaining);
 
                     // Rubyノードを更新（インデックスが変わっているので再計算）
                     let ruby_idx = nodes
@@ -76,8 +92,20 @@ pub fn resolve_inline_ruby(nodes: &mut Vec<Node>) {
     }
 }
 
+/// ルビ親文字の抽出関数
+type RubyBaseExtractor = fn(&[Node]) -> Option<RubyBaseSplit>;
+
+/// オプションに応じたルビ親文字の抽出関数を選ぶ
+fn ruby_base_extractor(options: &ParseOptions) -> RubyBaseExtractor {
+    if options.auto_ruby_base {
+        split_ruby_base_from_nodes
+    } else {
+        split_single_ruby_base_from_nodes
+    }
+}
+
 /// ルビの親文字を解決
-fn resolve_ruby_bases(nodes: &mut Vec<Node>) {
+fn resolve_ruby_bases(nodes: &mut Vec<Node>, extract_base: RubyBaseExtractor) {
     let mut i = 0;
     while i < nodes.len() {
         // 親文字が空のRubyノードを探す
@@ -90,17 +118,15 @@ fn resolve_ruby_bases(nodes: &mut Vec<Node>) {
             if children.is_empty() && !ruby.is_empty() {
                 // 直前のノードから親文字を抽出
                 if i > 0 {
-                    let preceding_nodes: Vec<Node> = nodes[..i].to_vec();
-                    if let Some((remaining, base)) = extract_ruby_base_from_nodes(&preceding_nodes)
+                    if let Some(RubyBaseSplit {
+                        end,
+                        remaining,
+                        base,
+                    }) = extract_base(&nodes[..i])
                     {
-                        // 直前のノードを更新
-                        let to_remove = i - (preceding_nodes.len() - remaining.len());
-
-                        // 残りのノードで前半を置き換え
-                        nodes.splice(..i, remaining.into_iter());
-
-                        // 新しいインデックスを計算
-                        let new_i = nodes.len() - (nodes.len() - to_remove);
+                        // 親文字になった部分を残りの部分で置き換え
+                        let new_i = end + remaining.len();
+                        nodes.splice(end..i, remaining);
 
                         // Rubyノードを更新
                         if let Some(Node::Ruby { children: c, .. }) = nodes.get_mut(new_i) {
@@ -130,11 +156,11 @@ fn resolve_annotation_ranges(nodes: &mut Vec<Node>) {
                 // 対応する終了を探す
                 let mut end_idx = None;
                 let mut annotation = None;
-                for j in (i + 1)..nodes.len() {
+                for (j, node) in nodes.iter().enumerate().skip(i + 1) {
                     if let Node::BlockEnd {
                         block_type: bt,
                         params,
-                    } = &nodes[j]
+                    } = node
                     {
                         if (*bt == BlockType::AnnotationRange && !is_left)
                             || (*bt == BlockType::LeftAnnotationRange && is_left)
@@ -166,7 +192,7 @@ fn resolve_annotation_ranges(nodes: &mut Vec<Node>) {
                         });
 
                         // 範囲を新しいノード列で置き換え
-                        nodes.splice(i..=end_idx, new_nodes.into_iter());
+                        nodes.splice(i..=end_idx, new_nodes);
                     } else {
                         // 通常の注記付きはRubyとして出力
                         let new_node = Node::Ruby {
@@ -186,8 +212,67 @@ fn resolve_annotation_ranges(nodes: &mut Vec<Node>) {
     }
 }
 
+/// 割り注の改行位置を示すノードかどうか
+///
+/// 割り注内の `｜` または `［＃改行］` を上段と下段の区切りとして扱います。
+fn is_warigaki_break(node: &Node) -> bool {
+    match node {
+        Node::Text(text) => text == "｜",
+        Node::LineBreak => true,
+        _ => false,
+    }
+}
+
+/// 改行位置の指定された割り注を解決（BlockStart/BlockEnd → Warigaki）
+///
+/// `［＃割り注］上段｜下段［＃割り注終わり］` を上段・下段に分けた `Warigaki` ノードに変換します。
+/// 各段にはルビや外字などのインラインノードをそのまま含めます。
+/// 改行位置の指定がない割り注はブロックのまま残します。
+fn resolve_warigaki(nodes: &mut Vec<Node>) {
+    let mut i = 0;
+    while i < nodes.len() {
+        if matches!(
+            &nodes[i],
+            Node::BlockStart {
+                block_type: BlockType::Warigaki,
+                ..
+            }
+        ) {
+            let end_idx = nodes[(i + 1)..]
+                .iter()
+                .position(|n| {
+                    matches!(
+                        n,
+                        Node::BlockEnd {
+                            block_type: BlockType::Warigaki,
+                            ..
+                        }
+                    )
+                })
+                .map(|offset| i + 1 + offset);
+
+            if let Some(end_idx) = end_idx {
+                let inner = &nodes[(i + 1)..end_idx];
+                if let Some(split) = inner.iter().position(is_warigaki_break) {
+                    let upper = inner[..split].to_vec();
+                    let lower = inner[(split + 1)..].to_vec();
+                    nodes.splice(
+                        i..=end_idx,
+                        std::iter::once(Node::Warigaki { upper, lower }),
+                    );
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
 /// 装飾の前方参照を解決
 fn resolve_style_references(nodes: &mut Vec<Node>) {
+    // 解決済みの範囲より前のノードのプレーンテキストを連結したもの
+    // （`offsets[k]` はノード k の開始位置で、`offsets.len()` 個のノードまでを含む）
+    let mut preceding_text = String::new();
+    let mut offsets: Vec<usize> = Vec::new();
     let mut i = 0;
     while i < nodes.len() {
         if let Node::UnresolvedReference {
@@ -200,10 +285,28 @@ fn resolve_style_references(nodes: &mut Vec<Node>) {
             let spec_clone = spec.clone();
             let connector_clone = connector.clone();
 
+            // 参照より前のテキストの連結を更新
+            if offsets.len() > i {
+                preceding_text.truncate(offsets[i]);
+                offsets.truncate(i);
+            }
+            while offsets.len() < i {
+                offsets.push(preceding_text.len());
+                preceding_text.push_str(&extract_plain_text(&nodes[offsets.len() - 1]));
+            }
+
             // 前方のノードから対象テキストを探す
-            if let Some((_, found_node_idx, split_info)) =
-                find_target_in_preceding(&nodes[..i], &target_clone)
-            {
+            // どの一致も前方のテキストを連結したものの一部なので、含まれなければノードごとには探さない
+            let found = if preceding_text.contains(&target_clone) {
+                let texts = NodeTexts {
+                    text: &preceding_text,
+                    offsets: &offsets,
+                };
+                find_target_in_preceding(&nodes[..i], &texts, &target_clone)
+            } else {
+                None
+            };
+            if let Some((start_idx, found_node_idx, split_info)) = found {
                 // 解決種類を決定
                 if let Some(kind) = ResolvedKind::from_spec(&spec_clone) {
                     apply_resolution(
@@ -214,12 +317,23 @@ fn resolve_style_references(nodes: &mut Vec<Node>) {
                         &target_clone,
                         &kind,
                     );
+                    // 組み替えたノード以降のテキストは次の参照で連結し直す
+                    if offsets.len() > start_idx {
+                        preceding_text.truncate(offsets[start_idx]);
+                        offsets.truncate(start_idx);
+                    }
                     continue;
                 }
             }
 
             // 解決できなかった場合はNoteノードに変換
-            nodes[i] = Node::Note(format!("「{target_clone}」{connector_clone}{spec_clone}"));
+            // 左側の装飾は装飾名にも「左に」が付いているため重複させない
+            let spec_text = if connector_clone.ends_with("左に") {
+                spec_clone.strip_prefix("左に").unwrap_or(&spec_clone)
+            } else {
+                &spec_clone
+            };
+            nodes[i] = Node::Note(format!("「{target_clone}」{connector_clone}{spec_text}"));
         }
         i += 1;
     }
@@ -250,7 +364,7 @@ fn apply_resolution(
             if !after.is_empty() {
                 new_nodes.push(Node::text(&after));
             }
-            nodes.splice(found_node_idx..found_node_idx + 1, new_nodes.into_iter());
+            nodes.splice(found_node_idx..found_node_idx + 1, new_nodes);
             let adjustment =
                 if before.is_empty() { 0 } else { 1 } + if after.is_empty() { 0 } else { 1 };
             let new_i = *i + adjustment;
@@ -268,11 +382,95 @@ fn apply_resolution(
                 nodes.remove(new_i);
             }
         }
+        SplitInfo::MultiNodeSplit {
+            start_idx,
+            end_idx,
+            before,
+            after,
+        } => {
+            // 両端のテキストノードから対象部分だけを取り出す（ルビなどの構造は保持）
+            let mut children: Vec<Node> = nodes[start_idx..=end_idx].to_vec();
+            if let Some(Node::Text(text)) = children.first_mut() {
+                text.replace_range(..before.len(), "");
+            }
+            if let Some(Node::Text(text)) = children.last_mut() {
+                text.truncate(text.len() - after.len());
+            }
+            let new_node = kind.create_node_with_children(children);
+
+            let mut new_nodes = Vec::new();
+            if !before.is_empty() {
+                new_nodes.push(Node::text(&before));
+            }
+            new_nodes.push(new_node);
+            if !after.is_empty() {
+                new_nodes.push(Node::text(&after));
+            }
+            let nodes_removed = end_idx - start_idx + 1;
+            let nodes_inserted = new_nodes.len();
+            nodes.splice(start_idx..=end_idx, new_nodes);
+            let new_i = *i + nodes_inserted - nodes_removed;
+            if new_i < nodes.len() {
+                nodes.remove(new_i);
+            }
+        }
+        SplitInfo::InsideNode { inner_idx, inner } => {
+            // 参照を見つかったノードの子の末尾に移して、子ノード列の中で解決する
+            let reference = nodes.remove(*i);
+            if let Some(children) = children_mut(&mut nodes[found_node_idx]) {
+                children.push(reference);
+                let mut inner_i = children.len() - 1;
+                apply_resolution(children, &mut inner_i, inner_idx, *inner, target, kind);
+            }
+        }
+    }
+}
+
+/// ノード列の各ノードのプレーンテキスト
+///
+/// 全ノードのプレーンテキストを連結したものと、各ノードの開始位置で表します。
+/// 連続したノードのテキストは連結したものの一部として取り出せます。
+struct NodeTexts<'a> {
+    text: &'a str,
+    offsets: &'a [usize],
+}
+
+impl<'a> NodeTexts<'a> {
+    /// ノード `start`〜`end` のテキストを連結したもの
+    fn range(&self, start: usize, end: usize) -> &'a str {
+        let end_pos = self
+            .offsets
+            .get(end + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        &self.text[self.offsets[start]..end_pos]
+    }
+
+    /// ノード `k` のテキスト
+    fn get(&self, k: usize) -> &'a str {
+        self.range(k, k)
+    }
+}
+
+/// ノード列のプレーンテキストを連結し、各ノードの開始位置とともに返す
+fn concat_plain_texts(nodes: &[Node]) -> (String, Vec<usize>) {
+    let mut text = String::new();
+    let mut offsets = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        offsets.push(text.len());
+        text.push_str(&extract_plain_text(node));
     }
+    (text, offsets)
 }
 
 /// 前方のノードから対象テキストを探す
-fn find_target_in_preceding(nodes: &[Node], target: &str) -> Option<(usize, usize, SplitInfo)> {
+///
+/// `texts` は `nodes` の各ノードのプレーンテキストです（呼び出し側で連結済みのものを使います）。
+fn find_target_in_preceding(
+    nodes: &[Node],
+    texts: &NodeTexts,
+    target: &str,
+) -> Option<(usize, usize, SplitInfo)> {
     // まず単一ノード内で探す（後ろから）
     for (i, node) in nodes.iter().enumerate().rev() {
         match node {
@@ -295,8 +493,9 @@ fn find_target_in_preceding(nodes: &[Node], target: &str) -> Option<(usize, usiz
             | Node::Yokogumi { .. }
             | Node::Caption { .. }
             | Node::Midashi { .. } => {
-                let content = extract_plain_text(node);
-                if content == target {
+                let content = texts.get(i);
+                // 見出しは見出しごと包まず、見出しの子ノードを対象にする
+                if content == target && !matches!(node, Node::Midashi { .. }) {
                     // ノード全体をラップ対象として返す
                     return Some((
                         i,
@@ -307,6 +506,26 @@ fn find_target_in_preceding(nodes: &[Node], target: &str) -> Option<(usize, usiz
                         },
                     ));
                 }
+                // 装飾済みの範囲や見出しの一部に重ねて装飾する場合（右と左の傍点など）は子ノードから探す
+                if content.contains(target) {
+                    if let Some((_, inner_idx, inner)) = children_of(node).and_then(|children| {
+                        let (text, offsets) = concat_plain_texts(children);
+                        let child_texts = NodeTexts {
+                            text: &text,
+                            offsets: &offsets,
+                        };
+                        find_target_in_preceding(children, &child_texts, target)
+                    }) {
+                        return Some((
+                            i,
+                            i,
+                            SplitInfo::InsideNode {
+                                inner_idx,
+                                inner: Box::new(inner),
+                            },
+                        ));
+                    }
+                }
             }
             _ => {}
         }
@@ -314,17 +533,19 @@ fn find_target_in_preceding(nodes: &[Node], target: &str) -> Option<(usize, usiz
 
     // 複数ノードにまたがる場合を探す
     // ノード列の末尾から連続したノードのプレーンテキストを結合して探す
+    // （両端のテキストノードは一部だけが対象に含まれていてもよい）
     for end_idx in (0..nodes.len()).rev() {
-        let mut combined = String::new();
+        let end_text = texts.get(end_idx);
+        // 両端を除いた間のノードのテキスト長
+        let mut inner_len = 0;
 
-        // 末尾から連結していく
+        // 末尾から連結していく（連結済みのテキストの一部を取り出すだけで、文字列は作らない）
         for start_idx in (0..=end_idx).rev() {
-            let text = extract_plain_text(&nodes[start_idx]);
-            combined = format!("{}{}", text, combined);
+            let start_text = texts.get(start_idx);
+            let combined = texts.range(start_idx, end_idx);
 
-            // 対象テキストが含まれていれば
-            if combined.contains(target) {
-                // 完全一致（連結テキスト == 対象）かチェック
+            if start_idx == end_idx {
+                // ルビなど子を持つノード1つの親文字と完全一致
                 if combined == target {
                     return Some((
                         start_idx,
@@ -332,18 +553,36 @@ fn find_target_in_preceding(nodes: &[Node], target: &str) -> Option<(usize, usiz
                         SplitInfo::MultiNodeExact { start_idx, end_idx },
                     ));
                 }
-                // 部分一致の場合、対象がノード境界に一致しているかチェック
-                if combined.ends_with(target) {
-                    // 末尾一致：前半のノードを分割する必要があるかも
-                    let prefix_len = combined.len() - target.len();
-                    if prefix_len == 0 {
-                        return Some((
-                            start_idx,
-                            end_idx,
-                            SplitInfo::MultiNodeExact { start_idx, end_idx },
-                        ));
+                continue;
+            }
+
+            if let Some((match_start, match_end)) = find_spanning_match(
+                combined,
+                target,
+                start_text.len(),
+                end_text.len(),
+                matches!(nodes[start_idx], Node::Text(_)),
+                matches!(nodes[end_idx], Node::Text(_)),
+            ) {
+                let split_info = if match_start == 0 && match_end == combined.len() {
+                    SplitInfo::MultiNodeExact { start_idx, end_idx }
+                } else {
+                    let end_offset = match_end - (combined.len() - end_text.len());
+                    SplitInfo::MultiNodeSplit {
+                        start_idx,
+                        end_idx,
+                        before: start_text[..match_start].to_string(),
+                        after: end_text[end_offset..].to_string(),
                     }
-                }
+                };
+                return Some((start_idx, end_idx, split_info));
+            }
+
+            // 間のノードだけで対象以上の長さになったら、これ以上連結しても一致しない
+            // （長い行で二乗の計算量にならないよう打ち切る）
+            inner_len += start_text.len();
+            if inner_len >= target.len() {
+                break;
             }
         }
     }
@@ -351,6 +590,58 @@ fn find_target_in_preceding(nodes: &[Node], target: &str) -> Option<(usize, usiz
     None
 }
 
+/// 先頭ノードで始まり末尾ノードで終わる一致位置を探す
+///
+/// 一致は先頭ノードと末尾ノードの両方にかかっている必要があります。
+/// 途中から（途中まで）の一致はそのノードがテキストノードの場合のみ許します。
+fn find_spanning_match(
+    combined: &str,
+    target: &str,
+    start_len: usize,
+    end_len: usize,
+    start_is_text: bool,
+    end_is_text: bool,
+) -> Option<(usize, usize)> {
+    let end_begin = combined.len() - end_len;
+    combined
+        .rmatch_indices(target)
+        .map(|(pos, _)| (pos, pos + target.len()))
+        .find(|&(match_start, match_end)| {
+            match_start < start_len
+                && match_end > end_begin
+                && (match_start == 0 || start_is_text)
+                && (match_end == combined.len() || end_is_text)
+        })
+}
+
+/// 参照の対象を探す子ノード列
+fn children_of(node: &Node) -> Option<&[Node]> {
+    match node {
+        Node::FontSize { children, .. }
+        | Node::Style { children, .. }
+        | Node::Tcy { children }
+        | Node::Keigakomi { children }
+        | Node::Yokogumi { children }
+        | Node::Caption { children }
+        | Node::Midashi { children, .. } => Some(children),
+        _ => None,
+    }
+}
+
+/// 参照の対象を探す子ノード列（変更用）
+fn children_mut(node: &mut Node) -> Option<&mut Vec<Node>> {
+    match node {
+        Node::FontSize { children, .. }
+        | Node::Style { children, .. }
+        | Node::Tcy { children }
+        | Node::Keigakomi { children }
+        | Node::Yokogumi { children }
+        | Node::Caption { children }
+        | Node::Midashi { children, .. } => Some(children),
+        _ => None,
+    }
+}
+
 /// ノードからプレーンテキストを抽出
 fn extract_plain_text(node: &Node) -> String {
     match node {
@@ -366,6 +657,8 @@ fn extract_plain_text(node: &Node) -> String {
         Node::Yokogumi { children } => children.iter().map(extract_plain_text).collect(),
         Node::Caption { children } => children.iter().map(extract_plain_text).collect(),
         Node::Midashi { children, .. } => children.iter().map(extract_plain_text).collect(),
+        // 外字は注記の対象でも外字表記のまま書かれる
+        Node::Gaiji { description, .. } => format!("※［＃{}］", description),
         _ => String::new(),
     }
 }
@@ -461,11 +754,11 @@ impl ResolvedKind {
                 direction: RubyDirection::Right,
             },
             ResolvedKind::SideNote { annotation } => {
-                // 親文字の文字数を数える
-                let char_count: usize = children.iter().map(|n| n.to_text().chars().count()).sum();
+                // 親文字の文字数を数える（結合文字が別の外字になっていても1文字とする）
+                let text: String = children.iter().map(|n| n.to_text()).collect();
+                let char_count = count_graphemes(&text);
                 // 注記を文字数分繰り返し、&nbsp;で区切る
-                let repeated: String = std::iter::repeat(annotation.as_str())
-                    .take(char_count.max(1))
+                let repeated: String = std::iter::repeat_n(annotation.as_str(), char_count.max(1))
                     .collect::<Vec<_>>()
                     .join("\u{00a0}"); // non-breaking space
                 Node::Ruby {
@@ -518,6 +811,18 @@ enum SplitInfo {
     Split { before: String, after: String },
     /// 複数ノードにまたがる完全一致
     MultiNodeExact { start_idx: usize, end_idx: usize },
+    /// 複数ノードにまたがり、両端のテキストノードの分割が必要
+    MultiNodeSplit {
+        start_idx: usize,
+        end_idx: usize,
+        before: String,
+        after: String,
+    },
+    /// 装飾・見出しなどの子ノード内で見つかった
+    InsideNode {
+        inner_idx: usize,
+        inner: Box<SplitInfo>,
+    },
 }
 
 /// 注記テキストをノード列にパース
@@ -570,6 +875,16 @@ fn parse_annotation_text(text: &str) -> Vec<Node> {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    /// ノード列のテキストを連結して前方のノードから対象テキストを探す
+    fn find_target(nodes: &[Node], target: &str) -> Option<(usize, usize, SplitInfo)> {
+        let (text, offsets) = concat_plain_texts(nodes);
+        let texts = NodeTexts {
+            text: &text,
+            offsets: &offsets,
+        };
+        find_target_in_preceding(nodes, &texts, target)
+    }
     use crate::node::RubyDirection;
 
     #[test]
@@ -595,6 +910,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolve_inline_ruby_without_auto_base() {
+        let ruby = Node::Ruby {
+            children: vec![],
+            ruby: vec![Node::text("よみ")],
+            direction: RubyDirection::Right,
+        };
+        let mut greedy = vec![Node::text("前の漢字"), ruby.clone()];
+        let mut single = greedy.clone();
+
+        resolve_inline_ruby(&mut greedy);
+        let options = ParseOptions::new().with_auto_ruby_base(false);
+        resolve_inline_ruby_with_options(&mut single, &options);
+
+        assert_eq!(greedy[0], Node::text("前の"));
+        assert!(
+            matches!(&greedy[1], Node::Ruby { children, .. } if children == &vec![Node::text("漢字")])
+        );
+        assert_eq!(single[0], Node::text("前の漢"));
+        assert!(
+            matches!(&single[1], Node::Ruby { children, .. } if children == &vec![Node::text("字")])
+        );
+    }
+
     #[test]
     fn test_resolve_inline_ruby_full_match() {
         let mut nodes = vec![
@@ -642,7 +981,7 @@ mod tests {
             Node::text("後の文"),
         ];
 
-        let result = find_target_in_preceding(&nodes, "重要");
+        let result = find_target(&nodes, "重要");
         assert!(result.is_some());
         let (_, idx, split) = result.unwrap();
         assert_eq!(idx, 1);
@@ -653,7 +992,7 @@ mod tests {
     fn test_find_target_split() {
         let nodes = vec![Node::text("これは重要なことだ")];
 
-        let result = find_target_in_preceding(&nodes, "重要");
+        let result = find_target(&nodes, "重要");
         assert!(result.is_some());
         let (_, idx, split) = result.unwrap();
         assert_eq!(idx, 0);
@@ -664,4 +1003,291 @@ mod tests {
             panic!("Expected Split");
         }
     }
+
+    #[test]
+    fn test_resolve_warigaki_with_ruby() {
+        let tokens = tokenize("［＃割り注］東京《とうきょう》｜都［＃割り注終わり］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 1);
+        if let Node::Warigaki { upper, lower } = &nodes[0] {
+            assert_eq!(upper.len(), 1);
+            assert!(matches!(&upper[0], Node::Ruby { children, .. }
+                if matches!(&children[0], Node::Text(s) if s == "東京")));
+            assert_eq!(lower, &vec![Node::text("都")]);
+        } else {
+            panic!("Expected Warigaki node");
+        }
+    }
+
+    #[test]
+    fn test_resolve_warigaki_with_line_break_command() {
+        let tokens = tokenize("［＃割り注］上［＃改行］下［＃割り注終わり］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(
+            nodes,
+            vec![Node::Warigaki {
+                upper: vec![Node::text("上")],
+                lower: vec![Node::text("下")],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resolve_warigaki_old_spellings() {
+        for input in [
+            "［＃割注］上｜下［＃割注終わり］",
+            "［＃わりちゅう］上｜下［＃わりちゅう終わり］",
+            "［＃割り注始め］上｜下［＃割り注終わり］",
+        ] {
+            let nodes = crate::parser::parse(&tokenize(input));
+            assert_eq!(
+                nodes,
+                vec![Node::Warigaki {
+                    upper: vec![Node::text("上")],
+                    lower: vec![Node::text("下")],
+                }],
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_warigaki_without_break() {
+        let tokens = tokenize("［＃割り注］注記［＃割り注終わり］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert!(matches!(
+            &nodes[0],
+            Node::BlockStart {
+                block_type: BlockType::Warigaki,
+                ..
+            }
+        ));
+    }
+
+    fn ruby_of(node: &Node) -> Option<(String, String)> {
+        match node {
+            Node::Ruby { children, ruby, .. } => Some((
+                children.iter().map(Node::to_text).collect(),
+                ruby.iter().map(Node::to_text).collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_style_across_ruby_keeps_ruby() {
+        let tokens = tokenize("東京《とうきょう》都［＃「東京都」に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 1);
+        if let Node::Style { children, .. } = &nodes[0] {
+            assert_eq!(children.len(), 2);
+            assert_eq!(
+                ruby_of(&children[0]),
+                Some(("東京".to_string(), "とうきょう".to_string()))
+            );
+            assert_eq!(children[1], Node::text("都"));
+        } else {
+            panic!("Expected Style node");
+        }
+    }
+
+    #[test]
+    fn test_resolve_style_across_ruby_with_surrounding_text() {
+        let tokens = tokenize("ここは東京《とうきょう》都です［＃「東京都」に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0], Node::text("ここは"));
+        if let Node::Style { children, .. } = &nodes[1] {
+            assert_eq!(children.len(), 2);
+            assert_eq!(
+                ruby_of(&children[0]),
+                Some(("東京".to_string(), "とうきょう".to_string()))
+            );
+            assert_eq!(children[1], Node::text("都"));
+        } else {
+            panic!("Expected Style node");
+        }
+        assert_eq!(nodes[2], Node::text("です"));
+    }
+
+    #[test]
+    fn test_resolve_style_spanning_text_into_ruby() {
+        let tokens = tokenize("大｜東京《とうきょう》［＃「大東京」に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 1);
+        if let Node::Style { children, .. } = &nodes[0] {
+            assert_eq!(children[0], Node::text("大"));
+            assert!(ruby_of(&children[1]).is_some());
+        } else {
+            panic!("Expected Style node");
+        }
+    }
+
+    fn style_of(node: &Node) -> Option<(StyleType, &[Node])> {
+        match node {
+            Node::Style {
+                style_type,
+                children,
+                ..
+            } => Some((*style_type, children.as_slice())),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_right_and_left_bouten_same_target() {
+        let tokens = tokenize("語［＃「語」に傍点］［＃「語」の左に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 1);
+        let (outer, children) = style_of(&nodes[0]).unwrap();
+        assert_eq!(outer, StyleType::SesameDotAfter);
+        let (inner, children) = style_of(&children[0]).unwrap();
+        assert_eq!(inner, StyleType::SesameDot);
+        assert_eq!(children, [Node::text("語")]);
+    }
+
+    #[test]
+    fn test_resolve_left_bouten_inside_styled_range() {
+        let tokens = tokenize("これは語句［＃「語句」に傍点］［＃「句」の左に傍点］です");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 3);
+        let (outer, children) = style_of(&nodes[1]).unwrap();
+        assert_eq!(outer, StyleType::SesameDot);
+        assert_eq!(children[0], Node::text("語"));
+        let (inner, inner_children) = style_of(&children[1]).unwrap();
+        assert_eq!(inner, StyleType::SesameDotAfter);
+        assert_eq!(inner_children, [Node::text("句")]);
+        assert_eq!(children.len(), 2);
+    }
+
+    #[test]
+    fn test_resolve_style_inside_midashi() {
+        let tokens = tokenize("第一章［＃「第一章」は大見出し］さらに［＃「第一章」に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 2);
+        let Node::Midashi { children, .. } = &nodes[0] else {
+            panic!("expected midashi: {nodes:?}");
+        };
+        let (style, inner) = style_of(&children[0]).unwrap();
+        assert_eq!(style, StyleType::SesameDot);
+        assert_eq!(inner, [Node::text("第一章")]);
+        assert_eq!(children.len(), 1);
+        assert_eq!(nodes[1], Node::text("さらに"));
+    }
+
+    #[test]
+    fn test_resolve_style_inside_part_of_midashi() {
+        let tokens = tokenize("第一章［＃「第一章」は中見出し］［＃「一」に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+
+        assert_eq!(nodes.len(), 1);
+        let Node::Midashi { children, .. } = &nodes[0] else {
+            panic!("expected midashi: {nodes:?}");
+        };
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0], Node::text("第"));
+        let (style, inner) = style_of(&children[1]).unwrap();
+        assert_eq!(style, StyleType::SesameDot);
+        assert_eq!(inner, [Node::text("一")]);
+        assert_eq!(children[2], Node::text("章"));
+    }
+
+    #[test]
+    fn test_resolve_tcy_inside_midashi() {
+        // 縦中横と見出しのどちらを先に指定しても、縦中横は見出しの中に入る
+        for line in [
+            "第１２章［＃「１２」は縦中横］［＃「第１２章」は大見出し］",
+            "第１２章［＃「第１２章」は大見出し］［＃「１２」は縦中横］",
+        ] {
+            let nodes = crate::parser::parse(&tokenize(line));
+
+            assert_eq!(nodes.len(), 1, "{line}");
+            let Node::Midashi { children, .. } = &nodes[0] else {
+                panic!("expected midashi: {nodes:?}");
+            };
+            assert_eq!(
+                children,
+                &[
+                    Node::text("第"),
+                    Node::Tcy {
+                        children: vec![Node::text("１２")],
+                    },
+                    Node::text("章"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_unresolved_left_style_note() {
+        let tokens = tokenize("本文［＃「語」の左に傍点］");
+        let nodes = crate::parser::parse(&tokens);
+        assert!(nodes.contains(&Node::Note("「語」の左に傍点".to_string())));
+    }
+
+    /// ルビの注記テキストを返す
+    fn side_note_ruby(node: &Node) -> Option<String> {
+        match node {
+            Node::Ruby { ruby, .. } => Some(ruby.iter().map(|n| n.to_text()).collect()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_side_note_with_combining_mark() {
+        // 基底文字と結合用半濁点が別々の外字になっている
+        let base = "※［＃「片仮名カ」、U+30AB］※［＃「半濁点」、U+309A］き";
+        let input = format!("{base}［＃「{base}」に「・」の傍記］");
+        let nodes = crate::parser::parse(&tokenize(&input));
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(side_note_ruby(&nodes[0]).unwrap(), "・\u{a0}・");
+    }
+
+    #[test]
+    fn test_resolve_many_references_in_one_line() {
+        // 同じ行に多くのルビと前方参照がある場合も、それぞれの対象に解決する
+        let line: String = (0..200)
+            .map(|i| {
+                format!(
+                    "第{i}の漢字《かんじ》を書く［＃「第{i}」に傍点］［＃「なし{i}」に傍点］字《じ》と文{i}［＃「字と文{i}」に傍線］"
+                )
+            })
+            .collect();
+        let nodes = crate::parser::parse(&tokenize(&line));
+
+        let styled: Vec<(StyleType, String)> = nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Style {
+                    children,
+                    style_type,
+                    ..
+                } => Some((
+                    *style_type,
+                    children.iter().map(extract_plain_text).collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(styled.len(), 400);
+        for (i, pair) in styled.chunks(2).enumerate() {
+            assert_eq!(pair[0], (StyleType::SesameDot, format!("第{i}")));
+            assert_eq!(pair[1], (StyleType::UnderlineSolid, format!("字と文{i}")));
+        }
+
+        let notes = nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Note(_)))
+            .count();
+        assert_eq!(notes, 200);
+    }
 }
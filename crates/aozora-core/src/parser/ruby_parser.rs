@@ -4,7 +4,7 @@
 //! 青空文庫形式では、ルビ記号（《》）の直前の同一文字種別の連続を親文字として扱います。
 
 use crate::char_type::{CharType, CharTypeExt};
-use crate::node::Node;
+use crate::node::{BlockType, Node};
 
 /// ルビ親文字の抽出結果
 #[derive(Debug, Clone, PartialEq)]
@@ -47,10 +47,22 @@ pub fn extract_ruby_base(text: &str) -> Option<RubyBaseResult> {
         return None;
     }
 
+    // 半角英数の直後の半角終端記号（`ABC)` など）は、その半角英数と合わせて親文字にする
+    // （終端記号の後に続く半角英数は別の親文字になるため、`v2.0` の親文字は `0`）
+    let mut scan_end = chars.len();
+    let mut scan_type = last_char_type;
+    if last_char_type == CharType::HankakuTerminate
+        && chars.len() >= 2
+        && chars[chars.len() - 2].char_type() == CharType::Hankaku
+    {
+        scan_end -= 1;
+        scan_type = CharType::Hankaku;
+    }
+
     // 後ろから同じ種別の文字を探す
-    let mut base_start = chars.len();
-    for i in (0..chars.len()).rev() {
-        if chars[i].char_type() == last_char_type {
+    let mut base_start = scan_end;
+    for i in (0..scan_end).rev() {
+        if chars[i].char_type() == scan_type {
             base_start = i;
         } else {
             break;
@@ -71,101 +83,301 @@ pub fn extract_ruby_base(text: &str) -> Option<RubyBaseResult> {
 ///
 /// ノード列の最後から、親文字になりうるノードを抽出します。
 /// Textノードの場合は文字種別で分割し、Gaijiノードは漢字として扱います。
+/// 末尾が装飾（`Node::Style` または行内の装飾範囲）の場合は装飾の中から親文字を探し、
+/// 装飾の一部だけが親文字になるときは装飾を分割して親文字側を `<ruby>` の中に入れます。
 pub fn extract_ruby_base_from_nodes(nodes: &[Node]) -> Option<(Vec<Node>, Vec<Node>)> {
-    if nodes.is_empty() {
-        return None;
+    split_ruby_base_from_nodes(nodes).map(|split| split.into_parts(nodes))
+}
+
+/// ノード列の末尾から抽出したルビ親文字
+///
+/// ノード列のうち変わるのは末尾の `nodes[end..]` だけなので、
+/// 長いノード列全体を複製せずに置き換えられるよう、その部分だけを持ちます。
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RubyBaseSplit {
+    /// 親文字の抽出で変わらないノードの終端
+    pub end: usize,
+    /// `nodes[end..]` のうち親文字にならずに残る部分
+    pub remaining: Vec<Node>,
+    /// 親文字のノード
+    pub base: Vec<Node>,
+}
+
+impl RubyBaseSplit {
+    /// 元のノード列から（残りのノード列, 親文字のノード列）を作る
+    fn into_parts(self, nodes: &[Node]) -> (Vec<Node>, Vec<Node>) {
+        let mut remaining = nodes[..self.end].to_vec();
+        remaining.extend(self.remaining);
+        (remaining, self.base)
     }
+}
 
-    // 最後のノードから文字種別を取得
-    let last_node = nodes.last()?;
-    let last_char_type = last_node.last_char_type()?;
+/// [`extract_ruby_base_from_nodes`] と同じ抽出を行い、変わる部分だけを返す
+pub(crate) fn split_ruby_base_from_nodes(nodes: &[Node]) -> Option<RubyBaseSplit> {
+    let last_char_type = nodes_last_char_type(nodes)?;
 
     if !last_char_type.can_be_ruby_base() {
         return None;
     }
 
+    // 親文字のノード（逆順）
     let mut base_nodes = Vec::new();
-    let mut remaining_nodes = Vec::new();
-    let mut found_different_type = false;
+    // 親文字と同じノードから分割された残りの部分
+    let mut split_remaining = Vec::new();
+    // 親文字より前のノードの終端
+    let mut end = nodes.len();
 
     // 後ろからノードを走査
-    for node in nodes.iter().rev() {
-        if found_different_type {
-            remaining_nodes.push(node.clone());
-            continue;
-        }
-
+    while end > 0 {
+        let node = &nodes[end - 1];
         match node {
             Node::Text(text) => {
                 // テキストノードは文字種別で分割
-                if let Some(result) = extract_ruby_base(text) {
-                    if result.char_type == last_char_type {
-                        if !result.base.is_empty() {
-                            base_nodes.push(Node::Text(result.base));
-                        }
-                        if !result.remaining.is_empty() {
-                            remaining_nodes.push(Node::Text(result.remaining));
-                            found_different_type = true;
-                        }
-                    } else {
-                        found_different_type = true;
-                        remaining_nodes.push(node.clone());
-                    }
-                } else {
-                    found_different_type = true;
-                    remaining_nodes.push(node.clone());
+                let Some(result) = extract_ruby_base(text) else {
+                    break;
+                };
+                if result.char_type != last_char_type {
+                    break;
+                }
+                end -= 1;
+                if !result.base.is_empty() {
+                    base_nodes.push(Node::Text(result.base));
+                }
+                if !result.remaining.is_empty() {
+                    split_remaining.push(Node::Text(result.remaining));
+                    break;
                 }
             }
             Node::Gaiji { .. } => {
                 // 外字は漢字として扱う
-                if last_char_type == CharType::Kanji {
-                    base_nodes.push(node.clone());
-                } else {
-                    found_different_type = true;
-                    remaining_nodes.push(node.clone());
+                if last_char_type != CharType::Kanji {
+                    break;
                 }
+                end -= 1;
+                base_nodes.push(node.clone());
             }
             Node::Accent { .. } => {
                 // アクセント付き文字は半角として扱う
-                if last_char_type == CharType::Hankaku {
-                    base_nodes.push(node.clone());
-                } else {
-                    found_different_type = true;
-                    remaining_nodes.push(node.clone());
+                if last_char_type != CharType::Hankaku {
+                    break;
                 }
+                end -= 1;
+                base_nodes.push(node.clone());
             }
-            Node::DakutenKatakana { .. } => {
-                // 濁点カタカナはカタカナとして扱う
-                if last_char_type == CharType::Katakana {
-                    base_nodes.push(node.clone());
-                } else {
-                    found_different_type = true;
-                    remaining_nodes.push(node.clone());
+            Node::Style {
+                children,
+                style_type,
+                class_name,
+            } => {
+                // 装飾の中から親文字を探す
+                let wrap = |children: Vec<Node>| {
+                    vec![Node::Style {
+                        children,
+                        style_type: *style_type,
+                        class_name: class_name.clone(),
+                    }]
+                };
+                let Some(split) = extract_from_group(children, last_char_type, wrap) else {
+                    break;
+                };
+                end -= 1;
+                base_nodes.extend(split.base.into_iter().rev());
+                if !split.remaining.is_empty() {
+                    split_remaining = split.remaining;
+                    break;
                 }
             }
             _ => {
-                // その他のノードは親文字にならない
-                found_different_type = true;
-                remaining_nodes.push(node.clone());
+                // 行内の装飾範囲（［＃傍点］...［＃傍点終わり］）の中から親文字を探す
+                let Some(start) = inline_style_range_start(nodes, end - 1) else {
+                    break;
+                };
+                let (start_marker, end_marker) = (&nodes[start], node);
+                let wrap = |inner: Vec<Node>| {
+                    let mut group = vec![start_marker.clone()];
+                    group.extend(inner);
+                    group.push(end_marker.clone());
+                    group
+                };
+                let Some(split) =
+                    extract_from_group(&nodes[start + 1..end - 1], last_char_type, wrap)
+                else {
+                    break;
+                };
+                end = start;
+                base_nodes.extend(split.base.into_iter().rev());
+                if !split.remaining.is_empty() {
+                    split_remaining = split.remaining;
+                    break;
+                }
             }
         }
     }
 
+    if base_nodes.is_empty() {
+        return None;
+    }
+
     // 逆順を戻す
     base_nodes.reverse();
-    remaining_nodes.reverse();
 
-    if base_nodes.is_empty() {
-        None
-    } else {
-        Some((remaining_nodes, base_nodes))
+    Some(RubyBaseSplit {
+        end,
+        remaining: split_remaining,
+        base: base_nodes,
+    })
+}
+
+/// 装飾の中から抽出した親文字
+struct GroupSplit {
+    /// 親文字より前の部分（装飾で囲んだもの、なければ空）
+    remaining: Vec<Node>,
+    /// 親文字の部分（装飾で囲んだもの）
+    base: Vec<Node>,
+}
+
+/// 装飾の中身から親文字を抽出し、残りと親文字をそれぞれ装飾で囲む
+fn extract_from_group(
+    inner: &[Node],
+    last_char_type: CharType,
+    wrap: impl Fn(Vec<Node>) -> Vec<Node>,
+) -> Option<GroupSplit> {
+    if nodes_last_char_type(inner)? != last_char_type {
+        return None;
     }
+    let (remaining, base) = extract_ruby_base_from_nodes(inner)?;
+    Some(GroupSplit {
+        remaining: if remaining.is_empty() {
+            Vec::new()
+        } else {
+            wrap(remaining)
+        },
+        base: wrap(base),
+    })
+}
+
+/// ノード列の最後の文字の種別（末尾の装飾は中身の最後の文字で判定）
+fn nodes_last_char_type(nodes: &[Node]) -> Option<CharType> {
+    match nodes.last()? {
+        Node::Style { children, .. } => nodes_last_char_type(children),
+        _ => match inline_style_range_start(nodes, nodes.len() - 1) {
+            Some(start) => nodes_last_char_type(&nodes[start + 1..nodes.len() - 1]),
+            None => nodes.last()?.last_char_type(),
+        },
+    }
+}
+
+/// `end_idx` が行内の装飾範囲の終了の場合、対応する開始の位置を返す
+fn inline_style_range_start(nodes: &[Node], end_idx: usize) -> Option<usize> {
+    let Node::BlockEnd {
+        block_type: BlockType::Style,
+        params,
+    } = &nodes[end_idx]
+    else {
+        return None;
+    };
+    if params.is_block {
+        return None;
+    }
+
+    let mut depth = 0;
+    for i in (0..end_idx).rev() {
+        match &nodes[i] {
+            Node::BlockEnd {
+                block_type: BlockType::Style,
+                ..
+            } => depth += 1,
+            Node::BlockStart {
+                block_type: BlockType::Style,
+                params: start_params,
+            } => {
+                if depth == 0 {
+                    return (start_params.style_type == params.style_type).then_some(i);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// ノード列から直前の1文字だけをルビ親文字として抽出
+///
+/// 文字種別による自動抽出（[`extract_ruby_base_from_nodes`]）を行わない場合に使用します。
+/// 外字・アクセント付き文字・濁点カタカナのノードは1文字として扱います。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::node::Node;
+/// use aozora_core::parser::ruby_parser::extract_single_ruby_base_from_nodes;
+///
+/// let (remaining, base) = extract_single_ruby_base_from_nodes(&[Node::text("前の漢字")]).unwrap();
+/// assert_eq!(remaining, vec![Node::text("前の漢")]);
+/// assert_eq!(base, vec![Node::text("字")]);
+/// ```
+pub fn extract_single_ruby_base_from_nodes(nodes: &[Node]) -> Option<(Vec<Node>, Vec<Node>)> {
+    split_single_ruby_base_from_nodes(nodes).map(|split| split.into_parts(nodes))
+}
+
+/// [`extract_single_ruby_base_from_nodes`] と同じ抽出を行い、変わる部分だけを返す
+pub(crate) fn split_single_ruby_base_from_nodes(nodes: &[Node]) -> Option<RubyBaseSplit> {
+    let (last_node, preceding) = nodes.split_last()?;
+    if !last_node.last_char_type()?.can_be_ruby_base() {
+        return None;
+    }
+
+    let mut remaining = Vec::new();
+    let base = match last_node {
+        Node::Text(text) => {
+            let last_char = text.chars().last()?;
+            let rest = &text[..text.len() - last_char.len_utf8()];
+            if !rest.is_empty() {
+                remaining.push(Node::Text(rest.to_string()));
+            }
+            Node::Text(last_char.to_string())
+        }
+        Node::Gaiji { .. } | Node::Accent { .. } => last_node.clone(),
+        _ => return None,
+    };
+
+    Some(RubyBaseSplit {
+        end: preceding.len(),
+        remaining,
+        base: vec![base],
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_extract_single_ruby_base_from_nodes() {
+        let gaiji = Node::Gaiji {
+            description: "「てへん＋劣」".to_string(),
+            unicode: None,
+            jis_code: None,
+        };
+        let nodes = vec![Node::text("前の"), gaiji.clone()];
+        assert_eq!(
+            extract_single_ruby_base_from_nodes(&nodes),
+            Some((vec![Node::text("前の")], vec![gaiji]))
+        );
+
+        assert_eq!(
+            extract_single_ruby_base_from_nodes(&[Node::text("字")]),
+            Some((vec![], vec![Node::text("字")]))
+        );
+        // 親文字になれない文字の直後
+        assert_eq!(
+            extract_single_ruby_base_from_nodes(&[Node::text("漢字、")]),
+            None
+        );
+        assert_eq!(extract_single_ruby_base_from_nodes(&[]), None);
+    }
+
     #[test]
     fn test_extract_ruby_base_kanji() {
         let result = extract_ruby_base("東京").unwrap();
@@ -229,6 +441,43 @@ mod tests {
         assert!(matches!(&base[0], Node::Text(s) if s == "東京"));
     }
 
+    #[test]
+    fn test_split_ruby_base_from_nodes() {
+        let nodes = vec![
+            Node::text("前"),
+            Node::Note("注".to_string()),
+            Node::text("私の東京"),
+        ];
+        assert_eq!(
+            split_ruby_base_from_nodes(&nodes),
+            Some(RubyBaseSplit {
+                end: 2,
+                remaining: vec![Node::text("私の")],
+                base: vec![Node::text("東京")],
+            })
+        );
+        assert_eq!(
+            split_single_ruby_base_from_nodes(&nodes),
+            Some(RubyBaseSplit {
+                end: 2,
+                remaining: vec![Node::text("私の東")],
+                base: vec![Node::text("京")],
+            })
+        );
+        // 分割しない部分を含めると従来の抽出結果と同じ
+        assert_eq!(
+            extract_ruby_base_from_nodes(&nodes),
+            Some((
+                vec![
+                    Node::text("前"),
+                    Node::Note("注".to_string()),
+                    Node::text("私の")
+                ],
+                vec![Node::text("東京")]
+            ))
+        );
+    }
+
     #[test]
     fn test_extract_ruby_base_from_nodes_with_gaiji() {
         let nodes = vec![
@@ -260,4 +509,133 @@ mod tests {
         assert!(remaining.is_empty());
         assert_eq!(base.len(), 2);
     }
+
+    /// ルビノードの親文字とルビのテキストを列挙
+    fn ruby_pairs(input: &str) -> Vec<(String, String)> {
+        let nodes = crate::parser::parse(&crate::tokenizer::tokenize(input));
+        nodes
+            .iter()
+            .map(|node| match node {
+                Node::Ruby { children, ruby, .. } => (
+                    children.iter().map(Node::to_text).collect(),
+                    ruby.iter().map(Node::to_text).collect(),
+                ),
+                other => (other.to_text(), String::new()),
+            })
+            .collect()
+    }
+
+    fn pair(base: &str, ruby: &str) -> (String, String) {
+        (base.to_string(), ruby.to_string())
+    }
+
+    #[test]
+    fn test_consecutive_ruby() {
+        assert_eq!(
+            ruby_pairs("漢字《かんじ》熟語《じゅくご》"),
+            vec![pair("漢字", "かんじ"), pair("熟語", "じゅくご")]
+        );
+    }
+
+    #[test]
+    fn test_consecutive_ruby_halfwidth() {
+        assert_eq!(
+            ruby_pairs("A《a》BC《bc》"),
+            vec![pair("A", "a"), pair("BC", "bc")]
+        );
+    }
+
+    #[test]
+    fn test_consecutive_ruby_with_intervening_text() {
+        assert_eq!(
+            ruby_pairs("漢字《かんじ》の熟語《じゅくご》"),
+            vec![
+                pair("漢字", "かんじ"),
+                pair("の", ""),
+                pair("熟語", "じゅくご")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_prefixed_ruby_base_uses_preceding_text() {
+        assert_eq!(ruby_pairs("前文｜《よみ》"), vec![pair("前文", "よみ")]);
+        assert_eq!(
+            ruby_pairs("ひらがな漢字｜《かんじ》"),
+            vec![pair("ひらがな", ""), pair("漢字", "かんじ")]
+        );
+    }
+
+    #[test]
+    fn test_hankaku_ruby_base() {
+        let result = extract_ruby_base("これはABC").unwrap();
+        assert_eq!(result.base, "ABC");
+        assert_eq!(result.remaining, "これは");
+        assert_eq!(
+            ruby_pairs("ABC《エービーシー》"),
+            vec![pair("ABC", "エービーシー")]
+        );
+    }
+
+    #[test]
+    fn test_hankaku_terminate_joins_preceding_hankaku() {
+        let result = extract_ruby_base("(ABC)").unwrap();
+        assert_eq!(result.base, "ABC)");
+        assert_eq!(result.remaining, "(");
+        assert_eq!(
+            ruby_pairs("(ABC)《かっこ》"),
+            vec![pair("(", ""), pair("ABC)", "かっこ")]
+        );
+
+        // 終端記号の後の半角英数から親文字を始める
+        assert_eq!(
+            ruby_pairs("v2.0《バージョン》"),
+            vec![pair("v2.", ""), pair("0", "バージョン")]
+        );
+
+        // 終端記号のみが続く場合はその連続が親文字
+        let result = extract_ruby_base("あ!?").unwrap();
+        assert_eq!(result.base, "!?");
+    }
+
+    #[test]
+    fn test_extract_ruby_base_from_trailing_style() {
+        use crate::node::StyleType;
+
+        let style = |text: &str| Node::Style {
+            children: vec![Node::text(text)],
+            style_type: StyleType::SesameDot,
+            class_name: String::new(),
+        };
+
+        // 装飾全体が親文字
+        assert_eq!(
+            extract_ruby_base_from_nodes(&[Node::text("私の"), style("東京")]),
+            Some((vec![Node::text("私の")], vec![style("東京")]))
+        );
+        // 装飾の一部が親文字
+        assert_eq!(
+            extract_ruby_base_from_nodes(&[style("私の東京")]),
+            Some((vec![style("私の")], vec![style("東京")]))
+        );
+        // 装飾の前の同じ文字種別も親文字に含める
+        assert_eq!(
+            extract_ruby_base_from_nodes(&[Node::text("大"), style("東京")]),
+            Some((vec![], vec![Node::text("大"), style("東京")]))
+        );
+    }
+
+    #[test]
+    fn test_extract_ruby_base_from_inline_style_range() {
+        let tokens = crate::tokenizer::tokenize("［＃傍点］私の東京［＃傍点終わり］");
+        let nodes = crate::parser::parse(&tokens);
+        let (start, end) = (nodes[0].clone(), nodes[2].clone());
+
+        let (remaining, base) = extract_ruby_base_from_nodes(&nodes).unwrap();
+        assert_eq!(
+            remaining,
+            vec![start.clone(), Node::text("私の"), end.clone()]
+        );
+        assert_eq!(base, vec![start, Node::text("東京"), end]);
+    }
 }
@@ -0,0 +1,162 @@
+//! 目次生成
+//!
+//! 本文中の見出しから目次を生成します。
+//! 見出しのアンカーIDはHTML変換時に付与されるものと同じ規則で割り当てます。
+
+use crate::document::extract_body_lines;
+use crate::node::{BlockType, MidashiLevel, Node};
+use crate::parser::parse;
+use crate::tokenizer::tokenize;
+
+/// 見出しIDの生成器
+///
+/// 大見出しで100、中見出しで10、小見出しで1ずつカウンターを進め、
+/// その値を見出しのIDとします。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MidashiIdGenerator {
+    counter: u32,
+}
+
+impl MidashiIdGenerator {
+    /// 新しい生成器を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 次の見出しIDを生成
+    pub fn next_id(&mut self, level: MidashiLevel) -> u32 {
+        let increment = match level {
+            MidashiLevel::O => 100,
+            MidashiLevel::Naka => 10,
+            MidashiLevel::Ko => 1,
+        };
+        self.counter += increment;
+        self.counter
+    }
+}
+
+/// 見出しIDからアンカー名（`id` 属性の値）を生成
+pub fn midashi_anchor_id(id: u32) -> String {
+    format!("midashi{id}")
+}
+
+/// 目次の項目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// 見出しレベル
+    pub level: MidashiLevel,
+    /// 見出しのテキスト
+    pub text: String,
+    /// アンカーID（HTML変換時の `id` 属性の値）
+    pub id: String,
+}
+
+/// 本文の見出しから目次を生成
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::toc::build_toc;
+/// use aozora_core::MidashiLevel;
+///
+/// let toc = build_toc("タイトル\n\n［＃大見出し］第一章［＃大見出し終わり］\n本文");
+/// assert_eq!(toc.len(), 1);
+/// assert_eq!(toc[0].level, MidashiLevel::O);
+/// assert_eq!(toc[0].text, "第一章");
+/// assert_eq!(toc[0].id, "midashi100");
+/// ```
+pub fn build_toc(input: &str) -> Vec<TocEntry> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut ids = MidashiIdGenerator::new();
+    let mut entries = Vec::new();
+    // 複数行にまたがる見出しの途中
+    let mut open: Option<TocEntry> = None;
+
+    for line in extract_body_lines(&lines) {
+        for node in parse(&tokenize(line)) {
+            match node {
+                Node::Midashi {
+                    children, level, ..
+                } => entries.push(TocEntry {
+                    level,
+                    text: children.iter().map(Node::to_text).collect(),
+                    id: midashi_anchor_id(ids.next_id(level)),
+                }),
+                Node::BlockStart {
+                    block_type: BlockType::Midashi,
+                    params,
+                } => {
+                    let level = params.level.unwrap_or(MidashiLevel::O);
+                    entries.extend(open.take());
+                    open = Some(TocEntry {
+                        level,
+                        text: String::new(),
+                        id: midashi_anchor_id(ids.next_id(level)),
+                    });
+                }
+                Node::BlockEnd {
+                    block_type: BlockType::Midashi,
+                    ..
+                } => entries.extend(open.take()),
+                node => {
+                    if let Some(entry) = open.as_mut() {
+                        entry.text.push_str(&node.to_text());
+                    }
+                }
+            }
+        }
+    }
+    entries.extend(open);
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_generator() {
+        let mut ids = MidashiIdGenerator::new();
+        assert_eq!(ids.next_id(MidashiLevel::O), 100);
+        assert_eq!(ids.next_id(MidashiLevel::Naka), 110);
+        assert_eq!(ids.next_id(MidashiLevel::Ko), 111);
+        assert_eq!(ids.next_id(MidashiLevel::O), 211);
+    }
+
+    #[test]
+    fn test_build_toc_nested() {
+        let input = "タイトル\n\n\
+            ［＃大見出し］第一部［＃大見出し終わり］\n\
+            ［＃中見出し］一《いち》［＃中見出し終わり］\n\
+            本文\n\
+            ［＃小見出し］上［＃小見出し終わり］\n\
+            ［＃中見出し］二［＃中見出し終わり］\n\
+            本文［＃「本文」は大見出し］\n\
+            ［＃ここから大見出し］\n\
+            第二部\n\
+            ［＃ここで大見出し終わり］";
+        let toc = build_toc(input);
+
+        let summary: Vec<(MidashiLevel, &str, &str)> = toc
+            .iter()
+            .map(|e| (e.level, e.text.as_str(), e.id.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (MidashiLevel::O, "第一部", "midashi100"),
+                (MidashiLevel::Naka, "一", "midashi110"),
+                (MidashiLevel::Ko, "上", "midashi111"),
+                (MidashiLevel::Naka, "二", "midashi121"),
+                (MidashiLevel::O, "本文", "midashi221"),
+                (MidashiLevel::O, "第二部", "midashi321"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_toc_empty() {
+        assert!(build_toc("タイトル\n\n本文のみ").is_empty());
+    }
+}
@@ -1,5 +1,8 @@
 //! 青空文庫形式のトークン型定義
 
+use alloc::string::String;
+use alloc::vec::Vec;
+
 /// 青空文庫形式のトークン
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -51,6 +54,8 @@ impl Token {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
 
     #[test]
     fn test_token_text() {
@@ -1,6 +1,11 @@
 //! 青空文庫形式の字句解析（トークナイザ）
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use crate::delimiters::*;
+use crate::limits::Limits;
 use crate::token::Token;
 
 /// 1行をトークン列に変換するトークナイザ
@@ -9,14 +14,38 @@ pub struct Tokenizer {
     chars: Vec<char>,
     /// 現在のchar位置
     pos: usize,
+    /// 記法として解釈する範囲の終端（char位置）
+    end: usize,
+    /// 記法として解釈する最大文字数（入れ子のトークナイザに引き継ぐ）
+    max_line_length: usize,
+    /// 生成する最大トークン数（ルビなどの内側のトークンを含む）
+    max_tokens: usize,
+    /// 現在の行で生成済みのトークン数（内側のトークンを除く）
+    emitted_tokens: usize,
+    /// ルビなどの内側で生成したトークン数
+    nested_tokens: usize,
 }
 
 impl Tokenizer {
-    /// 新しいトークナイザを作成
+    /// 新しいトークナイザを作成（デフォルトの上限を使用）
     pub fn new(input: &str) -> Self {
+        Self::with_limits(input, &Limits::default())
+    }
+
+    /// 上限を指定してトークナイザを作成
+    ///
+    /// 上限を超えた部分は記法として解釈せず、1つのテキストトークンになります。
+    pub fn with_limits(input: &str, limits: &Limits) -> Self {
+        let chars: Vec<char> = input.chars().collect();
+        let end = chars.len().min(limits.max_line_length);
         Self {
-            chars: input.chars().collect(),
+            chars,
             pos: 0,
+            end,
+            max_line_length: limits.max_line_length,
+            max_tokens: limits.max_tokens,
+            emitted_tokens: 0,
+            nested_tokens: 0,
         }
     }
 
@@ -25,6 +54,12 @@ impl Tokenizer {
         let mut tokens = Vec::new();
 
         while !self.is_eof() {
+            // トークン数の上限に達したら残りはテキストとして扱う
+            self.emitted_tokens = tokens.len();
+            if self.emitted_tokens.saturating_add(self.nested_tokens) >= self.max_tokens {
+                break;
+            }
+
             let ch = self.current_char().unwrap();
 
             match ch {
@@ -78,6 +113,12 @@ impl Tokenizer {
             }
         }
 
+        // 上限を超えた残りの部分はテキストとして扱う
+        if self.pos < self.chars.len() {
+            tokens.push(Token::Text(self.chars[self.pos..].iter().collect()));
+            self.pos = self.chars.len();
+        }
+
         tokens
     }
 
@@ -87,7 +128,7 @@ impl Tokenizer {
     fn read_text(&mut self) -> Token {
         let start = self.pos;
 
-        while self.pos < self.chars.len() {
+        while self.pos < self.end {
             let ch = self.chars[self.pos];
 
             // デリミタに遭遇したら終了
@@ -119,20 +160,39 @@ impl Tokenizer {
     }
 
     /// ルビトークンを読む 《...》
+    ///
+    /// 読みに 》 がそのまま含まれている場合の扱いは [`Self::skip_until_ruby_end`] を参照。
     fn read_ruby(&mut self) -> Token {
         self.skip(1); // 《
         let start = self.pos;
 
-        self.skip_until(RUBY_END);
+        self.skip_until_ruby_end();
         let content = self.slice_from(start);
         self.skip_if(RUBY_END);
 
         // ルビ内を再帰的にトークナイズ
-        let children = Tokenizer::new(&content).tokenize();
+        let children = self.tokenize_nested(&content);
 
         Token::Ruby { children }
     }
 
+    /// ルビや親文字など、記法の内側をトークナイズ
+    ///
+    /// 内側のトークンも行全体のトークン数の上限に含め、残りの数を上限として引き継ぎます。
+    fn tokenize_nested(&mut self, content: &str) -> Vec<Token> {
+        let used = self.emitted_tokens.saturating_add(self.nested_tokens);
+        let limits = Limits::new()
+            .with_max_line_length(self.max_line_length)
+            .with_max_tokens(self.max_tokens.saturating_sub(used));
+        let mut nested = Tokenizer::with_limits(content, &limits);
+        let children = nested.tokenize();
+        self.nested_tokens = self
+            .nested_tokens
+            .saturating_add(children.len())
+            .saturating_add(nested.nested_tokens);
+        children
+    }
+
     /// 明示ルビトークンを読む ｜...《...》
     fn read_prefixed_ruby(&mut self) -> Token {
         self.skip(1); // ｜
@@ -148,13 +208,20 @@ impl Tokenizer {
         self.skip(1); // 《
         let ruby_start = self.pos;
 
-        self.skip_until(RUBY_END);
+        self.skip_until_ruby_end();
         let ruby_content = self.slice_from(ruby_start);
         self.skip_if(RUBY_END);
 
         // 親文字とルビを再帰的にトークナイズ
-        let base_children = Tokenizer::new(&base_content).tokenize();
-        let ruby_children = Tokenizer::new(&ruby_content).tokenize();
+        let base_children = self.tokenize_nested(&base_content);
+        let ruby_children = self.tokenize_nested(&ruby_content);
+
+        // 親文字が空の場合（｜《よみ》）は通常のルビとして直前の文字列から親文字を取る
+        if base_children.is_empty() {
+            return Token::Ruby {
+                children: ruby_children,
+            };
+        }
 
         Token::PrefixedRuby {
             base_children,
@@ -196,7 +263,7 @@ impl Tokenizer {
 
         self.skip(1); // 〕
 
-        let children = Tokenizer::new(&content).tokenize();
+        let children = self.tokenize_nested(&content);
         Some(Token::Accent { children })
     }
 
@@ -209,17 +276,17 @@ impl Tokenizer {
 
     /// 入力の終端に達したか
     fn is_eof(&self) -> bool {
-        self.pos >= self.chars.len()
+        self.pos >= self.end
     }
 
     /// 現在位置から n 文字先を覗く
     fn peek_nth(&self, n: usize) -> Option<char> {
-        self.chars.get(self.pos + n).copied()
+        self.chars[..self.end].get(self.pos + n).copied()
     }
 
     /// 現在の文字を取得
     fn current_char(&self) -> Option<char> {
-        self.chars.get(self.pos).copied()
+        self.chars[..self.end].get(self.pos).copied()
     }
 
     /// n 文字スキップ
@@ -229,7 +296,7 @@ impl Tokenizer {
 
     /// 特定の文字までスキップ（見つかったらtrue）
     fn skip_until(&mut self, target: char) -> bool {
-        while self.pos < self.chars.len() {
+        while self.pos < self.end {
             if self.chars[self.pos] == target {
                 return true;
             }
@@ -238,10 +305,40 @@ impl Tokenizer {
         false
     }
 
+    /// ルビの終わりの 》 までスキップ（》 の手前で停止）
+    ///
+    /// 青空文庫形式には 》 のエスケープがないため、読みに 》 を含むファイルでは
+    /// `漢字《よ》み》` のように対応の取れない 》 が残ります。
+    /// 》 の後ろに、他の記法（《 ｜ ［ ※ 〔）が始まる前に別の 》 がある場合は、
+    /// 最後のその 》 までを読みとみなし、`よ》み` を読みとします。
+    /// 重ねた 》》 もそのまま読みに含めます。
+    fn skip_until_ruby_end(&mut self) {
+        if !self.skip_until(RUBY_END) {
+            return;
+        }
+        while let Some(next) = self.find_stray_ruby_end(self.pos + 1) {
+            self.pos = next;
+        }
+    }
+
+    /// `start` 以降で、他の記法が始まる前に現れる 》 の位置を探す
+    fn find_stray_ruby_end(&self, start: usize) -> Option<usize> {
+        self.chars[start..self.end]
+            .iter()
+            .position(|&ch| {
+                matches!(
+                    ch,
+                    RUBY_END | RUBY_BEGIN | RUBY_PREFIX | COMMAND_BEGIN | GAIJI_MARK | ACCENT_BEGIN
+                )
+            })
+            .map(|offset| start + offset)
+            .filter(|&pos| self.chars[pos] == RUBY_END)
+    }
+
     /// ネストを考慮して閉じ括弧までスキップ（閉じ括弧の手前で停止）
     fn skip_until_balanced(&mut self, open: char, close: char) {
         let mut depth = 1;
-        while self.pos < self.chars.len() && depth > 0 {
+        while self.pos < self.end && depth > 0 {
             let ch = self.chars[self.pos];
             if ch == open {
                 depth += 1;
@@ -272,9 +369,74 @@ pub fn tokenize(input: &str) -> Vec<Token> {
     Tokenizer::new(input).tokenize()
 }
 
+/// 上限を指定して文字列をトークン列に変換
+pub fn tokenize_with_limits(input: &str, limits: &Limits) -> Vec<Token> {
+    Tokenizer::with_limits(input, limits).tokenize()
+}
+
+/// 文字列をトークン列に変換し、確認用の読みやすい形式で返す
+///
+/// 記法がどのように字句解析されたかを確認するためのもので、
+/// トークンを空白区切りで並べ、子を持つトークンは子を `[...]` で囲みます。
+///
+/// # Examples
+///
+/// ```
+/// use aozora_core::tokenize_debug;
+///
+/// assert_eq!(
+///     tokenize_debug("吾輩《わがはい》は"),
+///     r#"Text("吾輩") Ruby[Text("わがはい")] Text("は")"#
+/// );
+/// ```
+pub fn tokenize_debug(input: &str) -> String {
+    let mut output = String::new();
+    write_debug_tokens(&mut output, &tokenize(input));
+    output
+}
+
+/// トークン列を確認用の形式で出力に追加
+fn write_debug_tokens(output: &mut String, tokens: &[Token]) {
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 {
+            output.push(' ');
+        }
+        match token {
+            Token::Text(text) => output.push_str(&format!("Text({text:?})")),
+            Token::Ruby { children } => {
+                output.push_str("Ruby");
+                write_debug_children(output, children);
+            }
+            Token::PrefixedRuby {
+                base_children,
+                ruby_children,
+            } => {
+                output.push_str("PrefixedRuby");
+                write_debug_children(output, base_children);
+                write_debug_children(output, ruby_children);
+            }
+            Token::Command { content } => output.push_str(&format!("Command({content:?})")),
+            Token::Gaiji { description } => output.push_str(&format!("Gaiji({description:?})")),
+            Token::Accent { children } => {
+                output.push_str("Accent");
+                write_debug_children(output, children);
+            }
+        }
+    }
+}
+
+/// 子トークン列を `[...]` で囲んで出力に追加
+fn write_debug_children(output: &mut String, children: &[Token]) {
+    output.push('[');
+    write_debug_tokens(output, children);
+    output.push(']');
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::limits::DEFAULT_MAX_TOKENS;
+    use alloc::vec;
 
     #[test]
     fn test_plain_text() {
@@ -282,6 +444,12 @@ mod tests {
         assert_eq!(tokens, vec![Token::Text("こんにちは".to_string())]);
     }
 
+    #[test]
+    fn test_kunojiten_as_text() {
+        let tokens = tokenize("かわる〳〵しみ〴〵");
+        assert_eq!(tokens, vec![Token::Text("かわる〳〵しみ〴〵".to_string())]);
+    }
+
     #[test]
     fn test_ruby() {
         let tokens = tokenize("漢字《かんじ》");
@@ -308,6 +476,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prefixed_ruby_with_gaiji_base() {
+        // 親文字に含まれる外字も親文字の一部としてトークン化する
+        let tokens = tokenize("｜東京※［＃「てへん＋劣」、第3水準1-84-77］《とうきょう》へ");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::PrefixedRuby {
+                    base_children: vec![
+                        Token::Text("東京".to_string()),
+                        Token::Gaiji {
+                            description: "「てへん＋劣」、第3水準1-84-77".to_string()
+                        },
+                    ],
+                    ruby_children: vec![Token::Text("とうきょう".to_string())]
+                },
+                Token::Text("へ".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ruby_with_stray_end() {
+        // 読みに含まれる 》 で読みが切れず、後ろにゴミのトークンが残らない
+        let tokens = tokenize("漢字《よ》み》です");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("漢字".to_string()),
+                Token::Ruby {
+                    children: vec![Token::Text("よ》み".to_string())]
+                },
+                Token::Text("です".to_string())
+            ]
+        );
+
+        let tokens = tokenize("｜東京《とう》きょう》");
+        assert_eq!(
+            tokens,
+            vec![Token::PrefixedRuby {
+                base_children: vec![Token::Text("東京".to_string())],
+                ruby_children: vec![Token::Text("とう》きょう".to_string())]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ruby_stray_end_stops_at_next_notation() {
+        // 次のルビの 》 は読みに含めない
+        let tokens = tokenize("漢字《かんじ》と仮名《かな》");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("漢字".to_string()),
+                Token::Ruby {
+                    children: vec![Token::Text("かんじ".to_string())]
+                },
+                Token::Text("と仮名".to_string()),
+                Token::Ruby {
+                    children: vec![Token::Text("かな".to_string())]
+                }
+            ]
+        );
+
+        // コマンド内の 》 も読みに含めない
+        let tokens = tokenize("漢字《かんじ》［＃「》」は底本では「〉」］");
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(
+            tokens[1],
+            Token::Ruby {
+                children: vec![Token::Text("かんじ".to_string())]
+            }
+        );
+    }
+
     #[test]
     fn test_command() {
         let tokens = tokenize("猫である［＃「である」に傍点］");
@@ -391,6 +634,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prefixed_ruby_with_empty_base() {
+        let tokens = tokenize("前文｜《よみ》");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("前文".to_string()),
+                Token::Ruby {
+                    children: vec![Token::Text("よみ".to_string())]
+                }
+            ]
+        );
+    }
+
     #[test]
     fn test_prefixed_ruby_without_ruby() {
         let tokens = tokenize("｜だけ");
@@ -431,4 +688,99 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_max_line_length() {
+        let limits = Limits::new().with_max_line_length(2);
+        let tokens = tokenize_with_limits("漢字《かんじ》", &limits);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("漢字".to_string()),
+                Token::Text("《かんじ》".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_tokens() {
+        let limits = Limits::new().with_max_tokens(1);
+        let tokens = tokenize_with_limits("漢字《かんじ》です", &limits);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("漢字".to_string()),
+                Token::Text("《かんじ》です".to_string())
+            ]
+        );
+    }
+
+    /// 入れ子を含めたトークン数
+    fn count_tokens(tokens: &[Token]) -> usize {
+        tokens
+            .iter()
+            .map(|token| {
+                1 + match token {
+                    Token::Ruby { children } | Token::Accent { children } => count_tokens(children),
+                    Token::PrefixedRuby {
+                        base_children,
+                        ruby_children,
+                    } => count_tokens(base_children) + count_tokens(ruby_children),
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_max_tokens_includes_ruby_base() {
+        // 親文字の中の大量の記法も行全体のトークン数の上限に含める
+        // （上限を超えた残りは、記法の内側ごとに1つのテキストトークンになる）
+        let line = format!("｜{}《るび》", "［＃注］".repeat(10_000));
+        let limits = Limits::new().with_max_tokens(100);
+        let tokens = tokenize_with_limits(&line, &limits);
+        let [Token::PrefixedRuby {
+            base_children,
+            ruby_children,
+        }] = tokens.as_slice()
+        else {
+            panic!("{tokens:?}");
+        };
+        assert_eq!(base_children.len(), 101);
+        assert_eq!(
+            base_children.last(),
+            Some(&Token::Text("［＃注］".repeat(10_000 - 100)))
+        );
+        assert_eq!(ruby_children, &vec![Token::text("るび")]);
+
+        // デフォルトの上限でも同じ
+        let tokens = tokenize(&line);
+        assert!(count_tokens(&tokens) <= DEFAULT_MAX_TOKENS + 3);
+    }
+
+    #[test]
+    fn test_unbalanced_command_stops_at_limit() {
+        let limits = Limits::new().with_max_line_length(4);
+        let tokens = tokenize_with_limits("［＃ここ［＃から", &limits);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Command {
+                    content: "ここ".to_string()
+                },
+                Token::Text("［＃から".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_debug_mixed_line() {
+        let debug = tokenize_debug(
+            "｜東京《とうきょう》の※［＃「丸印」、U+25CB］〔cafe'〕［＃「東京」に傍点］",
+        );
+        assert_eq!(
+            debug,
+            r#"PrefixedRuby[Text("東京")][Text("とうきょう")] Text("の") Gaiji("「丸印」、U+25CB") Accent[Text("cafe'")] Command("「東京」に傍点")"#
+        );
+    }
 }
@@ -4,7 +4,7 @@
 //! 青空文庫の一部の ZIP ファイルは CRC が不正なため、通常の方法では読み込めません。
 
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, Read, Seek};
 use std::path::Path;
 
 use flate2::read::DeflateDecoder;
@@ -24,39 +24,124 @@ use zip::CompressionMethod;
 /// ```
 pub fn read_first_txt_from_zip(path: &Path) -> io::Result<Vec<u8>> {
     let file = File::open(path)?;
-    let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("failed to read ZIP archive: {} ({})", e, path.display()),
-        )
-    })?;
+    read_first_txt(file, &path.display().to_string())
+}
+
+/// メモリ上のバッファなど、任意のリーダーの ZIP データから最初の .txt ファイルを読み込む
+///
+/// [`read_first_txt_from_zip`] と同様に CRC エラーを無視します。
+///
+/// # Examples
+///
+/// ```no_run
+/// use aozora_core::zip::read_first_txt_from_zip_reader;
+/// use std::io::Cursor;
+///
+/// let zip_bytes = std::fs::read("example.zip").unwrap();
+/// let content = read_first_txt_from_zip_reader(Cursor::new(zip_bytes)).unwrap();
+/// ```
+pub fn read_first_txt_from_zip_reader<R: Read + Seek>(reader: R) -> io::Result<Vec<u8>> {
+    read_first_txt(reader, "<reader>")
+}
+
+/// ZIP データから最初の .txt ファイルを読み込む（`source` はエラーメッセージ用の入力元）
+fn read_first_txt<R: Read + Seek>(reader: R, source: &str) -> io::Result<Vec<u8>> {
+    let mut archive = open_archive(reader, source)?;
 
     // .txt ファイルを探す
     for i in 0..archive.len() {
-        let mut entry = archive.by_index_raw(i).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("failed to read ZIP entry: {} ({})", e, path.display()),
-            )
-        })?;
+        let mut entry = read_raw_entry(&mut archive, i, source)?;
 
         let entry_name = entry.name().to_string();
         let name = entry_name.to_lowercase();
         if name.ends_with(".txt") && !entry.is_dir() {
-            return read_zip_entry_bytes(&mut entry, path, &entry_name);
+            return read_zip_entry_bytes(&mut entry, source, &entry_name);
         }
     }
 
     Err(io::Error::new(
         io::ErrorKind::NotFound,
-        format!("no .txt file found in ZIP archive: {}", path.display()),
+        format!("no .txt file found in ZIP archive: {}", source),
     ))
 }
 
+/// ZIP ファイル内のすべての .txt ファイルをエントリ名の順に読み込む
+///
+/// 1つの作品が巻ごとに複数の .txt ファイルに分かれている ZIP ファイル用です。
+/// CRC エラーは [`read_first_txt_from_zip`] と同様に無視します。
+pub fn read_all_txt_from_zip(path: &Path) -> io::Result<Vec<Vec<u8>>> {
+    let file = File::open(path)?;
+    read_all_txt(file, &path.display().to_string())
+}
+
+/// 任意のリーダーの ZIP データから、すべての .txt ファイルをエントリ名の順に読み込む
+///
+/// [`read_all_txt_from_zip`] と同様に CRC エラーを無視します。
+pub fn read_all_txt_from_zip_reader<R: Read + Seek>(reader: R) -> io::Result<Vec<Vec<u8>>> {
+    read_all_txt(reader, "<reader>")
+}
+
+/// ZIP データからすべての .txt ファイルを読み込む（`source` はエラーメッセージ用の入力元）
+fn read_all_txt<R: Read + Seek>(reader: R, source: &str) -> io::Result<Vec<Vec<u8>>> {
+    let mut archive = open_archive(reader, source)?;
+
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.to_lowercase().ends_with(".txt") && !name.ends_with('/'))
+        .map(str::to_string)
+        .collect();
+    names.sort();
+
+    if names.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no .txt file found in ZIP archive: {}", source),
+        ));
+    }
+
+    names
+        .iter()
+        .map(|name| {
+            let index = archive.index_for_name(name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("ZIP entry not found: {} ({})", name, source),
+                )
+            })?;
+            let mut entry = read_raw_entry(&mut archive, index, source)?;
+            read_zip_entry_bytes(&mut entry, source, name)
+        })
+        .collect()
+}
+
+/// ZIP アーカイブを開く
+fn open_archive<R: Read + Seek>(reader: R, source: &str) -> io::Result<zip::ZipArchive<R>> {
+    zip::ZipArchive::new(reader).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("failed to read ZIP archive: {} ({})", e, source),
+        )
+    })
+}
+
+/// ZIP エントリを展開せずに取得（CRC 検証をしないため）
+fn read_raw_entry<'a, R: Read + Seek>(
+    archive: &'a mut zip::ZipArchive<R>,
+    index: usize,
+    source: &str,
+) -> io::Result<zip::read::ZipFile<'a>> {
+    archive.by_index_raw(index).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("failed to read ZIP entry: {} ({})", e, source),
+        )
+    })
+}
+
 /// ZIP エントリからバイト列を読み込む（CRC 検証をスキップ）
 fn read_zip_entry_bytes(
     entry: &mut zip::read::ZipFile<'_>,
-    path: &Path,
+    source: &str,
     entry_name: &str,
 ) -> io::Result<Vec<u8>> {
     if entry.encrypted() {
@@ -64,8 +149,7 @@ fn read_zip_entry_bytes(
             io::ErrorKind::InvalidData,
             format!(
                 "encrypted ZIP entry is not supported: {} ({})",
-                entry_name,
-                path.display()
+                entry_name, source
             ),
         ));
     }
@@ -76,9 +160,7 @@ fn read_zip_entry_bytes(
             e.kind(),
             format!(
                 "failed to read ZIP entry: {} ({} in {})",
-                e,
-                entry_name,
-                path.display()
+                e, entry_name, source
             ),
         )
     })?;
@@ -96,9 +178,7 @@ fn read_zip_entry_bytes(
                     e.kind(),
                     format!(
                         "failed to decompress ZIP entry: {} ({} in {})",
-                        e,
-                        entry_name,
-                        path.display()
+                        e, entry_name, source
                     ),
                 )
             })?;
@@ -108,9 +188,7 @@ fn read_zip_entry_bytes(
             io::ErrorKind::InvalidData,
             format!(
                 "unsupported ZIP compression method {:?}: {} ({})",
-                method,
-                entry_name,
-                path.display()
+                method, entry_name, source
             ),
         )),
     }
@@ -135,6 +213,49 @@ pub fn is_zip_file(bytes: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_read_first_txt_from_zip_reader() {
+        use std::io::{Cursor, Write};
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, text) in [("readme.html", "<p>ignored</p>"), ("work.txt", "本文")] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+        }
+        let cursor = writer.finish().unwrap();
+
+        let content = read_first_txt_from_zip_reader(Cursor::new(cursor.into_inner())).unwrap();
+        assert_eq!(content, "本文".as_bytes());
+
+        let err = read_first_txt_from_zip_reader(Cursor::new(b"not a zip".to_vec())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_read_all_txt_from_zip_reader() {
+        use std::io::{Cursor, Write};
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, text) in [
+            ("work_2.txt", "下巻"),
+            ("readme.html", "<p>ignored</p>"),
+            ("work_1.txt", "上巻"),
+        ] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+        }
+        let cursor = writer.finish().unwrap();
+
+        let volumes = read_all_txt_from_zip_reader(Cursor::new(cursor.into_inner())).unwrap();
+        assert_eq!(volumes, vec!["上巻".as_bytes(), "下巻".as_bytes()]);
+    }
+
     #[test]
     fn test_is_zip_file() {
         assert!(is_zip_file(b"PK\x03\x04"));
@@ -0,0 +1,83 @@
+//! 他言語から呼び出すための平坦なAPI
+//!
+//! PyO3 などでバインディングを作る場合のために、オプションを構造体ではなく
+//! プリミティブ型の引数で受け取り、所有権を持つ `String` を返す関数を提供します。
+//! 細かい設定が必要な場合は [`strip`](crate::strip) や [`html`](crate::html) を直接使用してください。
+
+use crate::html::{self, RenderOptions};
+use crate::strip;
+
+/// 青空文庫形式のテキストをプレーンテキストに変換
+///
+/// [`strip::convert_str`] と同じ結果を返します。
+///
+/// # Examples
+///
+/// ```
+/// let plain = aozora2::api::convert_text("タイトル\n\n吾輩《わがはい》は猫である");
+/// assert_eq!(plain, "吾輩は猫である\n");
+/// ```
+pub fn convert_text(input: &str) -> String {
+    strip::convert_str(input)
+}
+
+/// 青空文庫形式のテキストをHTMLに変換
+///
+/// # Arguments
+///
+/// * `input` - 青空文庫形式のテキスト
+/// * `gaiji_dir` - 外字画像のディレクトリ
+/// * `use_unicode` - 外字をUnicode文字で出力するかどうか
+/// * `full_document` - `true` ならHTML文書全体、`false` なら本文のHTML断片を返す
+///
+/// # Examples
+///
+/// ```
+/// let input = "タイトル\n\n吾輩《わがはい》は猫である";
+/// let html = aozora2::api::convert_html(input, "../../../gaiji/", false, false);
+/// assert!(html.contains("<ruby>"));
+/// assert!(!html.contains("<!DOCTYPE"));
+/// ```
+pub fn convert_html(
+    input: &str,
+    gaiji_dir: &str,
+    use_unicode: bool,
+    full_document: bool,
+) -> String {
+    let options = RenderOptions::new()
+        .with_gaiji_dir(gaiji_dir)
+        .with_unicode(use_unicode);
+    if full_document {
+        html::convert(input, &options)
+    } else {
+        html::convert_fragment(input, &options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "タイトル\n著者名\n\n吾輩《わがはい》は※［＃「てへん＋劣」、第3水準1-84-77］である\n\n底本：青空文庫\n";
+
+    #[test]
+    fn test_convert_text() {
+        assert_eq!(convert_text(INPUT), strip::convert_str(INPUT));
+        assert!(convert_text(INPUT).starts_with("吾輩は"));
+    }
+
+    #[test]
+    fn test_convert_html() {
+        let fragment = convert_html(INPUT, "gaiji/", false, false);
+        assert_eq!(
+            fragment,
+            html::convert_fragment(INPUT, &RenderOptions::new().with_gaiji_dir("gaiji/"))
+        );
+        assert!(fragment.contains("gaiji/1-84/1-84-77.png"));
+        assert!(!fragment.contains("<!DOCTYPE"));
+
+        let document = convert_html(INPUT, "gaiji/", true, true);
+        assert!(document.contains("<!DOCTYPE"));
+        assert!(!document.contains("1-84-77.png"));
+    }
+}
@@ -0,0 +1,69 @@
+//! epub サブコマンド
+//!
+//! 青空文庫形式をEPUBに変換
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use aozora_core::input::{read_input, read_zip_volumes};
+use clap::Args as ClapArgs;
+
+use aozora2::epub;
+use aozora2::html::RenderOptions;
+
+/// epub サブコマンドの引数
+#[derive(ClapArgs, Debug)]
+pub struct Args {
+    /// 入力ファイル（省略時は標準入力）
+    pub input: Option<PathBuf>,
+
+    /// 出力ファイル
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// 入力をZIPファイルとして扱う
+    #[arg(short, long)]
+    pub zip: bool,
+
+    /// ZIPファイル内のすべての .txt ファイルをファイル名順に結合して変換
+    #[arg(long, requires = "input", conflicts_with = "zip")]
+    pub zip_all: bool,
+
+    /// 外字画像ディレクトリ
+    #[arg(long, default_value = "../../../gaiji/")]
+    pub gaiji_dir: String,
+
+    /// ドキュメントのタイトル
+    #[arg(long)]
+    pub title: Option<String>,
+}
+
+/// epub サブコマンドを実行
+pub fn run(args: Args) -> io::Result<()> {
+    // 入力読み込み（ZIP・gzipにも対応）
+    let bytes = match args.input.as_deref() {
+        Some(path) if args.zip_all => read_zip_volumes(path)?,
+        input => read_input(input, args.zip)?,
+    };
+
+    let input = aozora_core::encoding::decode_to_utf8(&bytes);
+
+    // オプション設定（外字は可能な限り文字として埋め込む）
+    let options = RenderOptions::new()
+        .with_gaiji_dir(&args.gaiji_dir)
+        .with_jisx0213(true)
+        .with_unicode(true);
+
+    let options = if let Some(title) = &args.title {
+        options.with_title(title)
+    } else {
+        options
+    };
+
+    // 変換・出力
+    let output = epub::convert(&input, &options)?;
+    fs::write(&args.output, output)?;
+
+    Ok(())
+}
@@ -3,15 +3,35 @@
 //! 青空文庫形式をHTMLに変換
 
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
-use aozora_core::zip::{is_zip_file, read_first_txt_from_zip};
-use clap::Args as ClapArgs;
-use encoding_rs::SHIFT_JIS;
+use aozora_core::encoding::{encode_from_utf8, OutputEncoding};
+use aozora_core::input::{read_input, read_zip_volumes};
+use clap::{Args as ClapArgs, ValueEnum};
 
 use aozora2::html::{self, RenderOptions};
 
+/// 出力エンコーディング
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    /// UTF-8
+    #[value(name = "utf8", alias = "utf-8")]
+    Utf8,
+    /// Shift_JIS
+    #[value(name = "sjis", alias = "shift_jis")]
+    Sjis,
+}
+
+impl From<Encoding> for OutputEncoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Utf8 => OutputEncoding::Utf8,
+            Encoding::Sjis => OutputEncoding::ShiftJis,
+        }
+    }
+}
+
 /// html サブコマンドの引数
 #[derive(ClapArgs, Debug)]
 pub struct Args {
@@ -26,6 +46,10 @@ pub struct Args {
     #[arg(short, long)]
     pub zip: bool,
 
+    /// ZIPファイル内のすべての .txt ファイルをファイル名順に結合して変換
+    #[arg(long, requires = "input", conflicts_with = "zip")]
+    pub zip_all: bool,
+
     /// 外字画像ディレクトリ
     #[arg(long, default_value = "../../../gaiji/")]
     pub gaiji_dir: String,
@@ -42,47 +66,37 @@ pub struct Args {
     #[arg(long)]
     pub use_unicode: bool,
 
+    /// 行右小書き・行左小書きを専用クラスの<span>で出力
+    #[arg(long)]
+    pub kogaki_class: bool,
+
+    /// 本文の連続する行を<p>でまとめて出力
+    #[arg(long)]
+    pub paragraphs: bool,
+
+    /// 本文のブロックを入れ子の深さに応じて字下げして出力
+    #[arg(long)]
+    pub pretty: bool,
+
+    /// 「表記について」セクションを出力しない
+    #[arg(long)]
+    pub no_notation_notes: bool,
+
     /// ドキュメントのタイトル
     #[arg(long)]
     pub title: Option<String>,
 
-    /// 出力エンコーディング（utf-8 または shift_jis）
-    #[arg(long, default_value = "shift_jis")]
-    pub encoding: String,
+    /// 出力エンコーディング（HTMLのcharset宣言も合わせて変更）
+    #[arg(long, alias = "encoding", value_enum, default_value_t = Encoding::Sjis)]
+    pub output_encoding: Encoding,
 }
 
 /// html サブコマンドを実行
 pub fn run(args: Args) -> io::Result<()> {
-    // 入力読み込み
-    let bytes = if args.zip {
-        // ZIPモード
-        let path = args.input.as_ref().ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "ZIP mode requires an input file",
-            )
-        })?;
-        read_first_txt_from_zip(path)?
-    } else {
-        // 通常モード
-        match &args.input {
-            Some(path) => {
-                let bytes = fs::read(path)?;
-                // ZIPファイルの誤用を検出
-                if is_zip_file(&bytes) {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "input appears to be a ZIP file; use --zip option",
-                    ));
-                }
-                bytes
-            }
-            None => {
-                let mut buf = Vec::new();
-                io::stdin().read_to_end(&mut buf)?;
-                buf
-            }
-        }
+    // 入力読み込み（ZIP・gzipにも対応）
+    let bytes = match args.input.as_deref() {
+        Some(path) if args.zip_all => read_zip_volumes(path)?,
+        input => read_input(input, args.zip)?,
     };
 
     let input = aozora_core::encoding::decode_to_utf8(&bytes);
@@ -98,7 +112,12 @@ pub fn run(args: Args) -> io::Result<()> {
         .with_gaiji_dir(&args.gaiji_dir)
         .with_css_files(css_files)
         .with_jisx0213(args.use_jisx0213)
-        .with_unicode(args.use_unicode);
+        .with_unicode(args.use_unicode)
+        .with_kogaki_class(args.kogaki_class)
+        .with_paragraphs(args.paragraphs)
+        .with_pretty(args.pretty)
+        .with_notation_notes(!args.no_notation_notes)
+        .with_output_encoding(args.output_encoding.into());
 
     let options = if let Some(title) = &args.title {
         options.with_title(title)
@@ -109,13 +128,8 @@ pub fn run(args: Args) -> io::Result<()> {
     // 変換
     let output_html = html::convert(&input, &options);
 
-    // エンコーディング変換
-    let output_bytes = if args.encoding.to_lowercase() == "shift_jis" {
-        let (encoded, _, _) = SHIFT_JIS.encode(&output_html);
-        encoded.into_owned()
-    } else {
-        output_html.into_bytes()
-    };
+    // エンコーディング変換（Shift_JISで表現できない文字は数値文字参照にする）
+    let output_bytes = encode_from_utf8(&output_html, args.output_encoding.into());
 
     // 出力
     match &args.output {
@@ -1,4 +1,5 @@
 //! CLI サブコマンド
 
+pub mod epub;
 pub mod html;
 pub mod strip;
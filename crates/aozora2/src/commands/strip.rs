@@ -3,10 +3,10 @@
 //! 青空文庫形式をプレーンテキストに変換
 
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
-use aozora_core::zip::{is_zip_file, read_first_txt_from_zip};
+use aozora_core::input::{read_input, read_zip_volumes};
 use clap::Args as ClapArgs;
 
 use aozora2::strip;
@@ -24,48 +24,48 @@ pub struct Args {
     /// 入力をZIPファイルとして扱う
     #[arg(short, long)]
     pub zip: bool,
+
+    /// ZIPファイル内のすべての .txt ファイルをファイル名順に結合して変換
+    #[arg(long, requires = "input", conflicts_with = "zip")]
+    pub zip_all: bool,
+
+    /// 字下げ・罫囲み・地付きブロックの前後に空行を出力
+    #[arg(long)]
+    pub block_markers: bool,
+
+    /// 変換せずに、各行のトークン列を確認用の形式で出力
+    #[arg(long)]
+    pub debug_tokens: bool,
 }
 
 /// strip サブコマンドを実行
 pub fn run(args: Args) -> io::Result<()> {
-    // 入力読み込み
-    let bytes = if args.zip {
-        // ZIPモード
-        let path = args.input.as_ref().ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "ZIP mode requires an input file",
-            )
-        })?;
-        read_first_txt_from_zip(path)?
-    } else {
-        // 通常モード
-        match &args.input {
-            Some(path) => {
-                let bytes = fs::read(path)?;
-                // ZIPファイルの誤用を検出
-                if is_zip_file(&bytes) {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "input appears to be a ZIP file; use --zip option",
-                    ));
-                }
-                bytes
-            }
-            None => {
-                let mut buf = Vec::new();
-                io::stdin().read_to_end(&mut buf)?;
-                buf
-            }
-        }
+    // 入力読み込み（ZIP・gzipにも対応）
+    let bytes = match args.input.as_deref() {
+        Some(path) if args.zip_all => read_zip_volumes(path)?,
+        input => read_input(input, args.zip)?,
     };
 
+    if args.debug_tokens {
+        let input = aozora_core::encoding::decode_to_utf8(&bytes);
+        let output: String = input
+            .lines()
+            .map(|line| aozora_core::tokenize_debug(line) + "\n")
+            .collect();
+        return write_output(&args, &output);
+    }
+
     // 変換
-    let output = strip::convert(&bytes);
+    let options = strip::StripOptions::new().with_block_markers(args.block_markers);
+    let output = strip::convert_with_options(&bytes, &options);
+
+    write_output(&args, &output)
+}
 
-    // 出力
+/// 出力ファイルまたは標準出力に書き出す
+fn write_output(args: &Args, output: &str) -> io::Result<()> {
     match &args.output {
-        Some(path) => fs::write(path, &output)?,
+        Some(path) => fs::write(path, output)?,
         None => io::stdout().write_all(output.as_bytes())?,
     }
 
@@ -0,0 +1,305 @@
+//! EPUB変換
+//!
+//! 青空文庫形式のテキストを最小構成のEPUB3に変換します。
+//! 本文はHTMLレンダラーの断片出力をそのままXHTMLとして格納します。
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Cursor, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aozora_core::document::{extract_header_info, HeaderInfo};
+use aozora_core::toc::{build_toc, TocEntry};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::html::{convert_fragment, html_escape, RenderOptions, WritingMode};
+
+/// 本文XHTMLのファイル名（OEBPSからの相対パス）
+const BODY_FILE: &str = "text.xhtml";
+
+/// 青空文庫形式のテキストをEPUBに変換
+///
+/// `mimetype`（無圧縮・先頭）、`META-INF/container.xml`、`OEBPS/content.opf`、
+/// `OEBPS/nav.xhtml`、`OEBPS/text.xhtml` からなるEPUBのバイト列を返します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora2::epub;
+/// use aozora2::html::RenderOptions;
+///
+/// let input = "吾輩は猫である\n夏目漱石\n\n吾輩《わがはい》は猫である。";
+/// let bytes = epub::convert(input, &RenderOptions::default()).unwrap();
+/// assert_eq!(&bytes[30..38], b"mimetype");
+/// ```
+pub fn convert(input: &str, options: &RenderOptions) -> io::Result<Vec<u8>> {
+    let lines: Vec<&str> = input.lines().collect();
+    let header_info = extract_header_info(&lines);
+    let title = options
+        .title
+        .clone()
+        .or_else(|| header_info.title.clone())
+        .unwrap_or_default();
+
+    let body = convert_fragment(input, options).replace("&nbsp;", "&#160;");
+    let toc = build_toc(input);
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // mimetypeは無圧縮で最初に格納する
+    zip.start_file("mimetype", stored).map_err(zip_error)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(container_xml().as_bytes())?;
+
+    zip.start_file("OEBPS/content.opf", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(content_opf(input, &title, &header_info).as_bytes())?;
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(nav_xhtml(&title, &toc).as_bytes())?;
+
+    zip.start_file(format!("OEBPS/{BODY_FILE}"), deflated)
+        .map_err(zip_error)?;
+    zip.write_all(body_xhtml(&title, &body, options.writing_mode).as_bytes())?;
+
+    let cursor = zip.finish().map_err(zip_error)?;
+    Ok(cursor.into_inner())
+}
+
+fn zip_error(e: zip::result::ZipError) -> io::Error {
+    io::Error::other(format!("failed to write EPUB archive: {e}"))
+}
+
+/// META-INF/container.xml を生成
+fn container_xml() -> String {
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str(
+        "<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n",
+    );
+    output.push_str("  <rootfiles>\n");
+    output.push_str("    <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n");
+    output.push_str("  </rootfiles>\n");
+    output.push_str("</container>\n");
+    output
+}
+
+/// OEBPS/content.opf を生成
+fn content_opf(input: &str, title: &str, header_info: &HeaderInfo) -> String {
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str("<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"ja\">\n");
+    output.push_str("  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+    output.push_str(&format!(
+        "    <dc:identifier id=\"book-id\">{}</dc:identifier>\n",
+        book_identifier(input)
+    ));
+    output.push_str(&format!(
+        "    <dc:title>{}</dc:title>\n",
+        html_escape(title)
+    ));
+    if let Some(subtitle) = &header_info.subtitle {
+        output.push_str(&format!(
+            "    <dc:title>{}</dc:title>\n",
+            html_escape(subtitle)
+        ));
+    }
+    if let Some(author) = &header_info.author {
+        output.push_str(&format!(
+            "    <dc:creator>{}</dc:creator>\n",
+            html_escape(author)
+        ));
+    }
+    // 編者と翻訳者を兼ねる場合（`編・訳`）は1回だけ出力
+    let editor = header_info
+        .editor
+        .as_ref()
+        .filter(|editor| header_info.translator.as_ref() != Some(*editor));
+    for contributor in [
+        header_info.translator.as_ref(),
+        editor,
+        header_info.henyaku.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        output.push_str(&format!(
+            "    <dc:contributor>{}</dc:contributor>\n",
+            html_escape(contributor)
+        ));
+    }
+    output.push_str("    <dc:language>ja</dc:language>\n");
+    output.push_str("    <dc:publisher>青空文庫</dc:publisher>\n");
+    output.push_str(&format!(
+        "    <meta property=\"dcterms:modified\">{}</meta>\n",
+        modified_timestamp()
+    ));
+    output.push_str("  </metadata>\n");
+    output.push_str("  <manifest>\n");
+    output.push_str("    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n");
+    output.push_str(&format!(
+        "    <item id=\"text\" href=\"{BODY_FILE}\" media-type=\"application/xhtml+xml\"/>\n"
+    ));
+    output.push_str("  </manifest>\n");
+    output.push_str("  <spine>\n");
+    output.push_str("    <itemref idref=\"text\"/>\n");
+    output.push_str("  </spine>\n");
+    output.push_str("</package>\n");
+    output
+}
+
+/// OEBPS/nav.xhtml を生成
+fn nav_xhtml(title: &str, toc: &[TocEntry]) -> String {
+    let mut output = String::new();
+    push_xhtml_head(&mut output, title, true);
+    output.push_str("<nav epub:type=\"toc\" id=\"toc\">\n");
+    output.push_str(&format!("<h1>{}</h1>\n", html_escape(title)));
+    output.push_str("<ol>\n");
+    if toc.is_empty() {
+        output.push_str(&format!(
+            "<li><a href=\"{BODY_FILE}\">{}</a></li>\n",
+            html_escape(title)
+        ));
+    }
+    for entry in toc {
+        output.push_str(&format!(
+            "<li><a href=\"{BODY_FILE}#{}\">{}</a></li>\n",
+            entry.id,
+            html_escape(&entry.text)
+        ));
+    }
+    output.push_str("</ol>\n");
+    output.push_str("</nav>\n");
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+/// 本文XHTMLを生成
+fn body_xhtml(title: &str, body: &str, writing_mode: WritingMode) -> String {
+    let mut output = String::new();
+    push_xhtml_head(&mut output, title, false);
+    output.push_str(&format!(
+        "<div class=\"main_text {}\">",
+        writing_mode.css_class()
+    ));
+    output.push_str(body);
+    output.push_str("</div>\n");
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+/// XHTMLの冒頭（`<body>` まで）を出力
+fn push_xhtml_head(output: &mut String, title: &str, with_epub_ns: bool) {
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str("<!DOCTYPE html>\n");
+    if with_epub_ns {
+        output.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"ja\" lang=\"ja\">\n");
+    } else {
+        output.push_str(
+            "<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"ja\" lang=\"ja\">\n",
+        );
+    }
+    output.push_str("<head>\n");
+    output.push_str("<meta charset=\"UTF-8\"/>\n");
+    output.push_str(&format!("<title>{}</title>\n", html_escape(title)));
+    output.push_str("</head>\n");
+    output.push_str("<body>\n");
+}
+
+/// 入力内容から識別子を生成
+fn book_identifier(input: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    format!("urn:aozora2:{:016x}", hasher.finish())
+}
+
+/// `dcterms:modified` 用の現在時刻（UTC）を生成
+fn modified_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // 1970-01-01 からの日数を年月日に変換
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const INPUT: &str = "吾輩は猫である\n夏目漱石\n\n［＃大見出し］一［＃大見出し終わり］\n吾輩《わがはい》は猫である。\n［＃中見出し］二［＃中見出し終わり］\n名前はまだ無い。";
+
+    #[test]
+    fn test_epub_archive() {
+        let bytes = convert(INPUT, &RenderOptions::default()).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        // mimetypeは先頭・無圧縮
+        let mimetype = archive.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+        drop(mimetype);
+
+        for name in [
+            "META-INF/container.xml",
+            "OEBPS/content.opf",
+            "OEBPS/nav.xhtml",
+            "OEBPS/text.xhtml",
+        ] {
+            assert!(archive.by_name(name).is_ok(), "missing {name}");
+        }
+
+        let mut opf = String::new();
+        archive
+            .by_name("OEBPS/content.opf")
+            .unwrap()
+            .read_to_string(&mut opf)
+            .unwrap();
+        assert!(opf.contains("<dc:title>吾輩は猫である</dc:title>"));
+        assert!(opf.contains("<dc:creator>夏目漱石</dc:creator>"));
+
+        let mut nav = String::new();
+        archive
+            .by_name("OEBPS/nav.xhtml")
+            .unwrap()
+            .read_to_string(&mut nav)
+            .unwrap();
+        assert!(nav.contains("<a href=\"text.xhtml#midashi100\">一</a>"));
+        assert!(nav.contains("<a href=\"text.xhtml#midashi110\">二</a>"));
+
+        let mut text = String::new();
+        archive
+            .by_name("OEBPS/text.xhtml")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.contains("id=\"midashi100\""));
+        assert!(text.contains("<ruby>"));
+    }
+}
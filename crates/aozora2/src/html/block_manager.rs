@@ -2,24 +2,63 @@
 //!
 //! ブロック要素のスタック管理を行います。
 
-use aozora_core::node::{BlockParams, BlockType, MidashiLevel};
+use std::collections::HashMap;
+use std::rc::Rc;
 
+use aozora_core::node::{BlockParams, BlockType, MidashiLevel, StyleType};
+use aozora_core::toc::MidashiIdGenerator;
+
+use super::presentation::{midashi_html_tag, DEFAULT_HEADING_BASE_LEVEL};
 use super::tag_generator::{generate_block_end_tag, generate_block_start_tag};
 
 /// ブロックコンテキスト
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlockContext {
     pub block_type: BlockType,
     pub params: BlockParams,
 }
 
+/// 字下げ系（字下げ・地付き・ぶら下げ）のブロックかどうか
+fn is_indent_block(block_type: BlockType) -> bool {
+    matches!(
+        block_type,
+        BlockType::Jisage | BlockType::Chitsuki | BlockType::Burasage
+    )
+}
+
+/// 文字の装飾だけを行うブロック（太字・斜体・文字サイズ）かどうか
+fn is_span_block(block_type: BlockType) -> bool {
+    matches!(
+        block_type,
+        BlockType::Futoji | BlockType::Shatai | BlockType::FontDai | BlockType::FontSho
+    )
+}
+
 /// ブロックマネージャー
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlockManager {
     /// 現在のブロックスタック
     stack: Vec<BlockContext>,
-    /// 見出しIDカウンター
-    midashi_id_counter: u32,
+    /// 見出しID生成器
+    midashi_ids: MidashiIdGenerator,
+    /// ページ番号ごとの出現回数（ページ位置のIDの重複を避ける）
+    page_counts: HashMap<u32, usize>,
+    /// 大見出しの見出しレベル
+    heading_base_level: u8,
+    /// 装飾のCSSクラス名の置き換え（行ごとの状態の複製で共有する）
+    style_class_map: Rc<HashMap<StyleType, String>>,
+}
+
+impl Default for BlockManager {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            midashi_ids: MidashiIdGenerator::default(),
+            page_counts: HashMap::new(),
+            heading_base_level: DEFAULT_HEADING_BASE_LEVEL,
+            style_class_map: Rc::default(),
+        }
+    }
 }
 
 impl BlockManager {
@@ -28,6 +67,23 @@ impl BlockManager {
         Self::default()
     }
 
+    /// 大見出しの見出しレベルを設定
+    pub fn with_heading_base_level(mut self, level: u8) -> Self {
+        self.heading_base_level = level;
+        self
+    }
+
+    /// 装飾のCSSクラス名の置き換えを設定
+    pub fn with_style_class_map(mut self, class_map: Rc<HashMap<StyleType, String>>) -> Self {
+        self.style_class_map = class_map;
+        self
+    }
+
+    /// 見出しのHTMLタグ名を取得
+    pub fn midashi_html_tag(&self, level: MidashiLevel) -> &'static str {
+        midashi_html_tag(level, self.heading_base_level)
+    }
+
     /// スタックの長さを取得
     pub fn stack_len(&self) -> usize {
         self.stack.len()
@@ -61,6 +117,11 @@ impl BlockManager {
         !self.stack.is_empty()
     }
 
+    /// 指定されたブロックの内側かどうか
+    pub fn is_inside(&self, block_type: BlockType) -> bool {
+        self.stack.iter().any(|c| c.block_type == block_type)
+    }
+
     /// 指定された長さまでスタックをポップし、終了タグを生成
     pub fn pop_to_length(&mut self, target_len: usize) -> Vec<(BlockType, BlockParams)> {
         let mut result = Vec::new();
@@ -72,6 +133,16 @@ impl BlockManager {
         result
     }
 
+    /// 指定位置より後に開いた、文字の装飾だけを行うブロックのうち最も外側の位置
+    pub fn find_span_block_from(&self, start: usize) -> Option<usize> {
+        self.stack
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, c)| is_span_block(c.block_type))
+            .map(|(pos, _)| pos)
+    }
+
     /// ぶら下げブロック内かどうかをチェックし、パラメータを返す
     pub fn find_burasage_context(&self) -> Option<(u32, i32)> {
         for ctx in &self.stack {
@@ -96,6 +167,9 @@ impl BlockManager {
     }
 
     /// 新しいブロック開始時に関連ブロックを閉じる
+    ///
+    /// 字下げ・地付き・ぶら下げ以外のブロック（横組み、罫囲みなど）より外側にある
+    /// ブロックは閉じません（入れ子の外側のブロックを閉じるとタグの対応が崩れるため）。
     pub fn close_related_blocks(
         &mut self,
         new_block_type: &BlockType,
@@ -106,13 +180,20 @@ impl BlockManager {
             || *new_block_type == BlockType::Chitsuki
             || *new_block_type == BlockType::Burasage
         {
-            while let Some(pos) = self.stack.iter().rposition(|c| {
+            // 最も内側の字下げ系以外のブロックより内側だけを対象にする
+            let start = self
+                .stack
+                .iter()
+                .rposition(|c| !is_indent_block(c.block_type))
+                .map_or(0, |pos| pos + 1);
+
+            while let Some(offset) = self.stack[start..].iter().rposition(|c| {
                 c.block_type == *new_block_type
                     || c.block_type == BlockType::Burasage
                     || (*new_block_type == BlockType::Jisage && c.block_type == BlockType::Jisage)
                     || (*new_block_type == BlockType::Burasage && c.block_type == BlockType::Jisage)
             }) {
-                let ctx = self.stack.remove(pos);
+                let ctx = self.stack.remove(start + offset);
                 // Burasageは終了タグを出力しない
                 if ctx.block_type != BlockType::Burasage {
                     result.push((ctx.block_type, ctx.params));
@@ -124,6 +205,9 @@ impl BlockManager {
     }
 
     /// 対応するブロック終了を探して削除
+    ///
+    /// 同じ種類のブロック（字下げの終了ではぶら下げも含む）だけを閉じます。
+    /// 開いていなければ何も閉じずに `None` を返します。
     pub fn find_and_close(&mut self, block_type: &BlockType) -> Option<BlockContext> {
         // Jisage終了でBurasageも閉じる
         let pos = self.stack.iter().rposition(|c| {
@@ -136,13 +220,20 @@ impl BlockManager {
 
     /// 見出しIDを生成
     pub fn generate_midashi_id(&mut self, level: MidashiLevel) -> u32 {
-        let increment = match level {
-            MidashiLevel::O => 100,
-            MidashiLevel::Naka => 10,
-            MidashiLevel::Ko => 1,
-        };
-        self.midashi_id_counter += increment;
-        self.midashi_id_counter
+        self.midashi_ids.next_id(level)
+    }
+
+    /// ページ位置のIDを生成
+    ///
+    /// 同じページ番号が2回目以降に現れた場合は `page-12-2` のように出現回数を付けます。
+    pub fn generate_page_id(&mut self, page: u32) -> String {
+        let count = self.page_counts.entry(page).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            format!("page-{page}")
+        } else {
+            format!("page-{page}-{count}")
+        }
     }
 
     /// ブロック開始タグを生成
@@ -159,11 +250,44 @@ impl BlockManager {
             None
         };
 
-        generate_block_start_tag(block_type, params, midashi_id)
+        generate_block_start_tag(
+            block_type,
+            params,
+            midashi_id,
+            self.heading_base_level,
+            &self.style_class_map,
+        )
+    }
+
+    /// 開いているブロックの開始タグを外側から順に再生成
+    ///
+    /// 行範囲を指定した変換で、範囲より前に開始されたブロックを補うために使います。
+    /// 各タグをスタック上の深さ（整形出力の字下げに使う）と組にして返します。
+    /// ぶら下げと横組み内の縦中横は開始タグを出力しないため除外します。
+    pub fn render_open_block_tags(&self) -> Vec<(usize, String)> {
+        let mut tags = Vec::new();
+        for (pos, ctx) in self.stack.iter().enumerate() {
+            let tcy_in_yokogumi = ctx.block_type == BlockType::Tcy
+                && self.stack[..pos]
+                    .iter()
+                    .any(|c| c.block_type == BlockType::Yokogumi);
+            if ctx.block_type == BlockType::Burasage || tcy_in_yokogumi {
+                continue;
+            }
+            let tag = generate_block_start_tag(
+                &ctx.block_type,
+                &ctx.params,
+                None,
+                self.heading_base_level,
+                &self.style_class_map,
+            );
+            tags.push((pos, tag));
+        }
+        tags
     }
 
     /// ブロック終了タグを生成
     pub fn render_block_end_tag(&self, block_type: &BlockType, params: &BlockParams) -> String {
-        generate_block_end_tag(block_type, params)
+        generate_block_end_tag(block_type, params, self.heading_base_level)
     }
 }
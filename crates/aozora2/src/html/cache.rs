@@ -0,0 +1,342 @@
+//! 行単位のキャッシュによる再変換
+//!
+//! エディタのプレビューのように、少しずつ変更される文書を繰り返し変換する場合に、
+//! 変更のない行の解析結果と変換結果を再利用します。
+
+use std::collections::HashMap;
+
+use aozora_core::node::Node;
+
+use super::block_manager::BlockManager;
+use super::node_renderer::NodeRenderer;
+use super::options::RenderOptions;
+use super::renderer::{HtmlRenderer, LineSource};
+
+/// 行の変換結果
+#[derive(Debug, Clone)]
+struct CachedRender {
+    /// 行の開始時点のブロックの状態
+    before: BlockManager,
+    /// 行の終了時点のブロックの状態
+    after: BlockManager,
+    /// 行の開始時点までに集めた後注の数（後注の番号が変わる場合は再変換する）
+    endnotes_before: usize,
+    /// 行で集めた後注
+    endnotes: Vec<String>,
+    /// 行のHTML
+    html: String,
+    /// 直近の変換で使用されたかどうか
+    used: bool,
+}
+
+/// 同じ内容の行のキャッシュ
+#[derive(Debug, Clone)]
+struct CachedLine {
+    /// 解析結果
+    nodes: Vec<Node>,
+    /// ブロックの状態ごとの変換結果
+    renders: Vec<CachedRender>,
+}
+
+/// 行単位のキャッシュ
+///
+/// 解析結果は行の内容ごとに、変換結果は行の内容と行の開始時点の状態の組ごとに保持します。
+/// ブロックの開始・終了を編集すると、それ以降の行は状態が変わるため再変換されますが、
+/// 対応する終了で状態が元に戻れば、その後の行は再びキャッシュから取り出されます。
+///
+/// 行の開始時点の状態として照合するのは、ブロックの状態（見出しIDなどを含む）と
+/// それまでに集めた後注の数です。行で集めた後注は変換結果と一緒に保持し、
+/// キャッシュから取り出す際に集め直します。注記や外字の使用の有無などのフラグは
+/// 文書全体を変換する場合のヘッダー・後付けにだけ使い、キャッシュを使う本文の断片の
+/// 変換には影響しないため保持しません。ノードごとのフックを設定している場合など、
+/// [`NodeRenderer::is_cacheable`] が `false` を返す場合は変換結果を再利用しません。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineCache {
+    /// 前回の変換で使用した行
+    previous: HashMap<String, CachedLine>,
+    /// 今回の変換で使用した行
+    current: HashMap<String, CachedLine>,
+    /// 行を解析した回数
+    parse_count: usize,
+    /// 行を変換した回数
+    render_count: usize,
+}
+
+impl LineCache {
+    /// 1行をHTMLに変換し、ブロックの状態を進める
+    pub(crate) fn render_line(
+        &mut self,
+        renderer: &HtmlRenderer,
+        line: &str,
+        node_renderer: &mut NodeRenderer,
+        block_manager: &mut BlockManager,
+    ) -> String {
+        if !self.current.contains_key(line) {
+            let cached = match self.previous.remove(line) {
+                Some(cached) => cached,
+                None => {
+                    self.parse_count += 1;
+                    CachedLine {
+                        nodes: renderer.parse_line(line),
+                        renders: Vec::new(),
+                    }
+                }
+            };
+            self.current.insert(line.to_string(), cached);
+        }
+        let cached = self.current.get_mut(line).expect("line was just inserted");
+
+        if !node_renderer.is_cacheable() {
+            self.render_count += 1;
+            return renderer.render_parsed_line(line, &cached.nodes, node_renderer, block_manager);
+        }
+
+        if let Some(render) = cached.renders.iter_mut().find(|render| {
+            render.before == *block_manager
+                && render.endnotes_before == node_renderer.endnotes.len()
+        }) {
+            render.used = true;
+            *block_manager = render.after.clone();
+            node_renderer.endnotes.extend_from_slice(&render.endnotes);
+            return render.html.clone();
+        }
+
+        self.render_count += 1;
+        let before = block_manager.clone();
+        let endnotes_before = node_renderer.endnotes.len();
+        let html = renderer.render_parsed_line(line, &cached.nodes, node_renderer, block_manager);
+        cached.renders.push(CachedRender {
+            before,
+            after: block_manager.clone(),
+            endnotes_before,
+            endnotes: node_renderer.endnotes[endnotes_before..].to_vec(),
+            html: html.clone(),
+            used: true,
+        });
+        html
+    }
+
+    /// 変換の終了時に、今回使用しなかった行と変換結果を破棄
+    fn finish(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        for cached in self.previous.values_mut() {
+            cached.renders.retain(|render| render.used);
+            for render in &mut cached.renders {
+                render.used = false;
+            }
+        }
+    }
+}
+
+/// 行単位のキャッシュを持つ変換器
+///
+/// 同じ文書を少しずつ変更しながら繰り返し変換する場合に使用します。
+/// 出力は [`convert_fragment`](super::convert_fragment) と同じです。
+///
+/// # Examples
+///
+/// ```
+/// use aozora2::html::{CachingConverter, RenderOptions};
+///
+/// let mut converter = CachingConverter::new(RenderOptions::default());
+/// converter.convert_fragment("題名\n\n一行目\n二行目\n");
+/// converter.convert_fragment("題名\n\n一行目\n二行目を変更\n");
+/// assert_eq!(converter.parse_count(), 3);
+/// ```
+#[derive(Debug)]
+pub struct CachingConverter {
+    renderer: HtmlRenderer,
+    cache: LineCache,
+}
+
+impl CachingConverter {
+    /// 新しい変換器を作成
+    pub fn new(options: RenderOptions) -> Self {
+        Self {
+            renderer: HtmlRenderer::new(options),
+            cache: LineCache::default(),
+        }
+    }
+
+    /// 本文をHTML断片に変換
+    ///
+    /// 前回の変換から内容が変わらず、開始時点のブロックの状態も同じ行は、
+    /// 前回の結果を再利用します。
+    pub fn convert_fragment(&mut self, input: &str) -> String {
+        let output = self
+            .renderer
+            .render_fragment_with_source(input, LineSource::Cache(&mut self.cache));
+        self.cache.finish();
+        output
+    }
+
+    /// これまでに行を解析した回数
+    pub fn parse_count(&self) -> usize {
+        self.cache.parse_count
+    }
+
+    /// これまでに行をHTMLに変換した回数
+    pub fn render_count(&self) -> usize {
+        self.cache.render_count
+    }
+
+    /// キャッシュを破棄（解析・変換の回数はそのまま）
+    pub fn clear(&mut self) {
+        self.cache.previous.clear();
+        self.cache.current.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::{convert_fragment, NoteCollection};
+
+    const DOC: &str = "題名\n\n一行目\n［＃ここから２字下げ］\n二行目\n三行目\n［＃ここで字下げ終わり］\n四行目\n五行目\n";
+
+    /// キャッシュを使った変換の結果が通常の変換と同じであることを確認
+    fn convert_and_check(converter: &mut CachingConverter, input: &str) {
+        assert_eq!(
+            converter.convert_fragment(input),
+            convert_fragment(input, &RenderOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_edit_middle_line_reparses_only_that_line() {
+        let mut converter = CachingConverter::new(RenderOptions::default());
+        convert_and_check(&mut converter, DOC);
+        assert_eq!(converter.parse_count(), 7);
+        assert_eq!(converter.render_count(), 7);
+
+        // 変更がなければ解析も変換もしない
+        convert_and_check(&mut converter, DOC);
+        assert_eq!(converter.parse_count(), 7);
+        assert_eq!(converter.render_count(), 7);
+
+        convert_and_check(&mut converter, &DOC.replace("二行目", "二行目を変更"));
+        assert_eq!(converter.parse_count(), 8);
+        assert_eq!(converter.render_count(), 8);
+    }
+
+    #[test]
+    fn test_block_start_edit_invalidates_until_block_end() {
+        let mut converter = CachingConverter::new(RenderOptions::default());
+        convert_and_check(&mut converter, DOC);
+
+        // ブロックの開始を変更すると、終了までの行は再変換されるが再解析はされない
+        convert_and_check(&mut converter, &DOC.replace("２字下げ", "３字下げ"));
+        assert_eq!(converter.parse_count(), 8);
+        assert_eq!(converter.render_count(), 7 + 4);
+    }
+
+    #[test]
+    fn test_inserted_line_shifts_following_lines() {
+        let mut converter = CachingConverter::new(RenderOptions::default());
+        convert_and_check(&mut converter, DOC);
+
+        // 行を挿入しても、後続の行は内容で照合されるため再利用される
+        convert_and_check(&mut converter, &DOC.replace("一行目\n", "一行目\n追加\n"));
+        assert_eq!(converter.parse_count(), 8);
+        assert_eq!(converter.render_count(), 8);
+
+        // 見出しを追加すると、見出しIDの状態が変わるため以降の行は再変換される
+        let with_midashi = DOC.replace("一行目\n", "一行目\n見出し［＃「見出し」は中見出し］\n");
+        convert_and_check(&mut converter, &with_midashi);
+        assert_eq!(converter.parse_count(), 9);
+        assert_eq!(converter.render_count(), 8 + 7);
+    }
+
+    #[test]
+    fn test_endnotes_renumbered_after_edit_above() {
+        let options = RenderOptions::new().with_note_collection(NoteCollection::Endnotes);
+        let check = |converter: &mut CachingConverter, input: &str| {
+            assert_eq!(
+                converter.convert_fragment(input),
+                convert_fragment(input, &options)
+            );
+        };
+        let doc = "題名\n\n一［＃注一］\n二［＃注二］\n";
+        let mut converter = CachingConverter::new(options.clone());
+        check(&mut converter, doc);
+
+        // 上の行に注記を追加すると、後続の行の後注の番号が変わる
+        let edited = doc.replace("一［", "前［＃注零］\n一［");
+        check(&mut converter, &edited);
+        let html = converter.convert_fragment(&edited);
+        assert!(html.contains("[3]"));
+        assert_eq!(html.matches("<li").count(), 3);
+
+        // 変更のない行はキャッシュから取り出しても後注を集める
+        check(&mut converter, &edited);
+        check(&mut converter, doc);
+    }
+
+    #[test]
+    fn test_cached_output_matches_convert_fragment_across_edits() {
+        let edits = [
+            DOC.to_string(),
+            DOC.replace("二行目", "二行目［＃注記］"),
+            DOC.replace(
+                "一行目\n",
+                "前［＃注零］\n見出し［＃「見出し」は中見出し］\n一行目\n",
+            ),
+            DOC.replace("２字下げ", "３字下げ"),
+            DOC.replace("四行目\n", "［＃ここから太字］\n四行目\n"),
+            DOC.replace("五行目", "［＃12ページ］五行目［＃注五］"),
+            DOC.to_string(),
+        ];
+        let option_sets = [
+            RenderOptions::default(),
+            RenderOptions::new().with_note_collection(NoteCollection::Endnotes),
+            RenderOptions::new().with_paragraphs(true),
+            RenderOptions::new().with_pretty(true),
+            RenderOptions::new().with_source_line_attrs(true),
+            RenderOptions::new().with_line_range(Some((2, 5))),
+        ];
+        for options in option_sets {
+            let mut converter = CachingConverter::new(options.clone());
+            for _ in 0..2 {
+                for input in &edits {
+                    assert_eq!(
+                        converter.convert_fragment(input),
+                        convert_fragment(input, &options),
+                        "{options:?}\n{input}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_hook_disables_render_cache() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let mut converter = CachingConverter::new(RenderOptions::default());
+        converter.renderer.set_node_hook(Box::new(move |_| {
+            counter.set(counter.get() + 1);
+            None
+        }));
+        converter.convert_fragment(DOC);
+        let first = calls.get();
+        assert!(first > 0);
+
+        // フックを設定していると、同じ文書でも行を変換し直してフックを呼び出す
+        converter.convert_fragment(DOC);
+        assert_eq!(calls.get(), first * 2);
+        assert_eq!(converter.parse_count(), 7);
+        assert_eq!(converter.render_count(), 14);
+    }
+
+    #[test]
+    fn test_clear_discards_cached_lines() {
+        let mut converter = CachingConverter::new(RenderOptions::default());
+        convert_and_check(&mut converter, DOC);
+        converter.clear();
+        convert_and_check(&mut converter, DOC);
+        assert_eq!(converter.parse_count(), 14);
+    }
+}
@@ -25,17 +25,21 @@ impl<'a> DocumentRenderer<'a> {
 
     /// HTMLヘッダーを出力
     pub fn render_html_head(&self, output: &mut String, header_info: &HeaderInfo) {
+        let charset = self.options.output_encoding.charset();
+
         // XML宣言とDOCTYPE
-        output.push_str("<?xml version=\"1.0\" encoding=\"Shift_JIS\"?>\r\n");
+        output.push_str(&format!(
+            "<?xml version=\"1.0\" encoding=\"{charset}\"?>\r\n"
+        ));
         output.push_str("<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.1//EN\"\r\n");
         output.push_str("    \"http://www.w3.org/TR/xhtml11/DTD/xhtml11.dtd\">\r\n");
         output.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"ja\" >\r\n");
         output.push_str("<head>\r\n");
 
         // メタ情報
-        output.push_str(
-            "\t<meta http-equiv=\"Content-Type\" content=\"text/html;charset=Shift_JIS\" />\r\n",
-        );
+        output.push_str(&format!(
+            "\t<meta http-equiv=\"Content-Type\" content=\"text/html;charset={charset}\" />\r\n"
+        ));
         output.push_str("\t<meta http-equiv=\"content-style-type\" content=\"text/css\" />\r\n");
 
         // CSSリンク
@@ -53,10 +57,12 @@ impl<'a> DocumentRenderer<'a> {
         };
         output.push_str(&format!("\t<title>{}</title>\r\n", html_title));
 
-        // jQuery
-        output.push_str(
-            "\t<script type=\"text/javascript\" src=\"../../jquery-1.4.2.min.js\"></script>\r\n",
-        );
+        // スクリプト（既定はjQuery）
+        for script in &self.options.scripts {
+            output.push_str(&format!(
+                "\t<script type=\"text/javascript\" src=\"{script}\"></script>\r\n"
+            ));
+        }
 
         // Dublin Core メタデータ
         output
@@ -127,7 +133,12 @@ impl<'a> DocumentRenderer<'a> {
             ));
         }
 
-        if let Some(translator) = &header_info.translator {
+        // 編者と翻訳者を兼ねる場合（`編・訳`）は編者としてのみ出力
+        if let Some(translator) = header_info
+            .translator
+            .as_ref()
+            .filter(|translator| header_info.editor.as_ref() != Some(*translator))
+        {
             output.push_str(&format!(
                 "<h2 class=\"translator\">{}</h2>\r\n",
                 html_escape(translator)
@@ -178,6 +189,23 @@ impl<'a> DocumentRenderer<'a> {
         output.push_str("</div>\r\n");
     }
 
+    /// 後注セクションを出力（後注がない場合は何も出力しない）
+    pub fn render_endnotes(&self, output: &mut String, endnotes: &[String]) {
+        if endnotes.is_empty() {
+            return;
+        }
+        output.push_str("<div class=\"endnotes\">\r\n");
+        output.push_str("<ol>\r\n");
+        for (i, note) in endnotes.iter().enumerate() {
+            let n = i + 1;
+            output.push_str(&format!(
+                "<li id=\"endnote-{n}\"><a href=\"#endnote-ref-{n}\">[{n}]</a> ［＃{note}］</li>\r\n"
+            ));
+        }
+        output.push_str("</ol>\r\n");
+        output.push_str("</div>\r\n");
+    }
+
     /// 表記についてセクションを出力
     pub fn render_notation_notes(
         &self,
@@ -185,15 +213,21 @@ impl<'a> DocumentRenderer<'a> {
         has_notes: bool,
         has_jisx0213: bool,
         has_accent: bool,
+        has_kunojiten: bool,
         unconverted_gaiji: &[UnconvertedGaiji],
     ) {
+        // 外字一覧表もこのセクションに含まれるため、あわせて省略される
+        if !self.options.notation_notes {
+            return;
+        }
+
         output.push_str("<div class=\"notation_notes\">\r\n");
         output.push_str("<hr />\r\n");
         output.push_str("<br />\r\n");
         output.push_str("●表記について<br />\r\n");
         output.push_str("<ul>\r\n");
 
-        // XHTML1.1準拠
+        // XHTML1.1準拠（このセクションは完全な文書の出力時のみ出力される）
         output.push_str(
             "\t<li>このファイルは W3C 勧告 XHTML1.1 にそった形式で作成されています。</li>\r\n",
         );
@@ -215,6 +249,11 @@ impl<'a> DocumentRenderer<'a> {
             );
         }
 
+        // くの字点を使用した場合
+        if has_kunojiten {
+            output.push_str("\t<li>「くの字点」は「〳〵」「〴〵」の文字で表しました。</li>\r\n");
+        }
+
         // 未変換外字がある場合
         if !unconverted_gaiji.is_empty() {
             output.push_str("\t<li>この作品には、JIS X 0213にない、以下の文字が用いられています。（数字は、底本中の出現「ページ-行」数。）これらの文字は本文内では「※［＃…］」の形で示しました。</li>\r\n");
@@ -253,6 +292,10 @@ impl<'a> DocumentRenderer<'a> {
 
     /// 図書カードセクションを出力
     pub fn render_card_section(&self, output: &mut String) {
+        if !self.options.card_section {
+            return;
+        }
+
         output.push_str("<div id=\"card\">\r\n");
         output.push_str("<hr />\r\n");
         output.push_str("<br />\r\n");
@@ -265,9 +308,10 @@ impl<'a> DocumentRenderer<'a> {
 
     /// main_text開始タグを出力
     pub fn render_main_text_start(&self, output: &mut String) {
-        output.push_str(
-            "<div id=\"contents\" style=\"display:none\"></div><div class=\"main_text\">",
-        );
+        output.push_str(&format!(
+            "<div id=\"contents\" style=\"display:none\"></div><div class=\"main_text {}\">",
+            self.options.writing_mode.css_class()
+        ));
     }
 
     /// main_text終了タグを出力
@@ -3,17 +3,25 @@
 //! 青空文庫形式のテキストをHTMLに変換します。
 
 mod block_manager;
+mod cache;
 mod document_renderer;
 mod node_renderer;
+mod normalize;
 mod options;
 mod presentation;
 mod renderer;
 mod tag_generator;
 
-pub use options::RenderOptions;
-pub use presentation::html_escape;
+pub use cache::CachingConverter;
+pub use node_renderer::NodeHook;
+pub use normalize::normalize_html;
+pub use options::{GaijiMode, NoteCollection, RenderOptions, WritingMode};
+pub use presentation::{classify_line, html_escape, LineType};
 pub use renderer::HtmlRenderer;
 
+use aozora_core::token::Token;
+use renderer::LineSource;
+
 /// 青空文庫形式のテキストをHTMLに変換
 ///
 /// # Arguments
@@ -40,6 +48,38 @@ pub fn convert(input: &str, options: &RenderOptions) -> String {
     renderer.render(input)
 }
 
+/// 青空文庫形式のテキストの本文をHTML断片に変換
+///
+/// ヘッダーやメタデータ・底本情報などを含まない、本文部分のHTMLのみを返します。
+///
+/// # Examples
+///
+/// ```
+/// use aozora2::html::{convert_fragment, RenderOptions};
+///
+/// let input = "タイトル\n\n吾輩《わがはい》は猫である";
+/// let html = convert_fragment(input, &RenderOptions::default());
+/// assert!(html.contains("<ruby>"));
+/// assert!(!html.contains("<!DOCTYPE"));
+/// ```
+pub fn convert_fragment(input: &str, options: &RenderOptions) -> String {
+    let mut renderer = HtmlRenderer::new(options.clone());
+    renderer.render_fragment(input)
+}
+
+/// 本文の行ごとに変換済みのトークン列を使ってテキスト全体をHTMLに変換
+///
+/// `body_tokens` は本文の各行（[`body_line_range`](aozora_core::document::body_line_range)）を
+/// `options` と同じ設定でトークン列に変換したものです。
+pub(crate) fn convert_tokenized(
+    input: &str,
+    body_tokens: &[Vec<Token>],
+    options: &RenderOptions,
+) -> String {
+    let mut renderer = HtmlRenderer::new(options.clone());
+    renderer.render_with_source(input, LineSource::Tokens(body_tokens))
+}
+
 /// 1行をHTMLに変換
 pub fn convert_line(line: &str, options: &RenderOptions) -> String {
     let mut renderer = HtmlRenderer::new(options.clone());
@@ -74,4 +114,103 @@ mod tests {
         let html = convert_line("猫《ねこ》", &RenderOptions::default());
         assert!(html.contains("<ruby>"));
     }
+
+    #[test]
+    fn test_toc_ids_match_rendered_anchors() {
+        let input = "タイトル\n\n［＃大見出し］第一部［＃大見出し終わり］\n［＃中見出し］一［＃中見出し終わり］\n本文\n［＃小見出し］上［＃小見出し終わり］\n本文［＃「本文」は中見出し］";
+        let html = convert(input, &RenderOptions::default());
+        let toc = aozora_core::build_toc(input);
+
+        assert_eq!(toc.len(), 4);
+        for entry in &toc {
+            let anchor = format!("id=\"{}\">{}</a>", entry.id, entry.text);
+            assert!(html.contains(&anchor), "missing anchor {anchor}");
+        }
+    }
+
+    #[test]
+    fn test_convert_editor_translator_header() {
+        let html = convert(
+            "タイトル\n著者名\n山田太郎編・訳\n\n本文",
+            &RenderOptions::default(),
+        );
+        assert!(html.contains("<h2 class=\"editor\">山田太郎編・訳</h2>"));
+        assert!(!html.contains("<h2 class=\"translator\">"));
+        assert!(html.contains("<title>著者名 山田太郎編・訳 タイトル</title>"));
+    }
+
+    #[test]
+    fn test_convert_default_boilerplate() {
+        let html = convert("タイトル\n\n本文", &RenderOptions::default());
+        assert!(
+            html.contains("<script type=\"text/javascript\" src=\"../../jquery-1.4.2.min.js\">")
+        );
+        assert!(html.contains("goAZLibCard"));
+    }
+
+    #[test]
+    fn test_convert_without_scripts() {
+        let options = RenderOptions::new().with_scripts(Vec::new());
+        let html = convert("タイトル\n\n本文", &options);
+        assert!(!html.contains("jquery"));
+        // 図書カードは別の設定
+        assert!(html.contains("goAZLibCard"));
+    }
+
+    #[test]
+    fn test_convert_custom_scripts() {
+        let options = RenderOptions::new().with_scripts(vec!["app.js".to_string()]);
+        let html = convert("タイトル\n\n本文", &options);
+        assert!(html.contains("<script type=\"text/javascript\" src=\"app.js\"></script>"));
+        assert!(!html.contains("jquery"));
+    }
+
+    #[test]
+    fn test_convert_without_card_section() {
+        let options = RenderOptions::new().with_card_section(false);
+        let html = convert("タイトル\n\n本文", &options);
+        assert!(!html.contains("goAZLibCard"));
+        assert!(!html.contains("golibcard.js"));
+        assert!(html.ends_with("</body>\r\n</html>\r\n"));
+    }
+
+    #[test]
+    fn test_convert_notation_notes() {
+        let input = "タイトル\n\n本文※［＃「てへん＋劣」、120-8］［＃注記］";
+        let html = convert(input, &RenderOptions::default());
+        assert!(html.contains("<div class=\"notation_notes\">"));
+        assert!(html.contains("<table class=\"gaiji_list\">"));
+    }
+
+    #[test]
+    fn test_convert_without_notation_notes() {
+        let input = "タイトル\n\n本文※［＃「てへん＋劣」、120-8］［＃注記］";
+        let options = RenderOptions::new().with_notation_notes(false);
+        let html = convert(input, &options);
+        assert!(!html.contains("notation_notes"));
+        assert!(!html.contains("●表記について"));
+        assert!(!html.contains("XHTML1.1 にそった形式"));
+        assert!(!html.contains("gaiji_list"));
+        // 本文と図書カードは出力される
+        assert!(html.contains("本文"));
+        assert!(html.contains("goAZLibCard"));
+    }
+
+    #[test]
+    fn test_convert_charset_matches_output_encoding() {
+        use aozora_core::encoding::{decode_to_utf8, encode_from_utf8, OutputEncoding};
+
+        let input = "タイトル\n著者\n\n吾輩《わがはい》は猫である。";
+        let html = convert(input, &RenderOptions::default());
+        assert!(html.contains("encoding=\"Shift_JIS\""));
+        assert!(html.contains("charset=Shift_JIS"));
+        let bytes = encode_from_utf8(&html, OutputEncoding::ShiftJis);
+        assert_eq!(decode_to_utf8(&bytes), html);
+
+        let options = RenderOptions::new().with_output_encoding(OutputEncoding::Utf8);
+        let html = convert(input, &options);
+        assert!(html.contains("encoding=\"UTF-8\""));
+        assert!(html.contains("charset=UTF-8"));
+        assert!(!html.contains("Shift_JIS"));
+    }
 }
@@ -4,14 +4,16 @@
 
 use aozora_core::gaiji::{parse_gaiji, GaijiResult};
 use aozora_core::node::{
-    BlockType, FontSizeType, MidashiLevel, MidashiStyle, Node, RubyDirection, StyleType,
+    BlockParams, BlockType, FontSizeType, MidashiLevel, MidashiStyle, Node, RubyDirection,
+    StyleType,
 };
+use aozora_core::toc::midashi_anchor_id;
 
 use super::block_manager::BlockManager;
-use super::options::RenderOptions;
+use super::options::{GaijiMode, NoteCollection, RenderOptions};
 use super::presentation::{
-    html_escape, jis_code_to_path, midashi_combined_css_class, midashi_html_tag, style_css_class,
-    style_html_tag,
+    contains_kanji, html_escape, is_halfwidth_text, jis_code_to_path, kogaki_as_sub_sup,
+    mapped_style_css_class, midashi_combined_css_class, style_html_tag,
 };
 
 /// 未変換外字情報
@@ -23,6 +25,11 @@ pub struct UnconvertedGaiji {
     pub page_line: String,
 }
 
+/// ノードごとに呼び出されるフック
+///
+/// `Some(html)` を返すとそのノードの出力を置き換え、`None` を返すと既定の変換を行います。
+pub type NodeHook = Box<dyn FnMut(&Node) -> Option<String>>;
+
 /// ノードレンダラー
 pub struct NodeRenderer<'a> {
     options: &'a RenderOptions,
@@ -34,8 +41,16 @@ pub struct NodeRenderer<'a> {
     pub has_accent: bool,
     /// JIS X 0213文字を使用したかどうか
     pub has_jisx0213: bool,
+    /// くの字点（〳〵・〴〵）を使用したかどうか
+    pub has_kunojiten: bool,
     /// 未変換外字のリスト
     pub unconverted_gaiji: Vec<UnconvertedGaiji>,
+    /// 後注として集めた注記（`［＃` と `］` の内側のHTML）
+    pub endnotes: Vec<String>,
+    /// 描画中の横組み（インライン）の入れ子の深さ
+    yokogumi_depth: usize,
+    /// ノードごとのフック
+    node_hook: Option<NodeHook>,
 }
 
 impl<'a> NodeRenderer<'a> {
@@ -47,10 +62,34 @@ impl<'a> NodeRenderer<'a> {
             has_gaiji_images: false,
             has_accent: false,
             has_jisx0213: false,
+            has_kunojiten: false,
             unconverted_gaiji: Vec::new(),
+            endnotes: Vec::new(),
+            yokogumi_depth: 0,
+            node_hook: None,
         }
     }
 
+    /// ノードごとのフックを設定
+    pub fn set_node_hook(&mut self, hook: Option<NodeHook>) {
+        self.node_hook = hook;
+    }
+
+    /// 設定されているフックを取り出す
+    pub fn take_node_hook(&mut self) -> Option<NodeHook> {
+        self.node_hook.take()
+    }
+
+    /// 行の変換結果をキャッシュから再利用できるかどうか
+    ///
+    /// 行をまたいで引き継ぐ状態のうち、キャッシュから取り出す際に再現できるのは
+    /// 後注（[`endnotes`](Self::endnotes)）だけです。ノードごとのフックは
+    /// 副作用を持つ可能性があり、キャッシュから取り出した行では呼び出されないため、
+    /// フックを設定している場合は再利用できません。
+    pub fn is_cacheable(&self) -> bool {
+        self.node_hook.is_none()
+    }
+
     /// ノード列をHTMLに変換
     pub fn render_nodes(&mut self, nodes: &[Node], block_manager: &mut BlockManager) -> String {
         let mut output = String::new();
@@ -62,8 +101,20 @@ impl<'a> NodeRenderer<'a> {
 
     /// 単一ノードをHTMLに変換
     pub fn render_node(&mut self, node: &Node, block_manager: &mut BlockManager) -> String {
+        if let Some(hook) = self.node_hook.as_mut() {
+            if let Some(html) = hook(node) {
+                return html;
+            }
+        }
+
         match node {
-            Node::Text(text) => html_escape(text),
+            Node::Text(text) => {
+                // くの字点はそのまま文字として出力する
+                if text.contains(['〳', '〴']) {
+                    self.has_kunojiten = true;
+                }
+                html_escape(text)
+            }
 
             Node::Ruby {
                 children,
@@ -124,7 +175,12 @@ impl<'a> NodeRenderer<'a> {
 
             Node::Tcy { children } => {
                 let inner = self.render_nodes(children, block_manager);
-                format!("<span dir=\"ltr\">{inner}</span>")
+                // 横組みの中では縦中横は不要なのでそのまま出力する
+                if self.in_yokogumi(block_manager) {
+                    inner
+                } else {
+                    format!("<span dir=\"ltr\">{inner}</span>")
+                }
             }
 
             Node::Keigakomi { children } => {
@@ -133,7 +189,9 @@ impl<'a> NodeRenderer<'a> {
             }
 
             Node::Yokogumi { children } => {
+                self.yokogumi_depth += 1;
                 let inner = self.render_nodes(children, block_manager);
+                self.yokogumi_depth -= 1;
                 format!("<span class=\"yokogumi\">{inner}</span>")
             }
 
@@ -160,8 +218,27 @@ impl<'a> NodeRenderer<'a> {
                 format!("<sub class=\"kaeriten\">{}</sub>", html_escape(text))
             }
 
-            Node::Okurigana(text) => {
-                format!("<sup class=\"okurigana\">{}</sup>", html_escape(text))
+            Node::Okurigana { text, direction } => match direction {
+                RubyDirection::Right => {
+                    format!("<sup class=\"okurigana\">{}</sup>", html_escape(text))
+                }
+                RubyDirection::Left => {
+                    format!("<sub class=\"okurigana_left\">{}</sub>", html_escape(text))
+                }
+            },
+
+            Node::LineBreak => "<br />".to_string(),
+
+            Node::Position { page, chou } => {
+                let mut attrs = String::new();
+                if let Some(page) = page {
+                    let id = block_manager.generate_page_id(*page);
+                    attrs.push_str(&format!(" id=\"{id}\" data-page=\"{page}\""));
+                }
+                if let Some(chou) = chou {
+                    attrs.push_str(&format!(" data-chou=\"{}\"", html_escape(chou)));
+                }
+                format!("<span class=\"position\"{attrs}></span>")
             }
 
             Node::BlockStart { block_type, params } => {
@@ -173,9 +250,13 @@ impl<'a> NodeRenderer<'a> {
                     output.push_str(&block_manager.render_block_end_tag(&bt, &bp));
                 }
 
+                let params = &self.output_block_params(params);
+                let tcy_in_yokogumi =
+                    *block_type == BlockType::Tcy && self.in_yokogumi(block_manager);
                 block_manager.push(*block_type, params.clone());
                 // Burasageは各行で個別にラップするため、開始タグを出力しない
-                if *block_type != BlockType::Burasage {
+                // 横組みの中の縦中横もタグを出力しない
+                if *block_type != BlockType::Burasage && !tcy_in_yokogumi {
                     output.push_str(&block_manager.render_block_start_tag(block_type, params));
                 }
                 output
@@ -184,13 +265,16 @@ impl<'a> NodeRenderer<'a> {
             Node::BlockEnd { block_type, params } => {
                 if let Some(ctx) = block_manager.find_and_close(block_type) {
                     // Burasageは各行で個別にラップするため、終了タグを出力しない
-                    if ctx.block_type == BlockType::Burasage {
+                    if ctx.block_type == BlockType::Burasage
+                        || (ctx.block_type == BlockType::Tcy && self.in_yokogumi(block_manager))
+                    {
                         String::new()
                     } else if ctx.block_type == BlockType::Warigaki
                         || ctx.block_type == BlockType::Style
                     {
                         // 割り注/装飾の場合はBlockEndのparamsを使用
-                        block_manager.render_block_end_tag(&ctx.block_type, params)
+                        let params = self.output_block_params(params);
+                        block_manager.render_block_end_tag(&ctx.block_type, &params)
                     } else {
                         block_manager.render_block_end_tag(&ctx.block_type, &ctx.params)
                     }
@@ -201,7 +285,7 @@ impl<'a> NodeRenderer<'a> {
 
             Node::Note(text) => {
                 self.has_notes = true;
-                format!("<span class=\"notes\">［＃{}］</span>", html_escape(text))
+                self.render_note(html_escape(text))
             }
 
             Node::AnnotationEnd {
@@ -211,34 +295,24 @@ impl<'a> NodeRenderer<'a> {
             } => {
                 self.has_notes = true;
                 let content_html = self.render_nodes(content, block_manager);
-                format!(
-                    "<span class=\"notes\">［＃{}{}{}］</span>",
+                self.render_note(format!(
+                    "{}{}{}",
                     html_escape(prefix),
                     content_html,
                     html_escape(suffix)
-                )
+                ))
             }
 
             Node::UnresolvedReference {
                 target,
                 spec,
                 connector,
-            } => {
-                format!(
-                    "<span class=\"notes\">［＃「{}」{}{}］</span>",
-                    html_escape(target),
-                    html_escape(connector),
-                    html_escape(spec)
-                )
-            }
-
-            Node::DakutenKatakana { num } => match num.as_str() {
-                "2" => "ワ゛".to_string(),
-                "3" => "ヰ゛".to_string(),
-                "4" => "ヱ゛".to_string(),
-                "5" => "ヲ゛".to_string(),
-                _ => String::new(),
-            },
+            } => self.render_note(format!(
+                "「{}」{}{}",
+                html_escape(target),
+                html_escape(connector),
+                html_escape(spec)
+            )),
         }
     }
 
@@ -251,24 +325,73 @@ impl<'a> NodeRenderer<'a> {
         block_manager: &mut BlockManager,
     ) -> String {
         let base_html = self.render_nodes(children, block_manager);
+        if self.options.ruby_only_kanji && !contains_kanji(children) {
+            return base_html;
+        }
         let ruby_html = self.render_nodes(ruby, block_manager);
         // Unicode nbsp (\u{00a0}) を HTML entity &nbsp; に変換
         let ruby_html = ruby_html.replace('\u{00a0}', "&nbsp;");
 
-        match direction {
-            RubyDirection::Right => {
-                format!(
-                    "<ruby><rb>{base_html}</rb><rp>（</rp><rt>{ruby_html}</rt><rp>）</rp></ruby>"
-                )
+        // 読みの文字列は長さの制限や属性に使う場合だけ作る
+        let needs_reading =
+            self.options.max_ruby_len.is_some() || self.options.ruby_data_attr || self.options.aria;
+        let reading: String = if needs_reading {
+            ruby.iter().map(Node::to_text).collect()
+        } else {
+            String::new()
+        };
+        if let Some(max_len) = self.options.max_ruby_len {
+            if reading.chars().count() > max_len {
+                return format!("{base_html}《{ruby_html}》");
             }
-            RubyDirection::Left => {
+        }
+
+        let class = match direction {
+            RubyDirection::Right => "",
+            RubyDirection::Left => " class=\"leftrb\"",
+        };
+        let data_attr = if self.options.ruby_data_attr {
+            format!(" data-ruby=\"{}\"", html_escape(&reading))
+        } else {
+            String::new()
+        };
+        let aria_attr = if self.options.aria {
+            format!(" aria-label=\"{}\"", html_escape(&reading))
+        } else {
+            String::new()
+        };
+        format!(
+            "<ruby{class}{data_attr}{aria_attr}><rb>{base_html}</rb><rp>（</rp><rt>{ruby_html}</rt><rp>）</rp></ruby>"
+        )
+    }
+
+    /// 入力者注を出力
+    ///
+    /// `content_html` は `［＃` と `］` の内側のHTMLです。後注にする場合は
+    /// 注記を集めて、その位置には番号付きの参照を出力します。
+    fn render_note(&mut self, content_html: String) -> String {
+        match self.options.note_collection {
+            NoteCollection::Inline => self.notes_span(&content_html),
+            NoteCollection::Endnotes => {
+                self.endnotes.push(content_html);
+                let n = self.endnotes.len();
                 format!(
-                    "<ruby class=\"leftrb\"><rb>{base_html}</rb><rp>（</rp><rt>{ruby_html}</rt><rp>）</rp></ruby>"
+                    "<sup class=\"endnote-ref\"><a id=\"endnote-ref-{n}\" href=\"#endnote-{n}\">[{n}]</a></sup>"
                 )
             }
         }
     }
 
+    /// 注記の `<span>` を出力
+    fn notes_span(&self, content_html: &str) -> String {
+        let role = if self.options.aria {
+            " role=\"note\""
+        } else {
+            ""
+        };
+        format!("<span class=\"notes\"{role}>［＃{content_html}］</span>")
+    }
+
     /// 装飾をHTMLに変換
     fn render_style(
         &mut self,
@@ -277,11 +400,40 @@ impl<'a> NodeRenderer<'a> {
         block_manager: &mut BlockManager,
     ) -> String {
         let inner = self.render_nodes(children, block_manager);
+        let style_type = self.output_style_type(style_type);
         let tag = style_html_tag(style_type);
-        let class = style_css_class(style_type);
+        let class = mapped_style_css_class(style_type, &self.options.style_class_map);
+        // 半角文字への傍点は、別のクラスを追加して位置を調整できるようにする
+        if style_type.is_bouten() && is_halfwidth_text(children) {
+            return format!("<{tag} class=\"{class} {class}_latin\">{inner}</{tag}>");
+        }
         format!("<{tag} class=\"{class}\">{inner}</{tag}>")
     }
 
+    /// 横組みの中かどうか
+    fn in_yokogumi(&self, block_manager: &BlockManager) -> bool {
+        self.yokogumi_depth > 0 || block_manager.is_inside(BlockType::Yokogumi)
+    }
+
+    /// オプションに応じて出力に使う装飾タイプを決定
+    ///
+    /// 専用クラスを使わない場合、行右小書き・行左小書きは上付き・下付き小文字として出力します。
+    fn output_style_type(&self, style_type: StyleType) -> StyleType {
+        if self.options.use_kogaki_class {
+            style_type
+        } else {
+            kogaki_as_sub_sup(style_type)
+        }
+    }
+
+    /// 装飾ブロックのパラメータを出力用に変換
+    fn output_block_params(&self, params: &BlockParams) -> BlockParams {
+        BlockParams {
+            style_type: params.style_type.map(|s| self.output_style_type(s)),
+            ..params.clone()
+        }
+    }
+
     /// 見出しをHTMLに変換
     fn render_midashi(
         &mut self,
@@ -291,12 +443,12 @@ impl<'a> NodeRenderer<'a> {
         block_manager: &mut BlockManager,
     ) -> String {
         let inner = self.render_nodes(children, block_manager);
-        let tag = midashi_html_tag(level);
+        let tag = block_manager.midashi_html_tag(level);
         let class = midashi_combined_css_class(level, style);
-        let midashi_id = block_manager.generate_midashi_id(level);
+        let id = midashi_anchor_id(block_manager.generate_midashi_id(level));
 
         format!(
-            "<{tag} class=\"{class}\"><a class=\"midashi_anchor\" id=\"midashi{midashi_id}\">{inner}</a></{tag}>"
+            "<{tag} class=\"{class}\"><a class=\"midashi_anchor\" id=\"{id}\">{inner}</a></{tag}>"
         )
     }
 
@@ -330,6 +482,47 @@ impl<'a> NodeRenderer<'a> {
         format!("<span class=\"{class}\" style=\"{style}\">{inner}</span>")
     }
 
+    /// 外字をHTMLに変換（Unicode優先）
+    ///
+    /// Unicodeに対応付けられる外字は文字そのものを、
+    /// 対応付けられないJIS外字は画像を出力します。
+    fn render_gaiji_unicode_preferred(
+        &mut self,
+        description: &str,
+        unicode: Option<&str>,
+        jis_code: Option<&str>,
+    ) -> String {
+        let (unicode, jis_code) = match (unicode, jis_code) {
+            (None, None) => match parse_gaiji(description) {
+                GaijiResult::Unicode(u) => (Some(u), None),
+                GaijiResult::JisConverted { jis_code, unicode } => (Some(unicode), Some(jis_code)),
+                GaijiResult::JisImage { jis_code } => (None, Some(jis_code)),
+                GaijiResult::Unconvertible => (None, None),
+            },
+            (u, jis) => (u.map(str::to_string), jis.map(str::to_string)),
+        };
+
+        match (unicode, jis_code) {
+            (Some(u), _) => html_escape(&u),
+            (None, Some(jis)) => {
+                self.has_gaiji_images = true;
+                let (folder, file) = jis_code_to_path(&jis);
+                format!(
+                    "<img src=\"{}{}/{}.png\" alt=\"※({})\" class=\"gaiji\" />",
+                    self.options.gaiji_dir,
+                    folder,
+                    file,
+                    html_escape(description)
+                )
+            }
+            (None, None) => {
+                self.has_notes = true;
+                self.add_unconverted_gaiji(description);
+                format!("※{}", self.notes_span(&html_escape(description)))
+            }
+        }
+    }
+
     /// 外字をHTMLに変換
     fn render_gaiji(
         &mut self,
@@ -337,6 +530,10 @@ impl<'a> NodeRenderer<'a> {
         unicode: Option<&str>,
         jis_code: Option<&str>,
     ) -> String {
+        if self.options.gaiji_mode == GaijiMode::UnicodePreferred {
+            return self.render_gaiji_unicode_preferred(description, unicode, jis_code);
+        }
+
         match (unicode, jis_code) {
             // JisConverted: unicodeとjis_code両方がある場合
             (Some(u), Some(jis)) => {
@@ -363,10 +560,7 @@ impl<'a> NodeRenderer<'a> {
                 // JISコードがないので画像化できない → 注記として出力
                 self.has_notes = true;
                 self.add_unconverted_gaiji(description);
-                return format!(
-                    "※<span class=\"notes\">［＃{}］</span>",
-                    html_escape(description)
-                );
+                return format!("※{}", self.notes_span(&html_escape(description)));
             }
             // JisImage: jis_codeだけがある場合
             (None, Some(jis)) => {
@@ -391,10 +585,7 @@ impl<'a> NodeRenderer<'a> {
                 } else {
                     self.has_notes = true;
                     self.add_unconverted_gaiji(description);
-                    format!(
-                        "※<span class=\"notes\">［＃{}］</span>",
-                        html_escape(description)
-                    )
+                    format!("※{}", self.notes_span(&html_escape(description)))
                 }
             }
             GaijiResult::JisConverted {
@@ -430,10 +621,7 @@ impl<'a> NodeRenderer<'a> {
             GaijiResult::Unconvertible => {
                 self.has_notes = true;
                 self.add_unconverted_gaiji(description);
-                format!(
-                    "※<span class=\"notes\">［＃{}］</span>",
-                    html_escape(description)
-                )
+                format!("※{}", self.notes_span(&html_escape(description)))
             }
         }
     }
@@ -494,6 +682,25 @@ impl<'a> NodeRenderer<'a> {
             html_escape(alt)
         ));
 
+        if self.options.image_srcset {
+            let filename_2x = hidpi_filename(filename, &self.options.image_2x_suffix);
+            attrs.push_str(&format!(" srcset=\"{filename} 1x, {filename_2x} 2x\""));
+        }
+
         format!("<img {attrs} />")
     }
 }
+
+/// 高解像度画像のファイル名を生成（`fig.png` → `fig@2x.png`）
+///
+/// 拡張子がない場合は末尾に接尾辞を付けます。
+fn hidpi_filename(filename: &str, suffix: &str) -> String {
+    let name_start = filename.rfind('/').map_or(0, |pos| pos + 1);
+    match filename[name_start..].rfind('.') {
+        Some(pos) if pos > 0 => {
+            let (stem, ext) = filename.split_at(name_start + pos);
+            format!("{stem}{suffix}{ext}")
+        }
+        _ => format!("{filename}{suffix}"),
+    }
+}
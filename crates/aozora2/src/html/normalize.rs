@@ -0,0 +1,334 @@
+//! HTMLの正規化
+//!
+//! 見た目に影響しない空白や属性の順序の違いをならし、
+//! 出力の比較（スナップショットテストなど）に使える正規形に変換します。
+
+/// HTMLを比較用の正規形に変換
+///
+/// - ブロック要素のタグ（`div`、`p`、`br` など）の前後の空白（改行を含む）を除きます
+/// - テキスト中の連続する空白を1つの半角空白にまとめます
+///   （インライン要素のタグの前後の空白は表示に影響するため、除かずに1つにまとめます）
+/// - 開始タグの属性を名前順に並べ、値を `"` で囲みます
+/// - 空要素の `/>` を ` />` にそろえます
+/// - `>` で閉じていない末尾の `<` 以降はテキストとして扱います
+///
+/// `<pre>` の中身なども区別せず正規化するため、比較用に使い、出力には使わないでください。
+///
+/// # Examples
+///
+/// ```
+/// use aozora2::html::normalize_html;
+///
+/// assert_eq!(
+///     normalize_html("<img  src='a.png' alt=\"a\"/>\r\n<br>"),
+///     normalize_html("<img alt=\"a\" src=\"a.png\" /><br>"),
+/// );
+/// ```
+pub fn normalize_html(html: &str) -> String {
+    let parts = split_parts(html);
+    let mut output = String::with_capacity(html.len());
+
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            Part::Tag(tag) => output.push_str(&normalize_tag(tag)),
+            Part::Text(text) => {
+                // 文書の先頭・末尾とブロック要素のタグに接する空白は除く
+                let after_block = i == 0 || parts[i - 1].is_block_tag();
+                let before_block = parts.get(i + 1).is_none_or(Part::is_block_tag);
+                let mut text = *text;
+                if after_block {
+                    text = text.trim_start_matches(|c: char| c.is_ascii_whitespace());
+                }
+                if before_block {
+                    text = text.trim_end_matches(|c: char| c.is_ascii_whitespace());
+                }
+                output.push_str(&collapse_whitespace(text));
+            }
+        }
+    }
+
+    output
+}
+
+/// HTMLの断片（タグまたはテキスト）
+enum Part<'a> {
+    Tag(&'a str),
+    Text(&'a str),
+}
+
+impl Part<'_> {
+    /// 前後の空白が表示に影響しないタグかどうか
+    fn is_block_tag(&self) -> bool {
+        match self {
+            Part::Tag(tag) => is_block_tag(tag),
+            Part::Text(_) => false,
+        }
+    }
+}
+
+/// HTMLをタグとテキストに分ける
+fn split_parts(html: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        match rest.find('<') {
+            Some(0) => match tag_end(rest) {
+                Some(end) => {
+                    parts.push(Part::Tag(&rest[..end]));
+                    rest = &rest[end..];
+                }
+                None => {
+                    // 閉じていないタグはテキストとして扱う
+                    parts.push(Part::Text(rest));
+                    rest = "";
+                }
+            },
+            Some(start) => {
+                parts.push(Part::Text(&rest[..start]));
+                rest = &rest[start..];
+            }
+            None => {
+                parts.push(Part::Text(rest));
+                rest = "";
+            }
+        }
+    }
+
+    parts
+}
+
+/// ブロック要素など、前後の空白が表示に影響しないタグかどうか
+///
+/// 終了タグ・コメント・DOCTYPE なども含みます。
+fn is_block_tag(tag: &str) -> bool {
+    let inner = tag.trim_start_matches('<');
+    if inner.starts_with(['!', '?']) {
+        return true;
+    }
+    let name: String = inner
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    matches!(
+        name.as_str(),
+        "html"
+            | "head"
+            | "body"
+            | "title"
+            | "meta"
+            | "link"
+            | "script"
+            | "style"
+            | "div"
+            | "p"
+            | "br"
+            | "hr"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "ul"
+            | "ol"
+            | "li"
+            | "dl"
+            | "dt"
+            | "dd"
+            | "table"
+            | "thead"
+            | "tbody"
+            | "tr"
+            | "th"
+            | "td"
+            | "blockquote"
+            | "pre"
+            | "section"
+            | "nav"
+            | "header"
+            | "footer"
+    )
+}
+
+/// 連続する空白を1つの半角空白にまとめる
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_space {
+                collapsed.push(' ');
+            }
+            in_space = true;
+        } else {
+            collapsed.push(c);
+            in_space = false;
+        }
+    }
+    collapsed
+}
+
+/// `<` で始まる文字列から、タグの終わり（`>` の直後）の位置を返す
+///
+/// 引用符で囲まれた属性値の中の `>` は読み飛ばします。閉じていないタグは `None` を返します。
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// タグを正規化
+fn normalize_tag(tag: &str) -> String {
+    // `split_parts` は `<` で始まり `>` で終わるものだけをタグとする
+    let inner = &tag[1..tag.len() - 1];
+
+    // 終了タグ・コメント・DOCTYPE などは空白をまとめるだけ
+    if inner.starts_with(['/', '!', '?']) {
+        return format!("<{}>", collapse_whitespace(inner.trim()));
+    }
+
+    let (body, self_closing) = match inner.trim_end().strip_suffix('/') {
+        Some(body) => (body, true),
+        None => (inner, false),
+    };
+    let body = body.trim();
+    let name_end = body
+        .find(|c: char| c.is_ascii_whitespace())
+        .unwrap_or(body.len());
+    let (name, attrs) = body.split_at(name_end);
+
+    let mut attributes = parse_attributes(attrs);
+    attributes.sort();
+
+    let mut normalized = format!("<{name}");
+    for (attr_name, value) in attributes {
+        normalized.push(' ');
+        normalized.push_str(&attr_name);
+        if let Some(value) = value {
+            normalized.push_str(&format!("=\"{}\"", value.replace('"', "&quot;")));
+        }
+    }
+    if self_closing {
+        normalized.push_str(" /");
+    }
+    normalized.push('>');
+    normalized
+}
+
+/// 属性の並びを (名前, 値) の組に分解
+fn parse_attributes(s: &str) -> Vec<(String, Option<String>)> {
+    let mut attributes = Vec::new();
+    let mut rest = s.trim_start();
+
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_string();
+        rest = rest[name_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(q).unwrap_or(body.len());
+                        (&body[..end], body.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = after
+                            .find(|c: char| c.is_ascii_whitespace())
+                            .unwrap_or(after.len());
+                        after.split_at(end)
+                    }
+                };
+                rest = remaining;
+                Some(value.to_string())
+            }
+            None => None,
+        };
+
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_attribute_order_and_whitespace() {
+        let a = "<div class=\"jisage_1\" style=\"margin-left: 1em\">\r\n  本文<br />\r\n</div>";
+        let b = "<div style='margin-left: 1em'   class=\"jisage_1\">本文<br/></div>";
+        assert_eq!(normalize_html(a), normalize_html(b));
+        assert_eq!(
+            normalize_html(a),
+            "<div class=\"jisage_1\" style=\"margin-left: 1em\">本文<br /></div>"
+        );
+    }
+
+    #[test]
+    fn test_normalize_text_whitespace() {
+        assert_eq!(
+            normalize_html("<p>吾輩は\r\n  猫である</p>"),
+            "<p>吾輩は 猫である</p>"
+        );
+        assert_eq!(
+            normalize_html("  前 <b>太字</b>  後  "),
+            "前 <b>太字</b> 後"
+        );
+    }
+
+    #[test]
+    fn test_normalize_inline_tag_whitespace_is_significant() {
+        assert_ne!(normalize_html("a <b>x</b>"), normalize_html("a<b>x</b>"));
+        assert_ne!(normalize_html("<b>x</b> a"), normalize_html("<b>x</b>a"));
+        // ブロック要素の前後の空白は除く
+        assert_eq!(
+            normalize_html("<div>\r\n  <p>本文</p>\r\n</div>"),
+            "<div><p>本文</p></div>"
+        );
+        assert_eq!(
+            normalize_html("一行目<br />\r\n二行目"),
+            "一行目<br />二行目"
+        );
+    }
+
+    #[test]
+    fn test_normalize_unclosed_tag() {
+        assert_eq!(normalize_html("本文<div"), "本文<div");
+        assert_eq!(
+            normalize_html("<p>本文</p><div class='a"),
+            "<p>本文</p><div class='a"
+        );
+    }
+
+    #[test]
+    fn test_normalize_special_tags() {
+        assert_eq!(
+            normalize_html("<!DOCTYPE  html>\r\n<html  lang=\"ja\">"),
+            "<!DOCTYPE html><html lang=\"ja\">"
+        );
+        // 値のない属性、属性値中の > と "
+        assert_eq!(
+            normalize_html("<input title='a>\"b' checked>"),
+            "<input checked title=\"a>&quot;b\">"
+        );
+    }
+}
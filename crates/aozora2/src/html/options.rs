@@ -1,18 +1,115 @@
 //! レンダリングオプション
 
+use std::collections::HashMap;
+
+use aozora_core::encoding::OutputEncoding;
+use aozora_core::limits::Limits;
+use aozora_core::node::StyleType;
+
+use super::presentation::DEFAULT_HEADING_BASE_LEVEL;
+
+/// 外字の出力方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GaijiMode {
+    /// `use_jisx0213`/`use_unicode` の指定に従う（従来の動作）
+    #[default]
+    Compatible,
+    /// Unicodeに対応付けられる外字は文字そのものを出力し、
+    /// 対応付けられないJIS外字のみ画像で出力する
+    UnicodePreferred,
+}
+
+/// 本文の書字方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WritingMode {
+    /// 縦書き（青空文庫の既定）
+    #[default]
+    Vertical,
+    /// 横書き
+    Horizontal,
+}
+
+/// 注記（`［＃…］`）の出力方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteCollection {
+    /// 注記をその位置に出力する（従来の動作）
+    #[default]
+    Inline,
+    /// 注記を番号付きの参照に置き換え、文書の末尾に後注としてまとめて出力する
+    Endnotes,
+}
+
+impl WritingMode {
+    /// main_textのdivに付けるクラス名
+    pub fn css_class(self) -> &'static str {
+        match self {
+            WritingMode::Vertical => "vertical",
+            WritingMode::Horizontal => "horizontal",
+        }
+    }
+}
+
 /// HTML変換オプション
-#[derive(Debug, Clone)]
+///
+/// 各設定は `with_*` メソッドで変更し、公開フィールドから読み出せます。
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RenderOptions {
     /// 外字画像ディレクトリのパス
     pub gaiji_dir: String,
     /// CSSファイルのパス
     pub css_files: Vec<String>,
+    /// `<head>` に出力するスクリプトのパス（空の場合はスクリプトタグを出力しない）
+    pub scripts: Vec<String>,
+    /// 図書カードセクションを出力
+    pub card_section: bool,
+    /// 表記について（notation_notes）セクションを出力
+    pub notation_notes: bool,
     /// JIS X 0213の数値実体参照を使用
     pub use_jisx0213: bool,
     /// Unicodeの数値実体参照を使用
     pub use_unicode: bool,
+    /// 外字の出力方式
+    pub gaiji_mode: GaijiMode,
+    /// 行右小書き・行左小書きを `<sup>`/`<sub>` ではなく専用クラスの `<span>` で出力
+    pub use_kogaki_class: bool,
+    /// 半角カタカナを全角に変換してから解析
+    pub normalize_halfwidth_kana: bool,
+    /// 本文の連続する行を `<p>` でまとめる（`<br />` による改行の代わり）
+    pub paragraphs: bool,
+    /// 本文のブロック以外の行を元の行番号（`data-line` 属性）付きの `<span>` で囲む
+    pub source_line_attrs: bool,
+    /// 本文のブロック要素をブロックの入れ子の深さに応じて字下げして出力
+    pub pretty: bool,
+    /// 親文字に漢字を含むルビだけを出力（かなだけの親文字はルビを除いて出力）
+    pub ruby_only_kanji: bool,
+    /// `<ruby>` 要素にルビのテキストを `data-ruby` 属性として出力
+    pub ruby_data_attr: bool,
+    /// ルビとして出力する読みの最大文字数（超えた読みは親文字の後に `《》` 付きで出力）
+    pub max_ruby_len: Option<usize>,
+    /// ルビ・注記にアクセシビリティ用のARIA属性を出力
+    pub aria: bool,
+    /// 出力の仮名と結合用の濁点・半濁点の組を合成済みの文字にまとめる
+    pub compose_voiced_kana: bool,
+    /// 装飾のCSSクラス名の置き換え（指定のない装飾は既定のクラス名）
+    pub style_class_map: HashMap<StyleType, String>,
+    /// 本文のうち変換する行の範囲（1始まり、両端を含む）
+    pub line_range: Option<(usize, usize)>,
+    /// 挿絵に高解像度画像の `srcset` 属性を出力
+    pub image_srcset: bool,
+    /// 高解像度画像のファイル名に付ける接尾辞（拡張子の前に挿入）
+    pub image_2x_suffix: String,
+    /// 出力エンコーディング（XML宣言・metaタグのcharsetに反映）
+    pub output_encoding: OutputEncoding,
+    /// 本文の書字方向（main_textのdivのクラスに反映）
+    pub writing_mode: WritingMode,
+    /// 注記の出力方式
+    pub note_collection: NoteCollection,
+    /// 大見出しの見出しレベル（中見出し・小見出しはその下のレベル、h6まで）
+    pub heading_base_level: u8,
     /// ドキュメントのタイトル
     pub title: Option<String>,
+    /// 1行あたりの入力サイズ上限
+    pub limits: Limits,
 }
 
 impl Default for RenderOptions {
@@ -20,9 +117,32 @@ impl Default for RenderOptions {
         Self {
             gaiji_dir: "../../../gaiji/".to_string(),
             css_files: vec!["../../aozora.css".to_string()],
+            scripts: vec!["../../jquery-1.4.2.min.js".to_string()],
+            card_section: true,
+            notation_notes: true,
             use_jisx0213: false,
             use_unicode: false,
+            gaiji_mode: GaijiMode::default(),
+            use_kogaki_class: false,
+            normalize_halfwidth_kana: false,
+            paragraphs: false,
+            source_line_attrs: false,
+            pretty: false,
+            ruby_only_kanji: false,
+            ruby_data_attr: false,
+            max_ruby_len: None,
+            aria: false,
+            compose_voiced_kana: false,
+            style_class_map: HashMap::new(),
+            line_range: None,
+            image_srcset: false,
+            image_2x_suffix: "@2x".to_string(),
+            output_encoding: OutputEncoding::default(),
+            writing_mode: WritingMode::default(),
+            note_collection: NoteCollection::default(),
+            heading_base_level: DEFAULT_HEADING_BASE_LEVEL,
             title: None,
+            limits: Limits::default(),
         }
     }
 }
@@ -45,6 +165,24 @@ impl RenderOptions {
         self
     }
 
+    /// スクリプトを設定
+    pub fn with_scripts(mut self, scripts: Vec<String>) -> Self {
+        self.scripts = scripts;
+        self
+    }
+
+    /// 図書カードセクションの出力を設定
+    pub fn with_card_section(mut self, enabled: bool) -> Self {
+        self.card_section = enabled;
+        self
+    }
+
+    /// 表記についてセクションの出力を設定
+    pub fn with_notation_notes(mut self, enabled: bool) -> Self {
+        self.notation_notes = enabled;
+        self
+    }
+
     /// JIS X 0213を使用
     pub fn with_jisx0213(mut self, use_it: bool) -> Self {
         self.use_jisx0213 = use_it;
@@ -57,11 +195,168 @@ impl RenderOptions {
         self
     }
 
+    /// 外字の出力方式を設定
+    pub fn with_gaiji_mode(mut self, mode: GaijiMode) -> Self {
+        self.gaiji_mode = mode;
+        self
+    }
+
+    /// 行右小書き・行左小書きに専用クラスを使用
+    pub fn with_kogaki_class(mut self, use_it: bool) -> Self {
+        self.use_kogaki_class = use_it;
+        self
+    }
+
+    /// 半角カタカナの全角変換を設定
+    pub fn with_normalize_halfwidth_kana(mut self, enabled: bool) -> Self {
+        self.normalize_halfwidth_kana = enabled;
+        self
+    }
+
+    /// 段落（`<p>`）による出力を設定
+    pub fn with_paragraphs(mut self, enabled: bool) -> Self {
+        self.paragraphs = enabled;
+        self
+    }
+
+    /// 行番号属性の出力を設定
+    pub fn with_source_line_attrs(mut self, enabled: bool) -> Self {
+        self.source_line_attrs = enabled;
+        self
+    }
+
+    /// 本文の字下げ（整形）出力を設定
+    ///
+    /// 有効にすると、ブロックの入れ子1段につき空白2つで各行を字下げします。
+    /// 行内の要素は1行のままで、空白以外の出力は変わりません。
+    pub fn with_pretty(mut self, enabled: bool) -> Self {
+        self.pretty = enabled;
+        self
+    }
+
+    /// 漢字の親文字だけにルビを出力するかどうかを設定
+    ///
+    /// 有効にすると、親文字に漢字（外字を含む）がないルビは親文字だけを出力します。
+    pub fn with_ruby_only_kanji(mut self, enabled: bool) -> Self {
+        self.ruby_only_kanji = enabled;
+        self
+    }
+
+    /// ルビの `data-ruby` 属性の出力を設定
+    ///
+    /// 有効にすると `<ruby data-ruby="かんじ">` のようにルビのテキストを属性にも出力します。
+    /// `<rt>` はそのまま出力します。
+    pub fn with_ruby_data_attr(mut self, enabled: bool) -> Self {
+        self.ruby_data_attr = enabled;
+        self
+    }
+
+    /// ルビの読みの最大文字数を設定
+    ///
+    /// 読みがこの文字数を超えるルビは `<ruby>` にせず、親文字の後に読みを
+    /// `《》` で囲んだテキストとして出力します。`《》` の閉じ忘れで段落全体が
+    /// 読みになってしまった入力でも表示が崩れないようにするためのものです。
+    pub fn with_max_ruby_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_ruby_len = max_len;
+        self
+    }
+
+    /// ARIA属性の出力を設定
+    ///
+    /// 有効にするとルビに読みの `aria-label` 属性を、注記の `<span>` に
+    /// `role="note"` 属性を出力し、スクリーンリーダーで読み上げやすくします。
+    pub fn with_aria(mut self, enabled: bool) -> Self {
+        self.aria = enabled;
+        self
+    }
+
+    /// 濁点・半濁点の合成を設定
+    ///
+    /// 有効にすると、出力中の仮名と結合用の濁点（U+3099）・半濁点（U+309A）の組を、
+    /// 合成済みの文字があればその文字にまとめます（`ハ` + U+309A → `パ`）。
+    /// 合成済みの文字がない組（`カ゚` など）やアクセント記号などの結合文字はそのまま出力します。
+    /// NFC正規化は行いません（ラテン文字と結合用のアクセント記号の組などは合成しません）。
+    /// 変換後のHTMLに対して行うため、タグや属性は変わりません。
+    /// 詳しくは [`compose_voiced_marks`](aozora_core::kana::compose_voiced_marks) を参照してください。
+    pub fn with_compose_voiced_kana(mut self, enabled: bool) -> Self {
+        self.compose_voiced_kana = enabled;
+        self
+    }
+
+    /// 装飾のCSSクラス名の置き換えを設定
+    ///
+    /// 指定した装飾は `<em class="sesame_dot">` や `<span class="futoji">` などの
+    /// 既定のクラス名の代わりに、指定したクラス名で出力します。
+    /// 太字・斜体のブロック（`［＃ここから太字］`など）にも適用します。
+    pub fn with_style_class_map(mut self, class_map: HashMap<StyleType, String>) -> Self {
+        self.style_class_map = class_map;
+        self
+    }
+
+    /// 本文の変換範囲を設定
+    ///
+    /// 範囲より前の行は出力せずに解析し、範囲をまたぐブロックの開始タグを補います。
+    pub fn with_line_range(mut self, range: Option<(usize, usize)>) -> Self {
+        self.line_range = range;
+        self
+    }
+
+    /// 挿絵の `srcset` 属性の出力を設定
+    ///
+    /// 有効にすると `fig.png` に対して `srcset="fig.png 1x, fig@2x.png 2x"` を出力します。
+    pub fn with_image_srcset(mut self, enabled: bool) -> Self {
+        self.image_srcset = enabled;
+        self
+    }
+
+    /// 高解像度画像のファイル名の接尾辞を設定
+    pub fn with_image_2x_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.image_2x_suffix = suffix.into();
+        self
+    }
+
+    /// 出力エンコーディングを設定
+    pub fn with_output_encoding(mut self, encoding: OutputEncoding) -> Self {
+        self.output_encoding = encoding;
+        self
+    }
+
+    /// 本文の書字方向を設定
+    pub fn with_writing_mode(mut self, mode: WritingMode) -> Self {
+        self.writing_mode = mode;
+        self
+    }
+
+    /// 注記の出力方式を設定
+    ///
+    /// [`NoteCollection::Endnotes`] にすると、入力者注の `［＃…］` を `[1]`, `[2]`, … の
+    /// 参照に置き換え、注記の本文を `<div class="endnotes">` にまとめて出力します。
+    /// 外字の説明は外字の位置に残します。
+    pub fn with_note_collection(mut self, collection: NoteCollection) -> Self {
+        self.note_collection = collection;
+        self
+    }
+
+    /// 大見出しの見出しレベルを設定
+    ///
+    /// 既定は3で、大見出し・中見出し・小見出しを h3・h4・h5 にします。
+    /// 1にすると h1・h2・h3 になります。h6を超えるレベルは h6 になります。
+    pub fn with_heading_base_level(mut self, level: u8) -> Self {
+        self.heading_base_level = level;
+        self
+    }
+
     /// タイトルを設定
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
         self.title = Some(title.into());
         self
     }
+
+    /// 入力サイズ上限を設定
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -87,4 +382,25 @@ mod tests {
         assert!(opts.use_jisx0213);
         assert_eq!(opts.title, Some("テスト".to_string()));
     }
+
+    #[test]
+    fn test_builder_values_readable_and_comparable() {
+        let opts = RenderOptions::new()
+            .with_gaiji_dir("gaiji/")
+            .with_css_files(vec!["a.css".to_string(), "b.css".to_string()])
+            .with_unicode(true)
+            .with_paragraphs(true)
+            .with_line_range(Some((3, 5)));
+
+        assert_eq!(opts.gaiji_dir, "gaiji/");
+        assert_eq!(opts.css_files, ["a.css", "b.css"]);
+        assert!(opts.use_unicode);
+        assert!(!opts.use_jisx0213);
+        assert!(opts.paragraphs);
+        assert_eq!(opts.line_range, Some((3, 5)));
+
+        assert_eq!(opts, opts.clone());
+        assert_ne!(opts, RenderOptions::default());
+        assert_eq!(RenderOptions::new(), RenderOptions::default());
+    }
 }
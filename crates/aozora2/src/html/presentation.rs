@@ -2,20 +2,33 @@
 //!
 //! CSSクラス名とHTMLタグ名のマッピングを提供します。
 
-use aozora_core::node::{MidashiLevel, MidashiStyle, StyleType};
+use std::collections::HashMap;
+
+use aozora_core::char_type::CharType;
+use aozora_core::node::{MidashiLevel, MidashiStyle, Node, StyleType};
 
 /// 行のHTML出力タイプ
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineType {
     /// 空行
     Empty,
-    /// ブロック要素（div, h3-h5など）- brタグ不要、ぶら下げラップ不要
+    /// ブロック要素（div, h1-h6など）- brタグ不要、ぶら下げラップ不要
     Block,
     /// インラインコンテンツ - brタグ必要、ぶら下げラップ可能
     Inline,
 }
 
 /// HTMLの行タイプを判定
+///
+/// # Examples
+///
+/// ```
+/// use aozora2::html::{classify_line, LineType};
+///
+/// assert_eq!(classify_line(""), LineType::Empty);
+/// assert_eq!(classify_line("<div class=\"jisage_2\">"), LineType::Block);
+/// assert_eq!(classify_line("本文"), LineType::Inline);
+/// ```
 pub fn classify_line(html: &str) -> LineType {
     if html.is_empty() {
         return LineType::Empty;
@@ -23,13 +36,9 @@ pub fn classify_line(html: &str) -> LineType {
 
     // ブロック要素の開始/終了で終わる場合
     if html.starts_with("<div class=\"")
-        || html.starts_with("<h3")
-        || html.starts_with("<h4")
-        || html.starts_with("<h5")
+        || starts_with_heading(html)
         || html.ends_with("</div>")
-        || html.ends_with("</h3>")
-        || html.ends_with("</h4>")
-        || html.ends_with("</h5>")
+        || ends_with_heading_end(html)
     {
         return LineType::Block;
     }
@@ -72,6 +81,30 @@ pub fn style_css_class(style_type: StyleType) -> &'static str {
         StyleType::Italic => "shatai",
         StyleType::Subscript => "subscript",
         StyleType::Superscript => "superscript",
+        StyleType::GyouMigiKogaki => "gyou-migi-kogaki",
+        StyleType::GyouHidariKogaki => "gyou-hidari-kogaki",
+        StyleType::Nibai => "nibai",
+    }
+}
+
+/// StyleType のCSSクラス名を、置き換えの指定があればそれを優先して取得
+pub fn mapped_style_css_class(
+    style_type: StyleType,
+    class_map: &HashMap<StyleType, String>,
+) -> &str {
+    class_map
+        .get(&style_type)
+        .map_or(style_css_class(style_type), String::as_str)
+}
+
+/// 行右小書き・行左小書きを上付き・下付き小文字として扱う場合の装飾タイプを取得
+///
+/// それ以外の装飾タイプはそのまま返します。
+pub fn kogaki_as_sub_sup(style_type: StyleType) -> StyleType {
+    match style_type {
+        StyleType::GyouMigiKogaki => StyleType::Superscript,
+        StyleType::GyouHidariKogaki => StyleType::Subscript,
+        other => other,
     }
 }
 
@@ -80,7 +113,11 @@ pub fn style_html_tag(style_type: StyleType) -> &'static str {
     match style_type {
         StyleType::Subscript => "sub",
         StyleType::Superscript => "sup",
-        StyleType::Bold | StyleType::Italic => "span",
+        StyleType::Bold
+        | StyleType::Italic
+        | StyleType::GyouMigiKogaki
+        | StyleType::GyouHidariKogaki
+        | StyleType::Nibai => "span",
         _ => "em", // すべての傍点・傍線は<em>タグを使用
     }
 }
@@ -101,13 +138,69 @@ pub fn midashi_combined_css_class(level: MidashiLevel, style: MidashiStyle) -> S
     }
 }
 
+/// 大見出しの既定の見出しレベル（h1・h2はタイトル・著者名に使用）
+pub const DEFAULT_HEADING_BASE_LEVEL: u8 = 3;
+
 /// MidashiLevel のHTMLタグ名を取得
-pub fn midashi_html_tag(level: MidashiLevel) -> &'static str {
-    match level {
-        MidashiLevel::O => "h3",
-        MidashiLevel::Naka => "h4",
-        MidashiLevel::Ko => "h5",
+///
+/// 大見出しを `base_level`、中見出しを1つ下、小見出しを2つ下のレベルにします（h6まで）。
+pub fn midashi_html_tag(level: MidashiLevel, base_level: u8) -> &'static str {
+    const TAGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+    let offset = match level {
+        MidashiLevel::O => 0,
+        MidashiLevel::Naka => 1,
+        MidashiLevel::Ko => 2,
+    };
+    let level = base_level.max(1).saturating_add(offset).min(6);
+    TAGS[usize::from(level) - 1]
+}
+
+/// 見出しの開始タグ（`<h1`〜`<h6`）で始まるかどうか
+fn starts_with_heading(html: &str) -> bool {
+    (1..=6).any(|n| html.starts_with(&format!("<h{n}")))
+}
+
+/// 見出しの終了タグ（`</h1>`〜`</h6>`）で終わるかどうか
+fn ends_with_heading_end(html: &str) -> bool {
+    (1..=6).any(|n| html.ends_with(&format!("</h{n}>")))
+}
+
+/// 見出しの開始タグ（`<h1`〜`<h6`）を含むかどうか
+fn contains_heading(html: &str) -> bool {
+    (1..=6).any(|n| html.contains(&format!("<h{n}")))
+}
+
+/// ノード列のテキストがすべて半角文字かどうか（外字や空白だけのテキストは対象外）
+pub fn is_halfwidth_text(nodes: &[Node]) -> bool {
+    let mut has_text = false;
+    for node in nodes {
+        if let Node::Gaiji { .. } = node {
+            return false;
+        }
+        for c in node.to_text().chars() {
+            // 空白は判定に使わない（空白だけのテキストは対象外）
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            match CharType::classify(c) {
+                CharType::Hankaku | CharType::HankakuTerminate => {}
+                _ => return false,
+            }
+            has_text = true;
+        }
     }
+    has_text
+}
+
+/// ノード列が漢字を含むかどうか（外字は漢字とみなす）
+pub fn contains_kanji(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Gaiji { .. } => true,
+        _ => node
+            .to_text()
+            .chars()
+            .any(|c| CharType::classify(c) == CharType::Kanji),
+    })
 }
 
 /// HTMLエスケープ
@@ -144,13 +237,7 @@ pub fn is_block_only_line(html: &str) -> bool {
     }
 
     // </h1>, </h2>, etc.で終わる（ただし同行見出しと窓見出しは除く）
-    if html.ends_with("</h1>")
-        || html.ends_with("</h2>")
-        || html.ends_with("</h3>")
-        || html.ends_with("</h4>")
-        || html.ends_with("</h5>")
-        || html.ends_with("</h6>")
-    {
+    if ends_with_heading_end(html) {
         // 同行見出しと窓見出しの場合は<br />を追加する
         if !html.contains("dogyo-") && !html.contains("mado-") {
             return true;
@@ -177,10 +264,8 @@ pub fn is_block_only_line(html: &str) -> bool {
     }
 
     // 見出し開始タグで終わる
-    if html.ends_with("\">") {
-        if html.contains("<h3") || html.contains("<h4") || html.contains("<h5") {
-            return true;
-        }
+    if html.ends_with("\">") && contains_heading(html) {
+        return true;
     }
 
     // 全体が単一のタグ: ^<[^>]*>$
@@ -265,6 +350,21 @@ mod tests {
         assert_eq!(classify_line("</h3>"), LineType::Block);
         assert_eq!(classify_line("<h4>title</h4>"), LineType::Block);
         assert_eq!(classify_line("<h5>title</h5>"), LineType::Block);
+        assert_eq!(classify_line("<h1>title</h1>"), LineType::Block);
+        assert_eq!(classify_line("<hr />"), LineType::Inline);
+    }
+
+    #[test]
+    fn test_midashi_html_tag_base_level() {
+        let tags = |base| {
+            [MidashiLevel::O, MidashiLevel::Naka, MidashiLevel::Ko]
+                .map(|level| midashi_html_tag(level, base))
+        };
+        assert_eq!(tags(DEFAULT_HEADING_BASE_LEVEL), ["h3", "h4", "h5"]);
+        assert_eq!(tags(1), ["h1", "h2", "h3"]);
+        // h6を超える場合はh6に揃える
+        assert_eq!(tags(5), ["h5", "h6", "h6"]);
+        assert_eq!(tags(0), ["h1", "h2", "h3"]);
     }
 
     #[test]
@@ -299,4 +399,12 @@ mod tests {
         assert!(is_block_only_line("<div class=\"test\">"));
         assert!(!is_block_only_line("text"));
     }
+
+    #[test]
+    fn test_is_halfwidth_text() {
+        assert!(is_halfwidth_text(&[Node::text("ABC def")]));
+        assert!(!is_halfwidth_text(&[Node::text("ABC漢字")]));
+        assert!(!is_halfwidth_text(&[Node::text("")]));
+        assert!(!is_halfwidth_text(&[Node::text("  ")]));
+    }
 }
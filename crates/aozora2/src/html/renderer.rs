@@ -2,35 +2,171 @@
 //!
 //! ASTノードをHTMLに変換します。
 
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
 use aozora_core::document::{
-    extract_after_text_lines, extract_bibliographical_lines, extract_body_lines,
-    extract_header_info,
+    body_line_range, extract_after_text_lines, extract_bibliographical_lines, extract_header_info,
+    HeaderInfo,
 };
-use aozora_core::node::Node;
+use aozora_core::encoding::encode_from_utf8;
+use aozora_core::kana::{compose_voiced_marks, normalize_halfwidth_kana};
+use aozora_core::node::{Node, StyleType};
 use aozora_core::parser::parse;
 use aozora_core::parser::reference_resolver::resolve_inline_ruby;
-use aozora_core::tokenizer::tokenize;
+use aozora_core::token::Token;
+use aozora_core::tokenizer::tokenize_with_limits;
 
 use super::block_manager::BlockManager;
+use super::cache::LineCache;
 use super::document_renderer::DocumentRenderer;
-use super::node_renderer::NodeRenderer;
+use super::node_renderer::{NodeHook, NodeRenderer};
 use super::options::RenderOptions;
 use super::presentation::{auto_link, classify_line, is_block_only_line, LineType};
 
+/// 本文の各行のノード列の求め方
+pub(crate) enum LineSource<'a> {
+    /// 各行をその場で解析する
+    Parse,
+    /// 行単位のキャッシュから取り出し、なければ解析する
+    Cache(&'a mut LineCache),
+    /// 本文の行ごとに変換済みのトークン列（本文の行と同じ順）を解析する
+    Tokens(&'a [Vec<Token>]),
+}
+
 /// HTMLレンダラー
-#[derive(Debug, Clone)]
 pub struct HtmlRenderer {
     options: RenderOptions,
+    /// 装飾のCSSクラス名の置き換え（変換のたびに複製しないよう共有する）
+    style_class_map: Rc<HashMap<StyleType, String>>,
+    /// ノードごとのフック
+    node_hook: Option<NodeHook>,
+    /// 直前に文書全体を変換したときに抽出したヘッダー情報
+    header_info: Option<HeaderInfo>,
+}
+
+impl fmt::Debug for HtmlRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HtmlRenderer")
+            .field("options", &self.options)
+            .field("node_hook", &self.node_hook.is_some())
+            .field("header_info", &self.header_info)
+            .finish()
+    }
 }
 
 impl HtmlRenderer {
     /// 新しいレンダラーを作成
     pub fn new(options: RenderOptions) -> Self {
-        Self { options }
+        Self {
+            style_class_map: Rc::new(options.style_class_map.clone()),
+            options,
+            node_hook: None,
+            header_info: None,
+        }
+    }
+
+    /// 直前に [`render`](Self::render) などで文書全体を変換したときのヘッダー情報
+    ///
+    /// まだ文書全体を変換していない場合は `None` を返します。
+    /// [`render_fragment`](Self::render_fragment) や [`render_line`](Self::render_line) では更新しません。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aozora2::html::{HtmlRenderer, RenderOptions};
+    ///
+    /// let mut renderer = HtmlRenderer::new(RenderOptions::default());
+    /// assert!(renderer.header_info().is_none());
+    /// renderer.render("吾輩は猫である\n夏目漱石\n\n本文");
+    /// let header_info = renderer.header_info().unwrap();
+    /// assert_eq!(header_info.title.as_deref(), Some("吾輩は猫である"));
+    /// ```
+    pub fn header_info(&self) -> Option<&HeaderInfo> {
+        self.header_info.as_ref()
+    }
+
+    /// ノードごとに呼び出されるフックを設定
+    ///
+    /// フックは各ノードの変換の前に呼び出されます。`Some(html)` を返すと
+    /// そのノードの出力を置き換え、`None` を返すと既定の変換を行います。
+    /// 子ノードを持つノードを置き換えた場合、子ノードに対してはフックは呼び出されません。
+    pub fn set_node_hook(&mut self, hook: NodeHook) {
+        self.node_hook = Some(hook);
     }
 
     /// テキスト全体をHTMLに変換
     pub fn render(&mut self, input: &str) -> String {
+        self.render_with_source(input, LineSource::Parse)
+    }
+
+    /// テキスト全体をHTMLに変換し、少しずつ書き出す
+    ///
+    /// ヘッダー、本文の各行、後付けの各セクションを変換するたびに `writer` に書き出します。
+    /// 書き出す内容は [`render`](Self::render) の結果を
+    /// [`RenderOptions::output_encoding`] でエンコードしたものと同じです。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aozora2::html::{HtmlRenderer, RenderOptions};
+    /// use aozora2::aozora_core::encoding::OutputEncoding;
+    ///
+    /// let options = RenderOptions::new().with_output_encoding(OutputEncoding::Utf8);
+    /// let mut renderer = HtmlRenderer::new(options);
+    /// let mut output = Vec::new();
+    /// renderer.render_to_writer("タイトル\n\n本文", &mut output).unwrap();
+    /// assert!(String::from_utf8(output).unwrap().contains("本文<br />"));
+    /// ```
+    pub fn render_to_writer<W: Write>(&mut self, input: &str, writer: &mut W) -> io::Result<()> {
+        let encoding = self.options.output_encoding;
+        let compose_voiced_kana = self.options.compose_voiced_kana;
+        let mut flush = |chunk: &mut String| -> io::Result<()> {
+            if !chunk.is_empty() {
+                let text = if compose_voiced_kana {
+                    compose_voiced_marks(chunk)
+                } else {
+                    Cow::Borrowed(chunk.as_str())
+                };
+                writer.write_all(&encode_from_utf8(&text, encoding))?;
+                writer.flush()?;
+            }
+            chunk.clear();
+            Ok(())
+        };
+
+        let mut rest = self.render_into(input, LineSource::Parse, &mut flush)?;
+        flush(&mut rest)
+    }
+
+    /// 本文の行の解析方法を指定してテキスト全体をHTMLに変換
+    pub(crate) fn render_with_source(&mut self, input: &str, source: LineSource<'_>) -> String {
+        let html = match self.render_into(input, source, &mut |_| Ok(())) {
+            Ok(html) => html,
+            Err(_) => unreachable!("書き出しを行わない変換は失敗しない"),
+        };
+        if self.options.compose_voiced_kana {
+            compose_voiced_marks(&html).into_owned()
+        } else {
+            html
+        }
+    }
+
+    /// テキスト全体をHTMLに変換
+    ///
+    /// 各セクションと本文の各行を出力するたびに `flush` を呼び出します。
+    /// `flush` は出力済みの部分を取り出して空にしてかまいません。
+    /// `flush` がエラーを返した場合は、そこで変換を打ち切ってエラーを返します。
+    /// 最後に `flush` されていない残りの出力を返します。
+    fn render_into(
+        &mut self,
+        input: &str,
+        source: LineSource<'_>,
+        flush: &mut dyn FnMut(&mut String) -> io::Result<()>,
+    ) -> io::Result<String> {
         let mut output = String::new();
         let lines: Vec<&str> = input.lines().collect();
 
@@ -38,30 +174,229 @@ impl HtmlRenderer {
         let header_info = extract_header_info(&lines);
 
         // サブレンダラーを作成
+        let mut ctx = LineContext::new(&self.options, &self.style_class_map, &mut self.node_hook);
+        let result =
+            self.render_document(&lines, &header_info, &mut ctx, source, &mut output, flush);
+
+        self.node_hook = ctx.node_renderer.take_node_hook();
+        self.header_info = Some(header_info);
+        result.map(|()| output)
+    }
+
+    /// 文書の各セクションをHTMLに変換して出力に追加
+    fn render_document(
+        &self,
+        lines: &[&str],
+        header_info: &HeaderInfo,
+        ctx: &mut LineContext,
+        source: LineSource<'_>,
+        output: &mut String,
+        flush: &mut dyn FnMut(&mut String) -> io::Result<()>,
+    ) -> io::Result<()> {
         let doc_renderer = DocumentRenderer::new(&self.options);
-        let mut node_renderer = NodeRenderer::new(&self.options);
-        let mut block_manager = BlockManager::new();
 
         // HTMLヘッダーとメタデータセクションを出力
-        doc_renderer.render_html_head(&mut output, &header_info);
-        doc_renderer.render_metadata_section(&mut output, &header_info);
+        doc_renderer.render_html_head(output, header_info);
+        doc_renderer.render_metadata_section(output, header_info);
 
         // main_text開始
-        doc_renderer.render_main_text_start(&mut output);
+        doc_renderer.render_main_text_start(output);
+        flush(output)?;
 
         // 本文のみ抽出してレンダリング
-        let body_lines = extract_body_lines(&lines);
-        for line in &body_lines {
-            let line_html =
-                self.render_line_with_context(line, &mut node_renderer, &mut block_manager);
+        let body_range = body_line_range(lines);
+        self.render_body_lines(
+            &lines[body_range.clone()],
+            body_range.start + 1,
+            output,
+            ctx,
+            source,
+            flush,
+        )?;
+
+        // main_text終了
+        doc_renderer.render_main_text_end(output);
+        flush(output)?;
+
+        // 本文終わり後のテキスト（after_text）セクション
+        let after_text_lines = extract_after_text_lines(lines);
+        if !after_text_lines.is_empty() {
+            doc_renderer.render_after_text_header(output);
+            for line in &after_text_lines {
+                let line_html = self.render_line_with_context(
+                    line,
+                    &mut ctx.node_renderer,
+                    &mut ctx.block_manager,
+                );
+                // 自動リンク化を適用
+                let line_html = auto_link(&line_html);
+                output.push_str(&line_html);
+                output.push_str("<br />\r\n");
+            }
+            doc_renderer.render_after_text_footer(output);
+            flush(output)?;
+        }
+
+        // 底本情報（bibliographical_information）セクション
+        let biblio_lines = extract_bibliographical_lines(lines);
+        if !biblio_lines.is_empty() {
+            doc_renderer.render_bibliographical_header(output);
+            for line in &biblio_lines {
+                let line_html = self.render_line_with_context(
+                    line,
+                    &mut ctx.node_renderer,
+                    &mut ctx.block_manager,
+                );
+                // 自動リンク化を適用
+                let line_html = auto_link(&line_html);
+                output.push_str(&line_html);
+                output.push_str("<br />\r\n");
+            }
+            doc_renderer.render_bibliographical_footer(output);
+            flush(output)?;
+        }
+
+        let node_renderer = &ctx.node_renderer;
+
+        // 後注（endnotes）セクション
+        doc_renderer.render_endnotes(output, &node_renderer.endnotes);
+
+        // 表記について（notation_notes）セクション
+        doc_renderer.render_notation_notes(
+            output,
+            node_renderer.has_notes,
+            node_renderer.has_jisx0213,
+            node_renderer.has_accent,
+            node_renderer.has_kunojiten,
+            &node_renderer.unconverted_gaiji,
+        );
+
+        // 図書カードセクション
+        doc_renderer.render_card_section(output);
+
+        doc_renderer.render_html_foot(output);
+        Ok(())
+    }
+
+    /// 本文（前付け・後付けを除く）のみをHTML断片に変換
+    ///
+    /// `<html>` や `<head>`、メタデータ・底本情報などのセクションを含まず、
+    /// 本文の各行のHTMLだけを返します。EPUBなど他の文書に埋め込む場合に使用します。
+    pub fn render_fragment(&mut self, input: &str) -> String {
+        self.render_fragment_with_source(input, LineSource::Parse)
+    }
+
+    /// 本文の行の解析方法を指定して本文をHTML断片に変換
+    pub(crate) fn render_fragment_with_source(
+        &mut self,
+        input: &str,
+        source: LineSource<'_>,
+    ) -> String {
+        let mut output = String::new();
+        let lines: Vec<&str> = input.lines().collect();
+
+        let mut ctx = LineContext::new(&self.options, &self.style_class_map, &mut self.node_hook);
+
+        let body_range = body_line_range(&lines);
+        // 書き出しを行わないため失敗しない
+        let _ = self.render_body_lines(
+            &lines[body_range.clone()],
+            body_range.start + 1,
+            &mut output,
+            &mut ctx,
+            source,
+            &mut |_| Ok(()),
+        );
+        DocumentRenderer::new(&self.options)
+            .render_endnotes(&mut output, &ctx.node_renderer.endnotes);
+
+        self.node_hook = ctx.node_renderer.take_node_hook();
+        output
+    }
+
+    /// 本文の行をHTMLに変換して出力に追加
+    ///
+    /// `first_line_no` は本文の最初の行の、元のファイルでの行番号（1始まり）です。
+    /// 出力範囲内の各行を変換する前に `flush` を呼び出し、エラーになればそこで打ち切ります。
+    fn render_body_lines(
+        &self,
+        body_lines: &[&str],
+        first_line_no: usize,
+        output: &mut String,
+        ctx: &mut LineContext,
+        mut source: LineSource<'_>,
+        flush: &mut dyn FnMut(&mut String) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let LineContext {
+            node_renderer,
+            block_manager,
+        } = ctx;
+        // 段落モードで開いている<p>があるか
+        let mut paragraph_open = false;
+        // 範囲より前の行の出力（ブロックの状態を再現するためだけに変換する）
+        let mut skipped = String::new();
+        let (range_start, range_end) = self.options.line_range.unwrap_or((1, usize::MAX));
+        let mut range_started = false;
+
+        for (index, line) in body_lines.iter().enumerate() {
+            if index + 1 > range_end {
+                break;
+            }
+            if range_started {
+                flush(output)?;
+            }
+            let output: &mut String = if index + 1 < range_start {
+                &mut skipped
+            } else {
+                if !range_started {
+                    // 範囲より前に開始されたブロックの開始タグを補う
+                    close_paragraph(&mut skipped, &mut paragraph_open);
+                    // ブロックの開始だけの行と同じく、タグごとに改行する
+                    for (depth, tag) in block_manager.render_open_block_tags() {
+                        output.push_str(&self.pretty_indent(depth));
+                        output.push_str(&tag);
+                        output.push_str("\r\n");
+                    }
+                    range_started = true;
+                }
+                &mut *output
+            };
+
+            let depth_before = block_manager.stack_len();
+            let mut line_html = match &mut source {
+                LineSource::Parse => {
+                    self.render_line_with_context(line, node_renderer, block_manager)
+                }
+                LineSource::Cache(cache) => {
+                    cache.render_line(self, line, node_renderer, block_manager)
+                }
+                LineSource::Tokens(tokenized) => {
+                    let nodes = self.parse_tokens(&tokenized[index]);
+                    self.render_parsed_line(line, &nodes, node_renderer, block_manager)
+                }
+            };
+
+            // 整形出力の字下げ（ブロックの開始タグは外側の深さ、終了タグは閉じた後の深さ）
+            let indent = self.pretty_indent(depth_before.min(block_manager.stack_len()));
 
             // ぶら下げブロック内かどうかをチェック
             let burasage_ctx = block_manager.find_burasage_context();
             let line_type = classify_line(&line_html);
 
+            // ブロック以外の行を元の行番号付きのspanで囲む
+            if self.options.source_line_attrs
+                && line_type == LineType::Inline
+                && !is_block_only_line(&line_html)
+            {
+                let line_no = first_line_no + index;
+                line_html = format!("<span data-line=\"{line_no}\">{line_html}</span>");
+            }
+
             if let Some((wrap_width, text_indent)) = burasage_ctx {
                 // ぶら下げブロック内: インライン行を個別のdivでラップ
                 if line_type == LineType::Inline {
+                    close_paragraph(output, &mut paragraph_open);
+                    output.push_str(&indent);
                     output.push_str(&format!(
                         "<div class=\"burasage\" style=\"margin-left: {wrap_width}em; text-indent: {text_indent}em;\">{line_html}</div>"
                     ));
@@ -75,6 +410,18 @@ impl HtmlRenderer {
                 continue;
             }
 
+            if self.options.paragraphs {
+                self.push_paragraph_line(
+                    output,
+                    line_html,
+                    &indent,
+                    block_manager,
+                    &mut paragraph_open,
+                );
+                continue;
+            }
+
+            output.push_str(&indent);
             output.push_str(&line_html);
 
             // インラインブロック（is_block = false）は行末で閉じる
@@ -101,59 +448,73 @@ impl HtmlRenderer {
             output.push_str("\r\n");
         }
 
+        if !range_started {
+            // 範囲内の行がない場合は開始タグを出力していないため、終了タグも出力しない
+            close_paragraph(&mut skipped, &mut paragraph_open);
+            while block_manager.pop().is_some() {}
+        }
+
+        close_paragraph(output, &mut paragraph_open);
+
         // 閉じられていないブロックを閉じる
         while let Some(ctx) = block_manager.pop() {
             output.push_str(&block_manager.render_block_end_tag(&ctx.block_type, &ctx.params));
         }
+        Ok(())
+    }
 
-        // main_text終了
-        doc_renderer.render_main_text_end(&mut output);
-
-        // 本文終わり後のテキスト（after_text）セクション
-        let after_text_lines = extract_after_text_lines(&lines);
-        if !after_text_lines.is_empty() {
-            doc_renderer.render_after_text_header(&mut output);
-            for line in &after_text_lines {
-                let line_html =
-                    self.render_line_with_context(line, &mut node_renderer, &mut block_manager);
-                // 自動リンク化を適用
-                let line_html = auto_link(&line_html);
-                output.push_str(&line_html);
-                output.push_str("<br />\r\n");
-            }
-            doc_renderer.render_after_text_footer(&mut output);
+    /// 段落モードで1行分のHTMLを出力に追加
+    ///
+    /// 連続するインライン行を1つの `<p>` にまとめ、行の間は `<br />` で区切ります。
+    /// 空行やブロック要素（div、見出し）の行で段落を閉じます。
+    fn push_paragraph_line(
+        &self,
+        output: &mut String,
+        mut line_html: String,
+        indent: &str,
+        block_manager: &mut BlockManager,
+        paragraph_open: &mut bool,
+    ) {
+        // 空行は段落の区切り
+        if line_html.is_empty() {
+            close_paragraph(output, paragraph_open);
+            return;
         }
 
-        // 底本情報（bibliographical_information）セクション
-        let biblio_lines = extract_bibliographical_lines(&lines);
-        if !biblio_lines.is_empty() {
-            doc_renderer.render_bibliographical_header(&mut output);
-            for line in &biblio_lines {
-                let line_html =
-                    self.render_line_with_context(line, &mut node_renderer, &mut block_manager);
-                // 自動リンク化を適用
-                let line_html = auto_link(&line_html);
-                output.push_str(&line_html);
-                output.push_str("<br />\r\n");
-            }
-            doc_renderer.render_bibliographical_footer(&mut output);
+        // インラインブロック（is_block = false）は行末で閉じる
+        let closed_blocks = block_manager.close_inline_blocks();
+        for (block_type, params) in closed_blocks {
+            line_html.push_str(&block_manager.render_block_end_tag(&block_type, &params));
         }
 
-        // 表記について（notation_notes）セクション
-        doc_renderer.render_notation_notes(
-            &mut output,
-            node_renderer.has_notes,
-            node_renderer.has_jisx0213,
-            node_renderer.has_accent,
-            &node_renderer.unconverted_gaiji,
-        );
-
-        // 図書カードセクション
-        doc_renderer.render_card_section(&mut output);
+        let is_paragraph_content =
+            classify_line(&line_html) == LineType::Inline && !is_block_only_line(&line_html);
+        if !is_paragraph_content {
+            close_paragraph(output, paragraph_open);
+            output.push_str(indent);
+            output.push_str(&line_html);
+            output.push_str("\r\n");
+            return;
+        }
 
-        doc_renderer.render_html_foot(&mut output);
+        if *paragraph_open {
+            output.push_str("<br />\r\n");
+            output.push_str(indent);
+        } else {
+            output.push_str(indent);
+            output.push_str("<p>");
+            *paragraph_open = true;
+        }
+        output.push_str(&line_html);
+    }
 
-        output
+    /// 整形出力で行頭に付ける字下げ（ブロックの入れ子1段につき空白2つ）
+    fn pretty_indent(&self, depth: usize) -> String {
+        if self.options.pretty {
+            "  ".repeat(depth)
+        } else {
+            String::new()
+        }
     }
 
     /// 1行をHTMLに変換（コンテキスト付き）
@@ -163,24 +524,61 @@ impl HtmlRenderer {
         node_renderer: &mut NodeRenderer,
         block_manager: &mut BlockManager,
     ) -> String {
-        let tokens = tokenize(line);
-        let mut nodes = parse(&tokens);
+        let nodes = self.parse_line(line);
+        self.render_parsed_line(line, &nodes, node_renderer, block_manager)
+    }
+
+    /// 1行を解析してノード列に変換
+    pub(crate) fn parse_line(&self, line: &str) -> Vec<Node> {
+        let line = if self.options.normalize_halfwidth_kana {
+            normalize_halfwidth_kana(line)
+        } else {
+            Cow::Borrowed(line)
+        };
+
+        let tokens = tokenize_with_limits(&line, &self.options.limits);
+        self.parse_tokens(&tokens)
+    }
+
+    /// 1行分のトークン列を解析してノード列に変換
+    fn parse_tokens(&self, tokens: &[Token]) -> Vec<Node> {
+        let mut nodes = parse(tokens);
 
         // 行内ルビを解決
         resolve_inline_ruby(&mut nodes);
+        nodes
+    }
 
+    /// 解析済みの1行をHTMLに変換（コンテキスト付き）
+    pub(crate) fn render_parsed_line(
+        &self,
+        line: &str,
+        nodes: &[Node],
+        node_renderer: &mut NodeRenderer,
+        block_manager: &mut BlockManager,
+    ) -> String {
         // 行の開始時点でのブロックスタックの長さを記録
         let stack_len_before = block_manager.stack_len();
 
-        let mut output = node_renderer.render_nodes(&nodes, block_manager);
+        let mut output = node_renderer.render_nodes(nodes, block_manager);
 
         // 行単位字下げ: 行の終わりで、その行で開いたブロックを閉じる
         let is_line_scope_block = line.starts_with("［＃")
             && !line.contains("ここから")
             && (line.contains("字下げ") || line.contains("地付き") || line.contains("地から"));
 
-        if is_line_scope_block {
-            let popped = block_manager.pop_to_length(stack_len_before);
+        // 本文と同じ行で開いた太字・斜体・文字サイズのブロックは、同じ行で閉じられなければ行末で閉じる
+        // （`ここから` 形式の注記は単独の行に書くため、本文と同じ行にあれば行内の指定とみなす）
+        let close_to = if is_line_scope_block {
+            Some(stack_len_before)
+        } else if has_inline_content(nodes) {
+            block_manager.find_span_block_from(stack_len_before)
+        } else {
+            None
+        };
+
+        if let Some(target_len) = close_to {
+            let popped = block_manager.pop_to_length(target_len);
             for (block_type, params) in popped {
                 output.push_str(&block_manager.render_block_end_tag(&block_type, &params));
             }
@@ -191,22 +589,81 @@ impl HtmlRenderer {
 
     /// 1行をHTMLに変換（公開API）
     pub fn render_line(&mut self, line: &str) -> String {
-        let mut node_renderer = NodeRenderer::new(&self.options);
-        let mut block_manager = BlockManager::new();
-        self.render_line_with_context(line, &mut node_renderer, &mut block_manager)
+        let mut ctx = LineContext::new(&self.options, &self.style_class_map, &mut self.node_hook);
+        let output =
+            self.render_line_with_context(line, &mut ctx.node_renderer, &mut ctx.block_manager);
+        self.node_hook = ctx.node_renderer.take_node_hook();
+        output
+    }
+
+    /// 1行をHTMLに変換し、行タイプとともに返す（公開API）
+    ///
+    /// 段落の組み立てを呼び出し側で行う場合に使用します。
+    pub fn render_line_typed(&mut self, line: &str) -> (String, LineType) {
+        let html = self.render_line(line);
+        let line_type = classify_line(&html);
+        (html, line_type)
     }
 
     /// ノード列をHTMLに変換
     pub fn render_nodes(&mut self, nodes: &[Node]) -> String {
-        let mut node_renderer = NodeRenderer::new(&self.options);
-        let mut block_manager = BlockManager::new();
-        node_renderer.render_nodes(nodes, &mut block_manager)
+        let mut ctx = LineContext::new(&self.options, &self.style_class_map, &mut self.node_hook);
+        let output = ctx
+            .node_renderer
+            .render_nodes(nodes, &mut ctx.block_manager);
+        self.node_hook = ctx.node_renderer.take_node_hook();
+        output
+    }
+}
+
+/// 行の変換で引き継ぐ状態（ノードレンダラーとブロックの状態）
+struct LineContext<'a> {
+    node_renderer: NodeRenderer<'a>,
+    block_manager: BlockManager,
+}
+
+impl<'a> LineContext<'a> {
+    /// オプションに合わせて作成（ノードフックはレンダラーから預かる）
+    fn new(
+        options: &'a RenderOptions,
+        style_class_map: &Rc<HashMap<StyleType, String>>,
+        node_hook: &mut Option<NodeHook>,
+    ) -> Self {
+        let mut node_renderer = NodeRenderer::new(options);
+        node_renderer.set_node_hook(node_hook.take());
+        let block_manager = BlockManager::new()
+            .with_heading_base_level(options.heading_base_level)
+            .with_style_class_map(Rc::clone(style_class_map));
+        Self {
+            node_renderer,
+            block_manager,
+        }
+    }
+}
+
+/// ブロックの開始・終了以外のノードを含むかどうか
+fn has_inline_content(nodes: &[Node]) -> bool {
+    nodes
+        .iter()
+        .any(|node| !matches!(node, Node::BlockStart { .. } | Node::BlockEnd { .. }))
+}
+
+/// 開いている段落を閉じる
+fn close_paragraph(output: &mut String, paragraph_open: &mut bool) {
+    if *paragraph_open {
+        output.push_str("</p>\r\n");
+        *paragraph_open = false;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::html::{GaijiMode, NoteCollection, WritingMode};
+    use aozora_core::node::StyleType;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
 
     #[test]
     fn test_render_text() {
@@ -223,4 +680,1010 @@ mod tests {
         assert!(html.contains("<rb>漢字</rb>"));
         assert!(html.contains("<rt>かんじ</rt>"));
     }
+
+    #[test]
+    fn test_render_prefixed_ruby_with_gaiji_base() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("｜東京※［＃「てへん＋劣」、第3水準1-84-77］《とうきょう》へ"),
+            "<ruby><rb>東京<img src=\"../../../gaiji/1-84/1-84-77.png\" alt=\"※(「てへん＋劣」、第3水準1-84-77)\" class=\"gaiji\" /></rb><rp>（</rp><rt>とうきょう</rt><rp>）</rp></ruby>へ"
+        );
+    }
+
+    #[test]
+    fn test_render_ruby_only_kanji() {
+        let options = RenderOptions::default().with_ruby_only_kanji(true);
+        let mut renderer = HtmlRenderer::new(options);
+        assert_eq!(
+            renderer.render_line("漢字《かんじ》と｜ひらがな《ヒラガナ》と｜※［＃「丸印」、U+25CB］《まる》"),
+            "<ruby><rb>漢字</rb><rp>（</rp><rt>かんじ</rt><rp>）</rp></ruby>とひらがなと<ruby><rb>※<span class=\"notes\">［＃「丸印」、U+25CB］</span></rb><rp>（</rp><rt>まる</rt><rp>）</rp></ruby>"
+        );
+    }
+
+    #[test]
+    fn test_render_ruby_data_attr() {
+        let options = RenderOptions::default().with_ruby_data_attr(true);
+        let mut renderer = HtmlRenderer::new(options);
+        assert_eq!(
+            renderer.render_line("漢字《かんじ》"),
+            "<ruby data-ruby=\"かんじ\"><rb>漢字</rb><rp>（</rp><rt>かんじ</rt><rp>）</rp></ruby>"
+        );
+        // 属性値はテキストとしてエスケープする
+        assert!(renderer
+            .render_line("｜R&D《アール&ディー》")
+            .starts_with("<ruby data-ruby=\"アール&amp;ディー\">"));
+    }
+
+    #[test]
+    fn test_render_warigaki_with_ruby() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line("［＃割り注］東京《とうきょう》｜都［＃割り注終わり］");
+        assert_eq!(
+            html,
+            "<span class=\"warichu\"><span class=\"warichu_upper\"><ruby><rb>東京</rb><rp>（</rp><rt>とうきょう</rt><rp>）</rp></ruby></span><span class=\"warichu_lower\">都</span></span>"
+        );
+    }
+
+    #[test]
+    fn test_render_unterminated_futoji_on_text_line() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment("題名\n\n前［＃ここから太字］強調\n次の行\n後の行\n");
+        assert_eq!(
+            html,
+            "前<div class=\"futoji\">強調</div>\r\n次の行<br />\r\n後の行<br />\r\n"
+        );
+
+        // 行内で閉じていればそのまま
+        let html = renderer
+            .render_fragment("題名\n\n［＃ここから斜体］強調［＃ここで斜体終わり］後\n次の行\n");
+        assert_eq!(
+            html,
+            "<div class=\"shatai\">強調</div>後<br />\r\n次の行<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_futoji_block_on_own_line() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "題名\n\n［＃ここから太字］\n一行目\n二行目\n［＃ここで太字終わり］\n後の行\n",
+        );
+        assert_eq!(
+            html,
+            "<div class=\"futoji\">\r\n一行目<br />\r\n二行目<br />\r\n</div>\r\n後の行<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_line_typed() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let (html, line_type) = renderer.render_line_typed("一［＃「一」は中見出し］");
+        assert!(html.starts_with("<h4 class=\"naka-midashi\">"));
+        assert_eq!(line_type, LineType::Block);
+
+        let (html, line_type) = renderer.render_line_typed("吾輩は猫である");
+        assert_eq!(html, "吾輩は猫である");
+        assert_eq!(line_type, LineType::Inline);
+
+        assert_eq!(
+            renderer.render_line_typed(""),
+            (String::new(), LineType::Empty)
+        );
+    }
+
+    #[test]
+    fn test_render_line_break() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(renderer.render_line("前［＃改行］後"), "前<br />後");
+
+        // キャプション内でも段落を分けずに改行する
+        assert_eq!(
+            renderer.render_line("［＃キャプション］上［＃改行］下［＃キャプション終わり］"),
+            "<span class=\"caption\">上<br />下</span>"
+        );
+
+        // 割り注内では上段と下段の区切り
+        assert_eq!(
+            renderer.render_line("［＃割り注］上［＃改行］下［＃割り注終わり］"),
+            "<span class=\"warichu\"><span class=\"warichu_upper\">上</span><span class=\"warichu_lower\">下</span></span>"
+        );
+
+        // 本文では行末の<br />とは別に出力される
+        let html = renderer.render_fragment("題名\n\n前［＃改行］後\n次の行");
+        assert_eq!(html, "前<br />後<br />\r\n次の行<br />\r\n");
+    }
+
+    #[test]
+    fn test_render_gyou_kogaki_default() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line("ア［＃「ア」は行右小書き］イ［＃「イ」は行左小書き］");
+        assert_eq!(
+            html,
+            "<sup class=\"superscript\">ア</sup><sub class=\"subscript\">イ</sub>"
+        );
+    }
+
+    #[test]
+    fn test_render_gyou_migi_kogaki_class() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::new().with_kogaki_class(true));
+        let html = renderer.render_line("ア［＃「ア」は行右小書き］");
+        assert_eq!(html, "<span class=\"gyou-migi-kogaki\">ア</span>");
+    }
+
+    #[test]
+    fn test_render_gyou_hidari_kogaki_class() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::new().with_kogaki_class(true));
+        let html = renderer.render_line("［＃行左小書き］イ［＃行左小書き終わり］");
+        assert_eq!(html, "<span class=\"gyou-hidari-kogaki\">イ</span>");
+    }
+
+    #[test]
+    fn test_render_kunojiten() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render("タイトル\n\nかわる〳〵見る\nしみ〴〵と");
+        assert!(html.contains("かわる〳〵見る"));
+        assert!(html.contains("しみ〴〵と"));
+        assert!(html.contains("「くの字点」は「〳〵」「〴〵」の文字で表しました。"));
+    }
+
+    #[test]
+    fn test_render_without_kunojiten_note() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render("タイトル\n\n本文");
+        assert!(!html.contains("くの字点」は"));
+    }
+
+    #[test]
+    fn test_render_gaiji_with_jis_and_unicode() {
+        let line = "※［＃「架空の字」、1-95-01、U+25CB］";
+
+        // 既定ではJISコードの画像
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line(line),
+            "<img src=\"../../../gaiji/1-95/1-95-01.png\" alt=\"※(「架空の字」、1-95-01、U+25CB)\" class=\"gaiji\" />"
+        );
+
+        // Unicodeモードでは U+ の文字
+        let mut renderer = HtmlRenderer::new(RenderOptions::new().with_unicode(true));
+        assert_eq!(renderer.render_line(line), "&#9675;");
+
+        let options = RenderOptions::new().with_gaiji_mode(GaijiMode::UnicodePreferred);
+        let mut renderer = HtmlRenderer::new(options);
+        assert_eq!(renderer.render_line(line), "○");
+    }
+
+    #[test]
+    fn test_render_gaiji_unicode_preferred_convertible() {
+        let options = RenderOptions::new().with_gaiji_mode(GaijiMode::UnicodePreferred);
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_line("※［＃半濁点付き片仮名カ、1-05-87］");
+        assert_eq!(html, "カ゚");
+    }
+
+    #[test]
+    fn test_render_gaiji_unicode_preferred_unmappable() {
+        let options = RenderOptions::new().with_gaiji_mode(GaijiMode::UnicodePreferred);
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_line("※［＃「架空の字」、1-95-01］");
+        assert!(html.starts_with("<img src=\"../../../gaiji/"));
+        assert!(html.contains("class=\"gaiji\""));
+    }
+
+    #[test]
+    fn test_render_gaiji_unicode_preferred_explicit_unicode() {
+        let options = RenderOptions::new().with_gaiji_mode(GaijiMode::UnicodePreferred);
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_line("※［＃「丸印」、U+25CB］");
+        assert_eq!(html, "○");
+    }
+
+    #[test]
+    fn test_render_tcy_inside_yokogumi_block() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer
+            .render_line("［＃横組み］第［＃縦中横］12［＃縦中横終わり］号［＃横組み終わり］");
+        assert_eq!(html, "<span class=\"yokogumi\">第12号</span>");
+    }
+
+    #[test]
+    fn test_render_tcy_inside_yokogumi_reference() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line("第12［＃「12」は縦中横］号［＃「第12号」は横組み］");
+        assert!(html.contains("第12号"));
+        assert!(!html.contains("dir=\"ltr\""));
+    }
+
+    #[test]
+    fn test_render_tcy_outside_yokogumi() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line("第12［＃「12」は縦中横］号");
+        assert_eq!(html, "第<span dir=\"ltr\">12</span>号");
+    }
+
+    #[test]
+    fn test_render_heading_base_level() {
+        /// 大・中・小見出しと、範囲指定の大見出しのタグ名
+        fn heading_tags(options: RenderOptions) -> Vec<String> {
+            let mut renderer = HtmlRenderer::new(options);
+            [
+                "大［＃「大」は大見出し］",
+                "中［＃「中」は中見出し］",
+                "小［＃「小」は小見出し］",
+                "［＃大見出し］範囲［＃大見出し終わり］",
+            ]
+            .iter()
+            .map(|line| {
+                let html = renderer.render_line(line);
+                assert!(html.ends_with(&format!("</{}>", &html[1..3])), "{html}");
+                html[1..3].to_string()
+            })
+            .collect()
+        }
+
+        assert_eq!(
+            heading_tags(RenderOptions::default()),
+            ["h3", "h4", "h5", "h3"]
+        );
+        assert_eq!(
+            heading_tags(RenderOptions::new().with_heading_base_level(3)),
+            ["h3", "h4", "h5", "h3"]
+        );
+        assert_eq!(
+            heading_tags(RenderOptions::new().with_heading_base_level(1)),
+            ["h1", "h2", "h3", "h1"]
+        );
+    }
+
+    #[test]
+    fn test_render_tcy_latin_targets() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("西暦AD［＃「AD」は縦中横］一年"),
+            "西暦<span dir=\"ltr\">AD</span>一年"
+        );
+        assert_eq!(
+            renderer.render_line("第Ⅲ［＃「Ⅲ」は縦中横］部"),
+            "第<span dir=\"ltr\">Ⅲ</span>部"
+        );
+        assert_eq!(
+            renderer.render_line("ＡＢ［＃「ＡＢ」は縦中横］型"),
+            "<span dir=\"ltr\">ＡＢ</span>型"
+        );
+    }
+
+    #[test]
+    fn test_render_chitsuki_block() {
+        for (start, end) in [
+            ("［＃ここから地付き］", "［＃ここで地付き終わり］"),
+            ("［＃ここから地寄せ］", "［＃ここで地寄せ終わり］"),
+            ("［＃ここから右寄せ］", "［＃ここで右寄せ終わり］"),
+        ] {
+            let mut renderer = HtmlRenderer::new(RenderOptions::default());
+            let input = format!("タイトル\n\n{start}\n一行目\n二行目\n{end}\n本文");
+            let html = renderer.render_fragment(&input);
+            assert!(
+                html.contains(
+                    "<div class=\"chitsuki_0\" style=\"text-align:right; margin-right: 0em\">\r\n一行目<br />\r\n二行目<br />\r\n</div>\r\n本文"
+                ),
+                "{html}"
+            );
+            assert_eq!(html.matches("<div class=\"chitsuki_0\"").count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_render_chuuou_block() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "タイトル\n\n［＃ここから中央寄せ］\n一行目\n［＃ここで中央寄せ終わり］\n本文",
+        );
+        assert_eq!(
+            html,
+            "<div class=\"chuuou\" style=\"text-align:center\">\r\n一行目<br />\r\n</div>\r\n本文<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_endnotes() {
+        let options = RenderOptions::default().with_note_collection(NoteCollection::Endnotes);
+        let input = "タイトル\n\n一つ目［＃注記その一］\n二つ目［＃注記その二］";
+
+        let mut renderer = HtmlRenderer::new(options.clone());
+        assert_eq!(
+            renderer.render_fragment(input),
+            "一つ目<sup class=\"endnote-ref\"><a id=\"endnote-ref-1\" href=\"#endnote-1\">[1]</a></sup><br />\r\n\
+             二つ目<sup class=\"endnote-ref\"><a id=\"endnote-ref-2\" href=\"#endnote-2\">[2]</a></sup><br />\r\n\
+             <div class=\"endnotes\">\r\n<ol>\r\n\
+             <li id=\"endnote-1\"><a href=\"#endnote-ref-1\">[1]</a> ［＃注記その一］</li>\r\n\
+             <li id=\"endnote-2\"><a href=\"#endnote-ref-2\">[2]</a> ［＃注記その二］</li>\r\n\
+             </ol>\r\n</div>\r\n"
+        );
+
+        // 文書全体では本文の後に後注を出力し、番号は文書ごとに振り直す
+        let html = renderer.render(input);
+        assert!(!html.contains("class=\"notes\""));
+        let main_end = html.find("<div class=\"endnotes\">").unwrap();
+        assert!(html[..main_end].contains("[2]</a></sup>"));
+        assert!(html[main_end..].contains("［＃注記その二］</li>"));
+        assert!(!html.contains("[3]"));
+
+        // 既定ではその位置に出力
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert!(!renderer
+            .render_fragment(input)
+            .contains("<div class=\"endnotes\">"));
+    }
+
+    #[test]
+    fn test_render_page_center_block() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "タイトル\n\n［＃ここからページの左右中央］\n［＃ここから２字下げ］\n献辞\n［＃ここで字下げ終わり］\n［＃ここでページの左右中央終わり］\n本文",
+        );
+        assert_eq!(
+            html,
+            "<div class=\"page-center\">\r\n<div class=\"jisage_2\" style=\"margin-left: 2em\">\r\n献辞<br />\r\n</div>\r\n</div>\r\n本文<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_migiyose_line() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment("タイトル\n\n［＃右寄せ］署名\n次の行");
+        assert_eq!(
+            html,
+            "<div class=\"chitsuki_0\" style=\"text-align:right; margin-right: 0em\">署名</div>\r\n次の行<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_stray_block_end() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+
+        // 開始のない終了は何も出力しない
+        let html = renderer.render_fragment("タイトル\n\n［＃ここで字下げ終わり］\n本文");
+        assert_eq!(html, "本文<br />\r\n");
+
+        // 別の種類のブロックは閉じない
+        let html = renderer.render_fragment(
+            "タイトル\n\n［＃ここから罫囲み］\n一行目\n［＃ここで字下げ終わり］\n二行目\n［＃ここで罫囲み終わり］\n後",
+        );
+        assert_eq!(
+            html,
+            "<div class=\"keigakomi\" style=\"border: solid 1px\">\r\n一行目<br />\r\n二行目<br />\r\n</div>\r\n後<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_chitsuki_line() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment("タイトル\n\n末尾［＃地付き］署名\n次の行");
+        assert!(html.contains(
+            "末尾<div class=\"chitsuki_0\" style=\"text-align:right; margin-right: 0em\">署名</div>\r\n次の行"
+        ));
+    }
+
+    #[test]
+    fn test_render_jiage_line() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer
+            .render_fragment("タイトル\n\n［＃地から２字上げ］一行\n［＃地付き］二行\n次の行");
+        assert_eq!(
+            html,
+            "<div class=\"chitsuki_2\" style=\"text-align:right; margin-right: 2em\">一行</div>\r\n\
+             <div class=\"chitsuki_0\" style=\"text-align:right; margin-right: 0em\">二行</div>\r\n\
+             次の行<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_jiage_block() {
+        // 複数行のブロックでは、すべての行が1つの右寄せのdivに入る
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "タイトル\n\n［＃ここから地から２字上げ］\n一行目\n二行目\n［＃ここで字上げ終わり］\n本文",
+        );
+        assert_eq!(
+            html,
+            "<div class=\"chitsuki_2\" style=\"text-align:right; margin-right: 2em\">\r\n\
+             一行目<br />\r\n二行目<br />\r\n</div>\r\n本文<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_normalize_halfwidth_kana() {
+        let options = RenderOptions::new().with_normalize_halfwidth_kana(true);
+        let mut renderer = HtmlRenderer::new(options);
+        assert_eq!(
+            renderer.render_line("ｶﾀｶﾅ《かたかな》"),
+            "<ruby><rb>カタカナ</rb><rp>（</rp><rt>かたかな</rt><rp>）</rp></ruby>"
+        );
+
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(renderer.render_line("ｶﾀｶﾅ"), "ｶﾀｶﾅ");
+    }
+
+    #[test]
+    fn test_render_bouten_across_ruby() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line("ここは東京《とうきょう》都です［＃「東京都」に傍点］");
+        assert_eq!(
+            html,
+            "ここは<em class=\"sesame_dot\"><ruby><rb>東京</rb><rp>（</rp><rt>とうきょう</rt><rp>）</rp></ruby>都</em>です"
+        );
+    }
+
+    #[test]
+    fn test_render_ruby_after_bouten_range() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("［＃傍点］東京［＃傍点終わり］《とうきょう》"),
+            "<ruby><rb><em class=\"sesame_dot\">東京</em></rb><rp>（</rp><rt>とうきょう</rt><rp>）</rp></ruby>"
+        );
+        // 装飾の一部だけが親文字の場合は装飾を分割する
+        assert_eq!(
+            renderer.render_line("［＃傍点］私の東京［＃傍点終わり］《とうきょう》"),
+            "<em class=\"sesame_dot\">私の</em><ruby><rb><em class=\"sesame_dot\">東京</em></rb><rp>（</rp><rt>とうきょう</rt><rp>）</rp></ruby>"
+        );
+    }
+
+    #[test]
+    fn test_render_bold_italic_inline_range() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("本文［＃太字］語［＃太字終わり］続き"),
+            "本文<span class=\"futoji\">語</span>続き"
+        );
+        assert_eq!(
+            renderer.render_line("［＃斜体］語［＃斜体終わり］続き"),
+            "<span class=\"shatai\">語</span>続き"
+        );
+    }
+
+    #[test]
+    fn test_render_bold_italic_block() {
+        for (command, class) in [("太字", "futoji"), ("斜体", "shatai")] {
+            let mut renderer = HtmlRenderer::new(RenderOptions::default());
+            let input = format!(
+                "タイトル\n\n［＃ここから{command}］\n一行目\n二行目\n［＃ここで{command}終わり］"
+            );
+            let html = renderer.render_fragment(&input);
+            assert!(
+                html.starts_with(&format!(
+                    "<div class=\"{class}\">\r\n一行目<br />\r\n二行目<br />\r\n</div>"
+                )),
+                "{html}"
+            );
+            assert!(!html.contains("<span"));
+        }
+    }
+
+    #[test]
+    fn test_render_paragraphs() {
+        let options = RenderOptions::new().with_paragraphs(true);
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_fragment("タイトル\n\n一行目\n二行目\n\n三行目");
+        assert_eq!(html, "<p>一行目<br />\r\n二行目</p>\r\n<p>三行目</p>\r\n");
+    }
+
+    #[test]
+    fn test_render_paragraphs_broken_by_blocks() {
+        let options = RenderOptions::new().with_paragraphs(true);
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_fragment(
+            "タイトル\n\n前文\n［＃大見出し］章［＃大見出し終わり］\n本文\n［＃ここから2字下げ］\n引用\n［＃ここで字下げ終わり］\n後文",
+        );
+        assert!(
+            html.starts_with("<p>前文</p>\r\n<h3 class=\"o-midashi\">"),
+            "{html}"
+        );
+        assert!(
+            html.contains("</h3>\r\n<p>本文</p>\r\n<div class=\"jisage_2\""),
+            "{html}"
+        );
+        assert!(
+            html.contains("<p>引用</p>\r\n</div>\r\n<p>後文</p>\r\n"),
+            "{html}"
+        );
+        assert!(!html.contains("<br />"));
+    }
+
+    #[test]
+    fn test_render_without_paragraphs() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment("タイトル\n\n一行目\n二行目");
+        assert_eq!(html, "一行目<br />\r\n二行目<br />\r\n");
+    }
+
+    #[test]
+    fn test_render_jisage_inside_yokogumi_block() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "タイトル\n\n［＃ここから横組み］\n前\n［＃ここから2字下げ］\n本文\n［＃ここで字下げ終わり］\n後\n［＃ここで横組み終わり］\n外",
+        );
+        assert_eq!(
+            html,
+            "<div class=\"yokogumi\">\r\n前<br />\r\n<div class=\"jisage_2\" style=\"margin-left: 2em\">\r\n本文<br />\r\n</div>\r\n後<br />\r\n</div>\r\n外<br />\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_jisage_inside_yokogumi_inside_jisage() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "タイトル\n\n［＃ここから2字下げ］\nA\n［＃ここから横組み］\n［＃ここから3字下げ］\nB\n［＃ここで字下げ終わり］\n［＃ここで横組み終わり］\nC\n［＃ここで字下げ終わり］",
+        );
+        // 横組みの内側で字下げを開始しても、外側の字下げは閉じない
+        assert_eq!(
+            html,
+            "<div class=\"jisage_2\" style=\"margin-left: 2em\">\r\nA<br />\r\n<div class=\"yokogumi\">\r\n<div class=\"jisage_3\" style=\"margin-left: 3em\">\r\nB<br />\r\n</div>\r\n</div>\r\nC<br />\r\n</div>\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_source_line_attrs() {
+        let options = RenderOptions::new().with_source_line_attrs(true);
+        let mut renderer = HtmlRenderer::new(options);
+        let input = "タイトル\n著者\n\n一行目\n\n［＃ここから2字下げ］\n二行目\n［＃ここで字下げ終わり］\n三行目《さんぎょうめ》";
+        let html = renderer.render_fragment(input);
+
+        let lines: Vec<&str> = input.lines().collect();
+        for (text, line_no) in [("一行目", 4), ("二行目", 7)] {
+            assert_eq!(lines[line_no - 1], text);
+            assert!(
+                html.contains(&format!(
+                    "<span data-line=\"{line_no}\">{text}</span><br />"
+                )),
+                "{html}"
+            );
+        }
+        assert!(html.contains("<span data-line=\"9\"><ruby><rb>三行目</rb>"));
+        // ブロックの開始・終了行と空行には付けない
+        assert_eq!(html.matches("data-line=").count(), 3);
+    }
+
+    #[test]
+    fn test_render_source_line_attrs_with_paragraphs() {
+        let options = RenderOptions::new()
+            .with_source_line_attrs(true)
+            .with_paragraphs(true);
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_fragment("タイトル\n\n一行目\n二行目");
+        assert_eq!(
+            html,
+            "<p><span data-line=\"3\">一行目</span><br />\r\n<span data-line=\"4\">二行目</span></p>\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_line_range_replays_blocks() {
+        let options = RenderOptions::new().with_line_range(Some((3, 5)));
+        let mut renderer = HtmlRenderer::new(options);
+        let input =
+            "タイトル\n著者\n\n［＃ここから2字下げ］\n一\n二\n三\n四\n［＃ここで字下げ終わり］\n五";
+        let html = renderer.render_fragment(input);
+        assert_eq!(
+            html,
+            "<div class=\"jisage_2\" style=\"margin-left: 2em\">\r\n二<br />\r\n三<br />\r\n四<br />\r\n</div>"
+        );
+
+        // 全体を変換した結果の該当部分と同じ形になる
+        let full = HtmlRenderer::new(RenderOptions::new()).render_fragment(input);
+        assert!(
+            full.contains("<div class=\"jisage_2\" style=\"margin-left: 2em\">\r\n一<br />\r\n")
+        );
+    }
+
+    #[test]
+    fn test_render_line_range_replays_nested_blocks_pretty() {
+        let options = RenderOptions::new()
+            .with_line_range(Some((4, 4)))
+            .with_pretty(true);
+        let mut renderer = HtmlRenderer::new(options);
+        let input = "タイトル\n\n［＃ここから2字下げ］\n［＃ここから太字］\n一\n二\n［＃ここで太字終わり］\n［＃ここで字下げ終わり］";
+        let html = renderer.render_fragment(input);
+        assert!(
+            html.starts_with("<div class=\"jisage_2\" style=\"margin-left: 2em\">\r\n  <div class=\"futoji\">\r\n    二<br />\r\n"),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn test_render_line_range_outside_body() {
+        let options = RenderOptions::new().with_line_range(Some((10, 12)));
+        let mut renderer = HtmlRenderer::new(options);
+        let html = renderer.render_fragment("タイトル\n\n［＃ここから2字下げ］\n一");
+        assert_eq!(html, "");
+    }
+
+    #[test]
+    fn test_render_right_and_left_bouten() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("語［＃「語」に傍点］［＃「語」の左に傍点］"),
+            "<em class=\"sesame_dot_after\"><em class=\"sesame_dot\">語</em></em>"
+        );
+        assert_eq!(
+            renderer.render_line("語句［＃「語句」に傍点］［＃「句」の左に傍点］"),
+            "<em class=\"sesame_dot\">語<em class=\"sesame_dot_after\">句</em></em>"
+        );
+    }
+
+    #[test]
+    fn test_render_kaeriten_after_compound() {
+        // 返り点は熟語の最後の文字の後に置かれ、前の文字列とは結び付けない
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("読漢字［＃一］書［＃二］"),
+            "読漢字<sub class=\"kaeriten\">一</sub>書<sub class=\"kaeriten\">二</sub>"
+        );
+        // 括弧付きは訓点送り仮名
+        assert_eq!(
+            renderer.render_line("漢字［＃（一）］"),
+            "漢字<sup class=\"okurigana\">一</sup>"
+        );
+    }
+
+    #[test]
+    fn test_render_okurigana_direction() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("之［＃右に（ノ）］"),
+            "之<sup class=\"okurigana\">ノ</sup>"
+        );
+        assert_eq!(
+            renderer.render_line("之［＃左に（ノ）］"),
+            "之<sub class=\"okurigana_left\">ノ</sub>"
+        );
+    }
+
+    #[test]
+    fn test_render_max_ruby_len() {
+        let reading = "よ".repeat(500);
+        let line = format!("漢字《{reading}》");
+        let options = RenderOptions::new().with_max_ruby_len(Some(20));
+        let mut renderer = HtmlRenderer::new(options);
+        assert_eq!(renderer.render_line(&line), format!("漢字《{reading}》"));
+
+        // 上限以内の読みは通常どおりルビにする
+        assert_eq!(
+            renderer.render_line("漢字《かんじ》"),
+            "<ruby><rb>漢字</rb><rp>（</rp><rt>かんじ</rt><rp>）</rp></ruby>"
+        );
+
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert!(renderer.render_line(&line).starts_with("<ruby>"));
+    }
+
+    #[test]
+    fn test_render_aria() {
+        let line = "漢字《かんじ》［＃「漢字」は底本では「感じ」］";
+        let mut renderer = HtmlRenderer::new(RenderOptions::new().with_aria(true));
+        let html = renderer.render_line(line);
+        assert!(html.contains("<ruby aria-label=\"かんじ\"><rb>漢字</rb>"));
+        assert!(html.contains("<span class=\"notes\" role=\"note\">"));
+
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line(line);
+        assert!(!html.contains("aria-label"));
+        assert!(!html.contains("role="));
+    }
+
+    #[test]
+    fn test_render_style_class_map() {
+        let class_map = HashMap::from([(StyleType::Bold, "my-bold".to_string())]);
+        let options = RenderOptions::new().with_style_class_map(class_map);
+        let mut renderer = HtmlRenderer::new(options.clone());
+        assert_eq!(
+            renderer.render_line("猫［＃「猫」は太字］に傍点［＃「傍点」に傍点］"),
+            "<span class=\"my-bold\">猫</span>に<em class=\"sesame_dot\">傍点</em>"
+        );
+
+        let html = HtmlRenderer::new(options)
+            .render_fragment("題名\n\n［＃ここから太字］\n本文\n［＃ここで太字終わり］");
+        assert!(html.contains("<div class=\"my-bold\">"));
+    }
+
+    #[test]
+    fn test_render_tcy_inside_midashi() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("第１２章［＃「１２」は縦中横］［＃「第１２章」は大見出し］"),
+            "<h3 class=\"o-midashi\"><a class=\"midashi_anchor\" id=\"midashi100\">第<span dir=\"ltr\">１２</span>章</a></h3>"
+        );
+    }
+
+    #[test]
+    fn test_render_position() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("［＃12ページ］本文"),
+            "<span class=\"position\" id=\"page-12\" data-page=\"12\"></span>本文"
+        );
+        assert_eq!(
+            renderer.render_line("［＃丁付け十二ウ］本文"),
+            "<span class=\"position\" data-chou=\"十二ウ\"></span>本文"
+        );
+        assert!(!renderer.render_line("［＃12ページ］").contains("notes"));
+    }
+
+    #[test]
+    fn test_render_repeated_page_position_ids_are_unique() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_fragment(
+            "題名\n\n［＃12ページ］一\n［＃12ページ］二\n［＃13ページ］三\n［＃12ページ］四",
+        );
+        assert!(html.contains("id=\"page-12\" data-page=\"12\""));
+        assert!(html.contains("id=\"page-12-2\" data-page=\"12\""));
+        assert!(html.contains("id=\"page-13\" data-page=\"13\""));
+        assert!(html.contains("id=\"page-12-3\" data-page=\"12\""));
+    }
+
+    #[test]
+    fn test_header_info_after_render() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        renderer.render("吾輩は猫である\n夏目漱石\n\n吾輩《わがはい》は猫である。\n底本：青空文庫");
+        let header_info = renderer.header_info().unwrap();
+        assert_eq!(header_info.title.as_deref(), Some("吾輩は猫である"));
+        assert_eq!(header_info.author.as_deref(), Some("夏目漱石"));
+
+        // 次の文書を変換すると置き換わる
+        renderer
+            .render_to_writer("坊っちゃん\n夏目漱石\n\n本文", &mut Vec::new())
+            .unwrap();
+        assert_eq!(
+            renderer.header_info().unwrap().title.as_deref(),
+            Some("坊っちゃん")
+        );
+    }
+
+    #[test]
+    fn test_render_bouten_latin() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render_line("ABC［＃「ABC」に傍点］");
+        assert_eq!(html, "<em class=\"sesame_dot sesame_dot_latin\">ABC</em>");
+
+        let html = renderer.render_line("日本語［＃「日本語」に傍点］");
+        assert_eq!(html, "<em class=\"sesame_dot\">日本語</em>");
+
+        // 傍点以外の装飾には追加しない
+        let html = renderer.render_line("ABC［＃「ABC」は太字］");
+        assert!(!html.contains("_latin"));
+    }
+
+    #[test]
+    fn test_render_compose_voiced_kana() {
+        let input = "題名\n\nハ\u{309A}ンとカ\u{309A}\n";
+        let options = RenderOptions::new()
+            .with_compose_voiced_kana(true)
+            .with_output_encoding(aozora_core::encoding::OutputEncoding::Utf8);
+        let html = HtmlRenderer::new(options.clone()).render(input);
+        assert!(html.contains("パンとカ\u{309A}<br />"));
+
+        let mut output = Vec::new();
+        HtmlRenderer::new(options)
+            .render_to_writer(input, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), html);
+
+        let html = HtmlRenderer::new(RenderOptions::default()).render(input);
+        assert!(html.contains("ハ\u{309A}ン"));
+    }
+
+    #[test]
+    fn test_render_image_srcset() {
+        let line = "［＃挿絵（fig.png、横320×縦240）入る］";
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert!(!renderer.render_line(line).contains("srcset"));
+
+        let options = RenderOptions::new().with_image_srcset(true);
+        let mut renderer = HtmlRenderer::new(options);
+        assert!(renderer
+            .render_line(line)
+            .contains(" src=\"fig.png\" alt=\"挿絵\" srcset=\"fig.png 1x, fig@2x.png 2x\" />"));
+
+        let options = RenderOptions::new()
+            .with_image_srcset(true)
+            .with_image_2x_suffix("_hd");
+        let mut renderer = HtmlRenderer::new(options);
+        assert!(renderer
+            .render_line("［＃挿絵（img.v1/fig.jpg、横320×縦240）入る］")
+            .contains("srcset=\"img.v1/fig.jpg 1x, img.v1/fig_hd.jpg 2x\""));
+    }
+
+    #[test]
+    fn test_render_warigaki_paren_after_style() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("前（［＃割り注］上下［＃割り注終わり］［＃「下」に傍点］）後"),
+            "前（<span class=\"warichu\">上<em class=\"sesame_dot\">下</em></span>）後"
+        );
+    }
+
+    #[test]
+    fn test_render_nibai() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("あ―［＃「―」は二倍角］い"),
+            "あ<span class=\"nibai\">―</span>い"
+        );
+        assert_eq!(
+            renderer.render_line("［＃二倍角］―［＃二倍角終わり］"),
+            "<span class=\"nibai\">―</span>"
+        );
+    }
+
+    #[test]
+    fn test_render_character_commands() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        assert_eq!(
+            renderer.render_line("前［＃四分アキ］後［＃全角ダッシュ］"),
+            "前\u{2005}後―"
+        );
+        // 対応していないものは従来どおり注記
+        assert!(renderer
+            .render_line("前［＃三分アキ］")
+            .contains("<span class=\"notes\">［＃三分アキ］</span>"));
+    }
+
+    #[test]
+    fn test_render_writing_mode_class() {
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        let html = renderer.render("タイトル\n\n本文");
+        assert!(html.contains("<div class=\"main_text vertical\">"));
+
+        let mut renderer =
+            HtmlRenderer::new(RenderOptions::default().with_writing_mode(WritingMode::Horizontal));
+        let html = renderer.render("タイトル\n\n本文");
+        assert!(html.contains("<div class=\"main_text horizontal\">"));
+        assert!(!html.contains("vertical"));
+    }
+
+    #[test]
+    fn test_node_hook_overrides_notes() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let count = Rc::new(Cell::new(0));
+        let hook_count = Rc::clone(&count);
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        renderer.set_node_hook(Box::new(move |node| match node {
+            Node::Note(_) => {
+                hook_count.set(hook_count.get() + 1);
+                Some(String::new())
+            }
+            _ => None,
+        }));
+
+        // 注記は空文字列に置き換わり、ほかのノードは既定の変換のまま
+        assert_eq!(
+            renderer.render_line("前［＃三分アキ］後｜漢字《かんじ》"),
+            "前後<ruby><rb>漢字</rb><rp>（</rp><rt>かんじ</rt><rp>）</rp></ruby>"
+        );
+        // フックは続けて変換しても引き継がれる
+        assert_eq!(renderer.render_line("［＃不明な注記］"), "");
+        assert_eq!(count.get(), 2);
+
+        let html = renderer.render("題名\n著者\n\n本文［＃不明な注記］\n");
+        assert!(!html.contains("不明な注記"));
+        assert_eq!(count.get(), 3);
+    }
+
+    #[test]
+    fn test_render_to_writer_matches_render() {
+        use aozora_core::encoding::{encode_from_utf8, OutputEncoding};
+
+        let input = "題名\n著者\n\n［＃ここから２字下げ］\n吾輩《わがはい》は猫である\n［＃ここで字下げ終わり］\n本文［＃注記］\n\n底本：「吾輩は猫である」\n";
+
+        let options = RenderOptions::new().with_output_encoding(OutputEncoding::Utf8);
+        let expected = HtmlRenderer::new(options.clone()).render(input);
+        let mut written = Vec::new();
+        HtmlRenderer::new(options)
+            .render_to_writer(input, &mut written)
+            .unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), expected);
+
+        // 既定ではShift_JISでエンコードして書き出す
+        let expected = HtmlRenderer::new(RenderOptions::default()).render(input);
+        let mut written = Vec::new();
+        HtmlRenderer::new(RenderOptions::default())
+            .render_to_writer(input, &mut written)
+            .unwrap();
+        assert_eq!(
+            written,
+            encode_from_utf8(&expected, OutputEncoding::ShiftJis)
+        );
+    }
+
+    #[test]
+    fn test_render_to_writer_flushes_incrementally() {
+        /// 書き込みごとの内容とフラッシュの回数を記録するライター
+        struct ChunkWriter(Vec<String>, usize);
+
+        impl Write for ChunkWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.push(String::from_utf8(buf.to_vec()).unwrap());
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.1 += 1;
+                Ok(())
+            }
+        }
+
+        let options =
+            RenderOptions::new().with_output_encoding(aozora_core::encoding::OutputEncoding::Utf8);
+        let mut writer = ChunkWriter(Vec::new(), 0);
+        HtmlRenderer::new(options)
+            .render_to_writer("題名\n\n一行目\n二行目\n三行目", &mut writer)
+            .unwrap();
+
+        // ヘッダーと本文の各行は別々に書き出され、書き出すたびにフラッシュされる
+        let ChunkWriter(chunks, flushes) = writer;
+        assert_eq!(flushes, chunks.len());
+        assert!(chunks[0].ends_with("<div class=\"main_text vertical\">"));
+        assert_eq!(chunks[1], "一行目<br />\r\n");
+        assert_eq!(chunks[2], "二行目<br />\r\n");
+        assert!(chunks[3].starts_with("三行目<br />\r\n</div>"));
+    }
+
+    #[test]
+    fn test_render_to_writer_reports_write_error() {
+        /// 書き込みの回数を数え、常に失敗するライター
+        struct FailingWriter(usize);
+
+        impl Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                self.0 += 1;
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let rendered = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&rendered);
+        let mut renderer = HtmlRenderer::new(RenderOptions::default());
+        renderer.set_node_hook(Box::new(move |_| {
+            counter.set(counter.get() + 1);
+            None
+        }));
+        let mut writer = FailingWriter(0);
+        let error = renderer
+            .render_to_writer("題名\n\n一行目\n二行目\n三行目", &mut writer)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        // ヘッダーの書き込みで失敗したら、本文は変換しない
+        assert_eq!(writer.0, 1);
+        assert_eq!(rendered.get(), 0);
+
+        // フックはレンダラーに戻されている
+        renderer.render("題名\n\n本文");
+        assert!(rendered.get() > 0);
+    }
+
+    #[test]
+    fn test_render_pretty_indents_nested_blocks() {
+        let input = "題名\n\n［＃ここから罫囲み］\n外側\n［＃ここから２字下げ］\n内側\n［＃ここで字下げ終わり］\n［＃ここで罫囲み終わり］\n本文";
+        let options = RenderOptions::new().with_pretty(true);
+        let output = HtmlRenderer::new(options).render_fragment(input);
+        let lines: Vec<&str> = output.split("\r\n").collect();
+
+        assert!(lines[0].starts_with("<div class=\"keigakomi\""));
+        assert_eq!(lines[1], "  外側<br />");
+        assert!(lines[2].starts_with("  <div class=\"jisage_2\""));
+        assert_eq!(lines[3], "    内側<br />");
+        assert_eq!(lines[4], "  </div>");
+        assert_eq!(lines[5], "</div>");
+        assert_eq!(lines[6], "本文<br />");
+
+        // 空白以外は通常の出力と同じ
+        let plain = HtmlRenderer::new(RenderOptions::default()).render_fragment(input);
+        let unindented: Vec<&str> = lines.iter().map(|line| line.trim_start()).collect();
+        assert_eq!(unindented.join("\r\n"), plain);
+    }
 }
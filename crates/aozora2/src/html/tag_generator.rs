@@ -2,53 +2,76 @@
 //!
 //! ブロック要素のHTMLタグを生成する純粋関数を提供します。
 
-use aozora_core::node::{BlockParams, BlockType, MidashiLevel, MidashiStyle};
+use std::collections::HashMap;
+
+use aozora_core::node::{BlockParams, BlockType, MidashiLevel, MidashiStyle, StyleType};
+use aozora_core::toc::midashi_anchor_id;
 
 use super::presentation::{
-    midashi_combined_css_class, midashi_html_tag, style_css_class, style_html_tag,
+    mapped_style_css_class, midashi_combined_css_class, midashi_html_tag, style_html_tag,
 };
 
 /// ブロック開始タグを生成
 ///
-/// 見出しの場合は `midashi_id` を使用してアンカーIDを生成します。
+/// 見出しの場合は `midashi_id` を使用してアンカーIDを生成し、
+/// `heading_base_level` を大見出しのレベルとしてタグを決めます。
+/// 装飾のブロックのクラス名は `style_class_map` の指定を優先します。
 pub fn generate_block_start_tag(
     block_type: &BlockType,
     params: &BlockParams,
     midashi_id: Option<u32>,
+    heading_base_level: u8,
+    style_class_map: &HashMap<StyleType, String>,
 ) -> String {
     match block_type {
         BlockType::Jisage => generate_jisage_start(params),
         BlockType::Chitsuki => generate_chitsuki_start(params),
+        BlockType::Chuuou => "<div class=\"chuuou\" style=\"text-align:center\">".to_string(),
+        BlockType::PageCenter => "<div class=\"page-center\">".to_string(),
         BlockType::Jizume => generate_jizume_start(params),
         BlockType::Keigakomi => generate_keigakomi_start(params),
-        BlockType::Midashi => generate_midashi_start(params, midashi_id.unwrap_or(0)),
+        BlockType::Midashi => {
+            generate_midashi_start(params, midashi_id.unwrap_or(0), heading_base_level)
+        }
         BlockType::Yokogumi => generate_yokogumi_start(params),
-        BlockType::Futoji => "<div class=\"futoji\">".to_string(),
-        BlockType::Shatai => "<div class=\"shatai\">".to_string(),
+        BlockType::Futoji => format!(
+            "<div class=\"{}\">",
+            mapped_style_css_class(StyleType::Bold, style_class_map)
+        ),
+        BlockType::Shatai => format!(
+            "<div class=\"{}\">",
+            mapped_style_css_class(StyleType::Italic, style_class_map)
+        ),
         BlockType::FontDai => generate_font_dai_start(params),
         BlockType::FontSho => generate_font_sho_start(params),
         BlockType::Tcy => "<span dir=\"ltr\">".to_string(),
         BlockType::Caption => generate_caption_start(params),
         BlockType::Warigaki => generate_warigaki_start(params),
         BlockType::Burasage => generate_burasage_start(params),
-        BlockType::Style => generate_style_block_start(params),
+        BlockType::Style => generate_style_block_start(params, style_class_map),
         // 注記付き範囲はパース段階でRubyノードに解決されるので、ここには来ない
         BlockType::AnnotationRange | BlockType::LeftAnnotationRange => String::new(),
     }
 }
 
 /// ブロック終了タグを生成
-pub fn generate_block_end_tag(block_type: &BlockType, params: &BlockParams) -> String {
+pub fn generate_block_end_tag(
+    block_type: &BlockType,
+    params: &BlockParams,
+    heading_base_level: u8,
+) -> String {
     match block_type {
         BlockType::Jisage
         | BlockType::Chitsuki
+        | BlockType::Chuuou
+        | BlockType::PageCenter
         | BlockType::Jizume
         | BlockType::Futoji
         | BlockType::Shatai
         | BlockType::Burasage => "</div>".to_string(),
         BlockType::Keigakomi => generate_keigakomi_end(params),
         BlockType::Yokogumi => generate_yokogumi_end(params),
-        BlockType::Midashi => generate_midashi_end(params),
+        BlockType::Midashi => generate_midashi_end(params, heading_base_level),
         BlockType::FontDai | BlockType::FontSho => generate_font_end(params),
         BlockType::Tcy => "</span>".to_string(),
         BlockType::Caption => generate_caption_end(params),
@@ -114,17 +137,18 @@ fn generate_yokogumi_end(params: &BlockParams) -> String {
     }
 }
 
-fn generate_midashi_start(params: &BlockParams, midashi_id: u32) -> String {
+fn generate_midashi_start(params: &BlockParams, midashi_id: u32, heading_base_level: u8) -> String {
     let level = params.level.unwrap_or(MidashiLevel::O);
     let style = params.midashi_style.unwrap_or(MidashiStyle::Normal);
-    let tag = midashi_html_tag(level);
+    let tag = midashi_html_tag(level, heading_base_level);
     let class = midashi_combined_css_class(level, style);
-    format!("<{tag} class=\"{class}\"><a class=\"midashi_anchor\" id=\"midashi{midashi_id}\">")
+    let id = midashi_anchor_id(midashi_id);
+    format!("<{tag} class=\"{class}\"><a class=\"midashi_anchor\" id=\"{id}\">")
 }
 
-fn generate_midashi_end(params: &BlockParams) -> String {
+fn generate_midashi_end(params: &BlockParams, heading_base_level: u8) -> String {
     let level = params.level.unwrap_or(MidashiLevel::O);
-    format!("</a></{}>", midashi_html_tag(level))
+    format!("</a></{}>", midashi_html_tag(level, heading_base_level))
 }
 
 fn generate_font_dai_start(params: &BlockParams) -> String {
@@ -192,10 +216,13 @@ fn generate_burasage_start(params: &BlockParams) -> String {
     )
 }
 
-fn generate_style_block_start(params: &BlockParams) -> String {
+fn generate_style_block_start(
+    params: &BlockParams,
+    style_class_map: &HashMap<StyleType, String>,
+) -> String {
     if let Some(style_type) = params.style_type {
         let tag = style_html_tag(style_type);
-        let class = style_css_class(style_type);
+        let class = mapped_style_css_class(style_type, style_class_map);
         format!("<{tag} class=\"{class}\">")
     } else {
         "<span>".to_string()
@@ -214,6 +241,7 @@ fn generate_style_block_end(params: &BlockParams) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::html::presentation::DEFAULT_HEADING_BASE_LEVEL;
 
     #[test]
     fn test_generate_jisage_start() {
@@ -221,7 +249,13 @@ mod tests {
             width: Some(2),
             ..Default::default()
         };
-        let tag = generate_block_start_tag(&BlockType::Jisage, &params, None);
+        let tag = generate_block_start_tag(
+            &BlockType::Jisage,
+            &params,
+            None,
+            DEFAULT_HEADING_BASE_LEVEL,
+            &HashMap::new(),
+        );
         assert_eq!(tag, "<div class=\"jisage_2\" style=\"margin-left: 2em\">");
     }
 
@@ -231,14 +265,26 @@ mod tests {
             is_block: true,
             ..Default::default()
         };
-        let tag = generate_block_start_tag(&BlockType::Caption, &params, None);
+        let tag = generate_block_start_tag(
+            &BlockType::Caption,
+            &params,
+            None,
+            DEFAULT_HEADING_BASE_LEVEL,
+            &HashMap::new(),
+        );
         assert_eq!(tag, "<div class=\"caption\">");
     }
 
     #[test]
     fn test_generate_caption_start_inline() {
         let params = BlockParams::default();
-        let tag = generate_block_start_tag(&BlockType::Caption, &params, None);
+        let tag = generate_block_start_tag(
+            &BlockType::Caption,
+            &params,
+            None,
+            DEFAULT_HEADING_BASE_LEVEL,
+            &HashMap::new(),
+        );
         assert_eq!(tag, "<span class=\"caption\">");
     }
 
@@ -246,9 +292,12 @@ mod tests {
     fn test_generate_block_end() {
         let params = BlockParams::default();
         assert_eq!(
-            generate_block_end_tag(&BlockType::Jisage, &params),
+            generate_block_end_tag(&BlockType::Jisage, &params, DEFAULT_HEADING_BASE_LEVEL),
             "</div>"
         );
-        assert_eq!(generate_block_end_tag(&BlockType::Tcy, &params), "</span>");
+        assert_eq!(
+            generate_block_end_tag(&BlockType::Tcy, &params, DEFAULT_HEADING_BASE_LEVEL),
+            "</span>"
+        );
     }
 }
@@ -6,6 +6,10 @@
 //!
 //! - `strip` - プレーンテキストへの変換（注記・ルビを除去）
 //! - `html` - HTMLへの変換
+//! - `epub` - EPUBへの変換
+//! - `tts` - 読み上げ用テキストへの変換
+//! - [`convert_both`] - プレーンテキストとHTMLへの同時変換
+//! - `api` - 他言語のバインディング向けの平坦な関数
 //!
 //! # 使用例
 //!
@@ -17,8 +21,61 @@
 //! assert_eq!(plain, "吾輩は猫である");
 //! ```
 
+pub mod api;
+pub mod epub;
 pub mod html;
 pub mod strip;
+pub mod tts;
 
 // Re-export aozora-core for downstream crates
 pub use aozora_core;
+
+use aozora_core::document::body_line_range;
+use aozora_core::encoding;
+use aozora_core::tokenizer::Tokenizer;
+
+/// 青空文庫形式のバイト列をプレーンテキストとHTMLの両方に変換
+///
+/// 本文の各行を一度だけトークン列に変換し、[`strip::convert`] と同じプレーンテキストと、
+/// 既定のオプションでの [`html::convert`] と同じHTMLを返します。
+/// 検索用のテキストと表示用のHTMLを同時に作る場合に使用します。
+///
+/// # Examples
+///
+/// ```
+/// let input = "タイトル\n\n吾輩《わがはい》は猫である";
+/// let (plain, html) = aozora2::convert_both(input.as_bytes());
+/// assert_eq!(plain, "吾輩は猫である\n");
+/// assert!(html.contains("<ruby><rb>吾輩</rb>"));
+/// ```
+pub fn convert_both(input: &[u8]) -> (String, String) {
+    let text = encoding::decode_to_utf8(input);
+    let lines: Vec<&str> = text.lines().collect();
+    let body_tokens: Vec<_> = lines[body_line_range(&lines)]
+        .iter()
+        .map(|line| Tokenizer::new(line).tokenize())
+        .collect();
+
+    let plain = strip::convert_tokenized_lines(&body_tokens, &strip::StripOptions::default());
+    let html = html::convert_tokenized(&text, &body_tokens, &html::RenderOptions::default());
+    (plain, html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_both_matches_separate_conversions() {
+        let input = "吾輩は猫である\n夏目漱石\n\n［＃ここから２字下げ］\n｜吾輩《わがはい》は猫である［＃「猫」に傍点］\n※［＃「てへん＋劣」、第3水準1-84-77］と※［＃「てへん＋劣」、120-8］\n［＃ここで字下げ終わり］\n［＃中見出し］一［＃中見出し終わり］\n\n底本：「吾輩は猫である」\n";
+
+        for bytes in [
+            input.as_bytes().to_vec(),
+            encoding::encode_from_utf8(input, encoding::OutputEncoding::ShiftJis),
+        ] {
+            let (plain, html) = convert_both(&bytes);
+            assert_eq!(plain, strip::convert(&bytes));
+            assert_eq!(html, html::convert(input, &html::RenderOptions::default()));
+        }
+    }
+}
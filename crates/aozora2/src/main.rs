@@ -22,6 +22,8 @@ enum Commands {
     Strip(commands::strip::Args),
     /// HTMLに変換
     Html(commands::html::Args),
+    /// EPUBに変換
+    Epub(commands::epub::Args),
 }
 
 fn main() -> io::Result<()> {
@@ -29,5 +31,6 @@ fn main() -> io::Result<()> {
     match cli.command {
         Commands::Strip(args) => commands::strip::run(args),
         Commands::Html(args) => commands::html::run(args),
+        Commands::Epub(args) => commands::epub::run(args),
     }
 }
@@ -2,13 +2,156 @@
 //!
 //! 青空文庫形式のテキストからルビ・注記を除去してプレーンテキストに変換します。
 
+use std::borrow::Cow;
+
 use aozora_core::accent::convert_accent;
+use aozora_core::char_type::CharType;
 use aozora_core::document;
 use aozora_core::encoding;
-use aozora_core::gaiji::convert_gaiji;
+use aozora_core::gaiji::try_convert_gaiji;
+use aozora_core::kana::{compose_voiced_marks, normalize_halfwidth_kana};
+use aozora_core::node::BlockType;
+use aozora_core::parser::{extract_ruby_base, parse_command, CommandResult};
 use aozora_core::token::Token;
 use aozora_core::tokenizer::Tokenizer;
 
+/// ルビの出力方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RubyMode {
+    /// ルビを削除し、親文字のみ出力
+    #[default]
+    Remove,
+    /// 親文字の後にルビを全角括弧で出力（`吾輩（わがはい）`）
+    Paren,
+    /// 親文字の代わりにルビを出力（`わがはい`）
+    ReadingOnly,
+}
+
+/// Unicodeに変換できない外字の代替表記
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum GaijiPlaceholder {
+    /// ゲタ記号（〓）
+    #[default]
+    Geta,
+    /// 何も出力しない
+    Empty,
+    /// 外字の説明（`「てへん＋劣」、120-8` など）
+    Description,
+    /// 外字の名前（説明の「」内の `てへん＋劣` など。「」がなければ説明全体）
+    Name,
+    /// 任意の文字列
+    Custom(String),
+}
+
+impl GaijiPlaceholder {
+    /// 外字の説明に対する代替表記を生成
+    fn render(&self, description: &str) -> String {
+        match self {
+            GaijiPlaceholder::Geta => "〓".to_string(),
+            GaijiPlaceholder::Empty => String::new(),
+            GaijiPlaceholder::Description => description.to_string(),
+            GaijiPlaceholder::Name => description
+                .strip_prefix('「')
+                .and_then(|rest| rest.split_once('」'))
+                .map_or(description, |(name, _)| name)
+                .to_string(),
+            GaijiPlaceholder::Custom(text) => text.clone(),
+        }
+    }
+}
+
+/// プレーンテキスト変換オプション
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StripOptions {
+    /// 字下げ・罫囲み・地付きブロックの前後に空行を出力
+    pub block_markers: bool,
+    /// 半角カタカナを全角に変換してから解析
+    pub normalize_halfwidth_kana: bool,
+    /// ルビの出力方式
+    pub ruby_mode: RubyMode,
+    /// 親文字に漢字を含むルビだけを出力（かなだけの親文字はルビを除いて出力）
+    pub ruby_only_kanji: bool,
+    /// Unicodeに変換できない外字の代替表記
+    pub gaiji_placeholder: GaijiPlaceholder,
+    /// 傍点を付けた文字に結合文字の点（U+0323）を重ねる
+    pub bouten_combining: bool,
+    /// 字下げブロック内の行の先頭に字下げの幅だけ全角空白を出力
+    pub preserve_indent: bool,
+    /// 出力の仮名と結合用の濁点・半濁点の組を合成済みの文字にまとめる
+    pub compose_voiced_kana: bool,
+}
+
+impl StripOptions {
+    /// 新しいオプションを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ブロック前後の空行出力を設定
+    pub fn with_block_markers(mut self, enabled: bool) -> Self {
+        self.block_markers = enabled;
+        self
+    }
+
+    /// 半角カタカナの全角変換を設定
+    pub fn with_normalize_halfwidth_kana(mut self, enabled: bool) -> Self {
+        self.normalize_halfwidth_kana = enabled;
+        self
+    }
+
+    /// ルビの出力方式を設定
+    pub fn with_ruby_mode(mut self, mode: RubyMode) -> Self {
+        self.ruby_mode = mode;
+        self
+    }
+
+    /// 漢字の親文字だけにルビを出力するかどうかを設定
+    ///
+    /// 有効にすると、親文字に漢字（外字を含む）がないルビは [`RubyMode`] によらず
+    /// 親文字だけを出力します。
+    pub fn with_ruby_only_kanji(mut self, enabled: bool) -> Self {
+        self.ruby_only_kanji = enabled;
+        self
+    }
+
+    /// 変換できない外字の代替表記を設定
+    pub fn with_gaiji_placeholder(mut self, placeholder: GaijiPlaceholder) -> Self {
+        self.gaiji_placeholder = placeholder;
+        self
+    }
+
+    /// 傍点の結合文字での出力を設定
+    ///
+    /// 有効にすると、傍点（左の傍点を含む）を付けた文字の後に結合文字の点（U+0323）を
+    /// 付けて出力し、プレーンテキストでも強調が分かるようにします。
+    /// 前方参照（`［＃「…」に傍点］`）と同じ行の中の範囲指定（`［＃傍点］…［＃傍点終わり］`）が対象です。
+    pub fn with_bouten_combining(mut self, enabled: bool) -> Self {
+        self.bouten_combining = enabled;
+        self
+    }
+
+    /// 字下げの保持を設定
+    ///
+    /// 有効にすると、字下げブロック（`［＃ここから2字下げ］`〜`［＃ここで字下げ終わり］`）の
+    /// 中の空行以外の行の先頭に、字下げの字数だけ全角空白を出力します。
+    /// 入れ子の字下げでは内側のブロックの字数を使います。
+    /// 折り返し指定のあるブロックでは最初の行の字数を使います。
+    pub fn with_preserve_indent(mut self, enabled: bool) -> Self {
+        self.preserve_indent = enabled;
+        self
+    }
+
+    /// 濁点・半濁点の合成を設定
+    ///
+    /// 有効にすると、出力中の仮名と結合用の濁点（U+3099）・半濁点（U+309A）の組を、
+    /// 合成済みの文字があればその文字にまとめます。それ以外の結合文字はそのまま出力します。
+    /// NFC正規化は行いません（ラテン文字と結合用のアクセント記号の組などは合成しません）。
+    pub fn with_compose_voiced_kana(mut self, enabled: bool) -> Self {
+        self.compose_voiced_kana = enabled;
+        self
+    }
+}
+
 /// 青空文庫形式のバイト列をプレーンテキストに変換
 ///
 /// エンコーディング自動判定（UTF-8 / Shift_JIS）、
@@ -22,11 +165,79 @@ use aozora_core::tokenizer::Tokenizer;
 /// assert_eq!(plain, "本文です\n");
 /// ```
 pub fn convert(input: &[u8]) -> String {
+    convert_with_options(input, &StripOptions::default())
+}
+
+/// オプションを指定して青空文庫形式のバイト列をプレーンテキストに変換
+///
+/// `block_markers` を指定すると、字下げ・罫囲み・地付きブロックの前後に
+/// 空行を1行ずつ出力します（連続する空行はまとめます）。
+///
+/// # Examples
+///
+/// ```
+/// use aozora2::strip::{convert_with_options, StripOptions};
+///
+/// let input = "タイトル\n\n前文\n［＃ここから2字下げ］引用［＃ここで字下げ終わり］\n後文";
+/// let options = StripOptions::new().with_block_markers(true);
+/// let plain = convert_with_options(input.as_bytes(), &options);
+/// assert_eq!(plain, "前文\n\n引用\n\n後文\n");
+/// ```
+pub fn convert_with_options(input: &[u8], options: &StripOptions) -> String {
     let text = encoding::decode_to_utf8(input);
-    let lines: Vec<&str> = text.lines().collect();
+    convert_str_with_options(&text, options)
+}
+
+/// UTF-8の文字列をプレーンテキストに変換
+///
+/// エンコーディング判定を行わず、本文抽出と変換のみを行う。
+///
+/// # Examples
+///
+/// ```
+/// let input = "タイトル\n著者\n\n吾輩《わがはい》は猫である\n底本：青空文庫";
+/// let plain = aozora2::strip::convert_str(input);
+/// assert_eq!(plain, "吾輩は猫である\n");
+/// ```
+pub fn convert_str(input: &str) -> String {
+    convert_str_with_options(input, &StripOptions::default())
+}
+
+/// オプションを指定してUTF-8の文字列をプレーンテキストに変換
+pub fn convert_str_with_options(input: &str, options: &StripOptions) -> String {
+    let lines: Vec<&str> = input.lines().collect();
     let body_lines = document::extract_body_lines(&lines);
 
-    let converted: Vec<String> = body_lines.iter().map(|line| convert_line(line)).collect();
+    let tokenized: Vec<Vec<Token>> = body_lines
+        .iter()
+        .map(|line| {
+            // 行ごとに半角カタカナを全角に変換
+            let line = if options.normalize_halfwidth_kana {
+                normalize_halfwidth_kana(line)
+            } else {
+                Cow::Borrowed(*line)
+            };
+            Tokenizer::new(&line).tokenize()
+        })
+        .collect();
+
+    convert_tokenized_lines(&tokenized, options)
+}
+
+/// トークン列に変換済みの本文の行をプレーンテキストに変換
+pub(crate) fn convert_tokenized_lines(lines: &[Vec<Token>], options: &StripOptions) -> String {
+    let mut texts: Vec<String> = lines
+        .iter()
+        .map(|tokens| extract(tokens, options))
+        .collect();
+    if options.preserve_indent {
+        indent_jisage_blocks(lines, &mut texts);
+    }
+    let converted = if options.block_markers {
+        add_block_markers(lines, texts)
+    } else {
+        texts
+    };
 
     // 冒頭と末尾の空行を削除
     let start = converted.iter().position(|s| !s.is_empty()).unwrap_or(0);
@@ -37,12 +248,33 @@ pub fn convert(input: &[u8]) -> String {
         .unwrap_or(0);
 
     if start >= end {
-        String::new()
+        return String::new();
+    }
+    let text = converted[start..end].join("\n") + "\n";
+    if options.compose_voiced_kana {
+        compose_voiced_marks(&text).into_owned()
     } else {
-        converted[start..end].join("\n") + "\n"
+        text
     }
 }
 
+/// UTF-8の文字列を読みやすいテキストに変換
+///
+/// 注記・コマンドは削除し、ルビは親文字の後に全角括弧で残します。
+/// 外字はUnicode文字に変換します（[`convert_str`] と同じ）。
+///
+/// # Examples
+///
+/// ```
+/// let input = "タイトル\n\n吾輩《わがはい》は猫である［＃「猫」に傍点］";
+/// let text = aozora2::strip::convert_readable(input);
+/// assert_eq!(text, "吾輩（わがはい）は猫である\n");
+/// ```
+pub fn convert_readable(input: &str) -> String {
+    let options = StripOptions::new().with_ruby_mode(RubyMode::Paren);
+    convert_str_with_options(input, &options)
+}
+
 /// 青空文庫形式の文字列をプレーンテキストに変換（本文抽出なし）
 ///
 /// 前付け・後付けの除去を行わず、入力全体を変換する。
@@ -57,34 +289,259 @@ pub fn convert(input: &[u8]) -> String {
 pub fn convert_line(input: &str) -> String {
     let mut tokenizer = Tokenizer::new(input);
     let tokens = tokenizer.tokenize();
-    extract(&tokens)
+    extract(&tokens, &StripOptions::default())
+}
+
+/// 変換済みの行のブロック境界に空行を挟む
+fn add_block_markers(lines: &[Vec<Token>], texts: Vec<String>) -> Vec<String> {
+    let mut output: Vec<String> = Vec::new();
+    // 直前にブロック境界の空行を出力したか
+    let mut separated = false;
+
+    for (tokens, text) in lines.iter().zip(texts) {
+        let (opens, closes) = block_boundaries(tokens);
+
+        if opens {
+            push_separator(&mut output);
+            separated = true;
+        }
+        // ブロック指定のみの行や境界直後の空行は区切りの空行にまとめる
+        if !text.is_empty() {
+            output.push(text);
+            separated = false;
+        } else if !(separated || opens || closes) {
+            output.push(text);
+        }
+        if closes {
+            push_separator(&mut output);
+            separated = true;
+        }
+    }
+
+    output
+}
+
+/// 字下げブロック内の変換済みの行の先頭に、字下げの幅だけ全角空白を付ける
+///
+/// ブロックを開始した行には開始後の幅を、終了した行には終了前の幅を使います。
+fn indent_jisage_blocks(lines: &[Vec<Token>], texts: &mut [String]) {
+    // 開いている字下げブロックの幅
+    let mut widths: Vec<u32> = Vec::new();
+
+    for (tokens, text) in lines.iter().zip(texts.iter_mut()) {
+        let mut line_width = widths.last().copied();
+        for token in tokens {
+            let Token::Command { content } = token else {
+                continue;
+            };
+            match parse_command(content) {
+                CommandResult::BlockStart {
+                    block_type: BlockType::Jisage | BlockType::Burasage,
+                    params,
+                } if params.is_block => {
+                    let width = params.width.unwrap_or(0);
+                    widths.push(width);
+                    line_width = Some(width);
+                }
+                // 折り返し指定のあるブロックも「ここで字下げ終わり」で閉じる
+                CommandResult::BlockEnd {
+                    block_type: BlockType::Jisage,
+                } if content.trim().starts_with("ここで") => {
+                    widths.pop();
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(width) = line_width.filter(|&width| width > 0) {
+            if !text.is_empty() {
+                text.insert_str(0, &"　".repeat(width as usize));
+            }
+        }
+    }
+}
+
+/// 直前が空行でなければ空行を追加
+fn push_separator(output: &mut Vec<String>) {
+    if output.last().is_some_and(|line| !line.is_empty()) {
+        output.push(String::new());
+    }
+}
+
+/// 区切りの空行を挟むブロックかどうか
+fn is_marked_block(block_type: BlockType) -> bool {
+    matches!(
+        block_type,
+        BlockType::Jisage
+            | BlockType::Burasage
+            | BlockType::Keigakomi
+            | BlockType::Chitsuki
+            | BlockType::Chuuou
+            | BlockType::PageCenter
+    )
+}
+
+/// 行内に対象ブロックの開始・終了があるかを判定
+fn block_boundaries(tokens: &[Token]) -> (bool, bool) {
+    let mut opens = false;
+    let mut closes = false;
+
+    for token in tokens {
+        let Token::Command { content } = token else {
+            continue;
+        };
+        match parse_command(content) {
+            CommandResult::BlockStart { block_type, params }
+                if params.is_block && is_marked_block(block_type) =>
+            {
+                opens = true;
+            }
+            CommandResult::BlockEnd { block_type }
+                if content.trim().starts_with("ここで") && is_marked_block(block_type) =>
+            {
+                closes = true;
+            }
+            _ => {}
+        }
+    }
+
+    (opens, closes)
 }
 
 /// トークン列をプレーンテキストに変換
-fn extract(tokens: &[Token]) -> String {
-    tokens.iter().map(extract_token).collect()
+fn extract(tokens: &[Token], options: &StripOptions) -> String {
+    let mut output = String::new();
+    // 範囲指定の傍点の開始位置
+    let mut bouten_starts: Vec<usize> = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if options.bouten_combining {
+            if let Token::Command { content } = token {
+                apply_bouten_command(content, &mut output, &mut bouten_starts);
+            }
+        }
+        // 漢字を含まない親文字のルビは親文字だけを出力
+        if options.ruby_only_kanji && !ruby_base_has_kanji(tokens, i) {
+            if let Token::PrefixedRuby { base_children, .. } = token {
+                output.push_str(&extract(base_children, options));
+            }
+            continue;
+        }
+        // ルビのみの出力では、直前のテキストから親文字を取り除く
+        if options.ruby_mode == RubyMode::ReadingOnly && matches!(token, Token::Ruby { .. }) {
+            if let Some(Token::Text(prev)) = i.checked_sub(1).map(|j| &tokens[j]) {
+                if let Some(result) = extract_ruby_base(prev) {
+                    output.truncate(output.len() - result.base.len());
+                }
+            }
+        }
+        output.push_str(&extract_token(token, options));
+    }
+
+    output
+}
+
+/// 傍点のコマンドに応じて、出力済みのテキストに結合文字の点を付ける
+fn apply_bouten_command(content: &str, output: &mut String, starts: &mut Vec<usize>) {
+    match parse_command(content) {
+        CommandResult::Style {
+            target, style_type, ..
+        } if style_type.is_bouten() => {
+            if let Some(pos) = output.rfind(&target) {
+                let marked = add_combining_dots(&target);
+                output.replace_range(pos..pos + target.len(), &marked);
+            }
+        }
+        CommandResult::StyleStart { style_type } if style_type.is_bouten() => {
+            starts.push(output.len());
+        }
+        CommandResult::StyleEnd { style_type } if style_type.is_bouten() => {
+            if let Some(start) = starts.pop() {
+                let marked = add_combining_dots(&output[start..]);
+                output.replace_range(start.., &marked);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 空白以外の各文字の後に結合文字の点（U+0323）を付ける
+fn add_combining_dots(text: &str) -> String {
+    let mut marked = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        marked.push(c);
+        if !c.is_whitespace() {
+            marked.push('\u{0323}');
+        }
+    }
+    marked
+}
+
+/// `tokens[i]` がルビの場合、その親文字が漢字を含むかどうか（ルビ以外は常に `true`）
+fn ruby_base_has_kanji(tokens: &[Token], i: usize) -> bool {
+    match &tokens[i] {
+        Token::Ruby { .. } => match i.checked_sub(1).map(|j| &tokens[j]) {
+            Some(Token::Text(prev)) => {
+                extract_ruby_base(prev).is_none_or(|result| has_kanji(&result.base))
+            }
+            _ => true,
+        },
+        Token::PrefixedRuby { base_children, .. } => {
+            base_children.iter().any(|token| match token {
+                Token::Text(text) => has_kanji(text),
+                Token::Gaiji { .. } => true,
+                _ => false,
+            })
+        }
+        _ => true,
+    }
+}
+
+/// 漢字を含むかどうか
+fn has_kanji(text: &str) -> bool {
+    text.chars()
+        .any(|c| CharType::classify(c) == CharType::Kanji)
 }
 
 /// 単一トークンからテキストを抽出
-fn extract_token(token: &Token) -> String {
+fn extract_token(token: &Token, options: &StripOptions) -> String {
     match token {
         // テキスト: そのまま出力
         Token::Text(s) => s.clone(),
 
         // 暗黙ルビ: 削除（親文字は直前のTextに含まれる）
-        Token::Ruby { .. } => String::new(),
+        Token::Ruby { children } => match options.ruby_mode {
+            RubyMode::Remove => String::new(),
+            RubyMode::Paren => format!("（{}）", extract(children, options)),
+            RubyMode::ReadingOnly => extract(children, options),
+        },
 
         // 明示ルビ: 親文字部分のみ抽出
-        Token::PrefixedRuby { base_children, .. } => extract(base_children),
+        Token::PrefixedRuby {
+            base_children,
+            ruby_children,
+        } => match options.ruby_mode {
+            RubyMode::Remove => extract(base_children, options),
+            RubyMode::Paren => format!(
+                "{}（{}）",
+                extract(base_children, options),
+                extract(ruby_children, options)
+            ),
+            RubyMode::ReadingOnly => extract(ruby_children, options),
+        },
 
-        // コマンド: 削除
-        Token::Command { .. } => String::new(),
+        // コマンド: 削除（空白・ダッシュを表すものは文字に置き換える）
+        Token::Command { content } => match parse_command(content) {
+            CommandResult::Character(text) => text,
+            _ => String::new(),
+        },
 
-        // 外字: Unicode文字列に変換
-        Token::Gaiji { description } => convert_gaiji(description),
+        // 外字: Unicode文字列に変換（変換できない場合は代替表記）
+        Token::Gaiji { description } => try_convert_gaiji(description)
+            .unwrap_or_else(|| options.gaiji_placeholder.render(description)),
 
         // アクセント: 内容を抽出してアクセント変換
-        Token::Accent { children } => convert_accent(&extract(children)),
+        Token::Accent { children } => convert_accent(&extract(children, options)),
     }
 }
 
@@ -130,10 +587,219 @@ mod tests {
         assert_eq!(convert_line("〔cafe'〕"), "café");
     }
 
+    #[test]
+    fn test_accent_block_with_plain_words() {
+        assert_eq!(convert_line("〔cafe' au lait〕"), "café au lait");
+        assert_eq!(
+            convert_line("前〔cafe', s'il vous plait!〕後"),
+            "前café, s'il vous plait!後"
+        );
+    }
+
     #[test]
     fn test_convert_with_header_footer() {
         let input = "タイトル\n著者\n\n本文です\n底本：青空文庫";
         let plain = convert(input.as_bytes());
         assert_eq!(plain, "本文です\n");
     }
+
+    const JISAGE_DOCUMENT: &str = "タイトル\n\n前文\n［＃ここから2字下げ］引用の一行目\n引用の二行目［＃ここで字下げ終わり］\n後文";
+
+    #[test]
+    fn test_jisage_block_without_markers() {
+        let plain = convert(JISAGE_DOCUMENT.as_bytes());
+        assert_eq!(plain, "前文\n引用の一行目\n引用の二行目\n後文\n");
+    }
+
+    #[test]
+    fn test_jisage_block_with_markers() {
+        let options = StripOptions::new().with_block_markers(true);
+        let plain = convert_with_options(JISAGE_DOCUMENT.as_bytes(), &options);
+        assert_eq!(plain, "前文\n\n引用の一行目\n引用の二行目\n\n後文\n");
+    }
+
+    #[test]
+    fn test_block_markers_collapse_command_lines() {
+        let input =
+            "タイトル\n\n前文\n［＃ここから地付き］\n署名\n［＃ここで地付き終わり］\n\n後文";
+        let options = StripOptions::new().with_block_markers(true);
+        let plain = convert_with_options(input.as_bytes(), &options);
+        assert_eq!(plain, "前文\n\n署名\n\n後文\n");
+    }
+
+    #[test]
+    fn test_preserve_indent() {
+        let input = "タイトル\n\n前文\n［＃ここから2字下げ］\n引用1\n\n引用2\n［＃ここで字下げ終わり］\n後文";
+        assert_eq!(convert_str(input), "前文\n\n引用1\n\n引用2\n\n後文\n");
+
+        let options = StripOptions::new().with_preserve_indent(true);
+        assert_eq!(
+            convert_str_with_options(input, &options),
+            "前文\n\n　　引用1\n\n　　引用2\n\n後文\n"
+        );
+
+        // 同じ行で開始・終了するブロックと、入れ子のブロック
+        let input = "タイトル\n\n［＃ここから2字下げ］引用［＃ここで字下げ終わり］\n［＃ここから1字下げ］\n外\n［＃ここから3字下げ］\n内\n［＃ここで字下げ終わり］\n外\n［＃ここで字下げ終わり］";
+        assert_eq!(
+            convert_str_with_options(input, &options),
+            "　　引用\n\n　外\n\n　　　内\n\n　外\n"
+        );
+    }
+
+    #[test]
+    fn test_compose_voiced_kana() {
+        // 合成済みの文字があれば合成し、なければ結合文字のまま残す
+        let input = "タイトル\n\nハ\u{309A}ンと※［＃「半濁点付き片仮名カ」、第3水準1-5-87］";
+        assert_eq!(convert_str(input), "ハ\u{309A}ンとカ\u{309A}\n");
+
+        let options = StripOptions::new().with_compose_voiced_kana(true);
+        assert_eq!(
+            convert_str_with_options(input, &options),
+            "パンとカ\u{309A}\n"
+        );
+
+        // 仮名以外の結合文字は合成しない
+        let input = "タイトル\n\ncafe\u{0301}";
+        assert_eq!(convert_str_with_options(input, &options), "cafe\u{0301}\n");
+    }
+
+    #[test]
+    fn test_convert_str_matches_convert() {
+        let input = "吾輩は猫である\n夏目漱石\n\n吾輩《わがはい》は猫《ねこ》である。\n名前はまだ無い［＃「無い」に傍点］。\n\n底本：「夏目漱石全集」";
+        assert_eq!(convert_str(input), convert(input.as_bytes()));
+    }
+
+    #[test]
+    fn test_kunojiten_preserved() {
+        assert_eq!(convert_line("かわる〳〵見る"), "かわる〳〵見る");
+        assert_eq!(convert_line("しみ〴〵と［＃「と」に傍点］"), "しみ〴〵と");
+    }
+
+    #[test]
+    fn test_normalize_halfwidth_kana() {
+        let input = "タイトル\n\nｶﾀｶﾅ《かたかな》とＡＢＣ";
+        let options = StripOptions::new().with_normalize_halfwidth_kana(true);
+        assert_eq!(
+            convert_str_with_options(input, &options),
+            "カタカナとＡＢＣ\n"
+        );
+        assert_eq!(convert_str(input), "ｶﾀｶﾅとＡＢＣ\n");
+    }
+
+    #[test]
+    fn test_character_commands() {
+        assert_eq!(
+            convert_line("前［＃四分アキ］後［＃全角ダッシュ］"),
+            "前\u{2005}後―"
+        );
+    }
+
+    #[test]
+    fn test_convert_readable() {
+        let input = "タイトル\n著者\n\n吾輩《わがはい》は｜猫又《ねこまた》である※［＃「丸印」、U+25CB］\n猫である［＃「である」に傍点］\n底本：青空文庫";
+        let text = convert_readable(input);
+        assert_eq!(
+            text,
+            "吾輩（わがはい）は猫又（ねこまた）である○\n猫である\n"
+        );
+        assert!(!text.contains('［'));
+    }
+
+    #[test]
+    fn test_ruby_mode_paren_with_block_markers() {
+        let input = "タイトル\n\n［＃ここから2字下げ］漢字《かんじ》［＃ここで字下げ終わり］";
+        let options = StripOptions::new()
+            .with_block_markers(true)
+            .with_ruby_mode(RubyMode::Paren);
+        assert_eq!(
+            convert_str_with_options(input, &options),
+            "漢字（かんじ）\n"
+        );
+    }
+
+    #[test]
+    fn test_ruby_mode_reading_only() {
+        let input = "タイトル\n\n吾輩《わがはい》は｜猫又《ねこまた》である［＃「である」に傍点］\n見当《けんとう》がつかぬ\nルビのない行";
+        let options = StripOptions::new().with_ruby_mode(RubyMode::ReadingOnly);
+        assert_eq!(
+            convert_str_with_options(input, &options),
+            "わがはいはねこまたである\nけんとうがつかぬ\nルビのない行\n"
+        );
+    }
+
+    #[test]
+    fn test_bouten_combining() {
+        let options = StripOptions::new().with_bouten_combining(true);
+        let convert = |input: &str| convert_str_with_options(input, &options);
+
+        assert_eq!(
+            convert("題名\n\n吾輩は猫である［＃「猫」に傍点］"),
+            "吾輩は猫\u{0323}である\n"
+        );
+        // 範囲指定・左の傍点・ルビのある親文字
+        assert_eq!(
+            convert("題名\n\n前［＃丸傍点］強調［＃丸傍点終わり］後"),
+            "前強\u{0323}調\u{0323}後\n"
+        );
+        assert_eq!(
+            convert("題名\n\n同じ語と同じ語［＃「同じ語」の左に傍点］"),
+            "同じ語と同\u{0323}じ\u{0323}語\u{0323}\n"
+        );
+        assert_eq!(
+            convert("題名\n\n吾輩《わがはい》は猫［＃「吾輩」に傍点］"),
+            "吾\u{0323}輩\u{0323}は猫\n"
+        );
+
+        // 傍線や、オプションを指定しない場合はそのまま
+        let input = "題名\n\n吾輩は猫である［＃「猫」に傍線］";
+        assert_eq!(convert(input), "吾輩は猫である\n");
+        let input = "題名\n\n吾輩は猫である［＃「猫」に傍点］";
+        assert_eq!(convert_str(input), "吾輩は猫である\n");
+    }
+
+    #[test]
+    fn test_ruby_only_kanji() {
+        let input = "タイトル\n\n吾輩《わがはい》は｜ねこ《ネコ》である\nさよう《ヽヽヽ》なら｜※［＃「てへん＋劣」、第3水準1-84-77］《れつ》";
+        let convert_with = |mode: RubyMode| {
+            let options = StripOptions::new()
+                .with_ruby_mode(mode)
+                .with_ruby_only_kanji(true);
+            convert_str_with_options(input, &options)
+        };
+
+        assert_eq!(
+            convert_with(RubyMode::Paren),
+            "吾輩（わがはい）はねこである\nさようなら挘（れつ）\n"
+        );
+        assert_eq!(
+            convert_with(RubyMode::ReadingOnly),
+            "わがはいはねこである\nさようなられつ\n"
+        );
+        assert_eq!(
+            convert_with(RubyMode::Remove),
+            "吾輩はねこである\nさようなら挘\n"
+        );
+    }
+
+    #[test]
+    fn test_gaiji_placeholder() {
+        let input = "タイトル\n\n※［＃「てへん＋劣」、120-8］と※［＃「丸印」、U+25CB］";
+        let convert_with = |placeholder: GaijiPlaceholder| {
+            let options = StripOptions::new().with_gaiji_placeholder(placeholder);
+            convert_str_with_options(input, &options)
+        };
+
+        assert_eq!(convert_with(GaijiPlaceholder::Geta), "〓と○\n");
+        assert_eq!(convert_str(input), "〓と○\n");
+        assert_eq!(convert_with(GaijiPlaceholder::Empty), "と○\n");
+        assert_eq!(
+            convert_with(GaijiPlaceholder::Description),
+            "「てへん＋劣」、120-8と○\n"
+        );
+        assert_eq!(convert_with(GaijiPlaceholder::Name), "てへん＋劣と○\n");
+        assert_eq!(
+            convert_with(GaijiPlaceholder::Custom("[?]".to_string())),
+            "[?]と○\n"
+        );
+    }
 }
@@ -0,0 +1,74 @@
+//! 読み上げ用テキストへの変換（tts）
+//!
+//! 音声合成（TTS）に渡すために、青空文庫形式のテキストを意図した読みのテキストに変換します。
+//!
+//! # 変換の方針
+//!
+//! - 漢字を含む親文字のルビは、親文字の代わりにルビを出力する（`吾輩《わがはい》` → `わがはい`）
+//! - かなだけの親文字のルビ（傍点代わりの `ヽヽヽ` など）は、ルビを除いて親文字を出力する
+//! - ルビのないテキストはそのまま出力する
+//! - 外字はUnicode文字に変換し、変換できなければ外字の名前（説明の「」内）を出力する
+//! - 縦中横・割り注などの内容は通常のテキストとして出力し、数字は変換せずにそのまま残す
+//!   （数字の読み方は音声合成エンジンに任せる）
+//! - 注記・コマンドは削除する（全角アキ・ダッシュなど文字を表すものは文字に置き換える）
+//!   注記だけの行は空行になる
+//!
+//! ヘッダー・底本情報は除き、本文だけを変換します。
+
+use crate::strip::{convert_str_with_options, GaijiPlaceholder, RubyMode, StripOptions};
+
+/// 読み上げ用の変換オプション
+fn tts_options() -> StripOptions {
+    StripOptions::new()
+        .with_ruby_mode(RubyMode::ReadingOnly)
+        .with_ruby_only_kanji(true)
+        .with_gaiji_placeholder(GaijiPlaceholder::Name)
+}
+
+/// 青空文庫形式のテキストを読み上げ用のテキストに変換
+///
+/// # Examples
+///
+/// ```
+/// let text = aozora2::tts::convert("タイトル\n\n吾輩《わがはい》は猫である");
+/// assert_eq!(text, "わがはいは猫である\n");
+/// ```
+pub fn convert(input: &str) -> String {
+    convert_str_with_options(input, &tts_options())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_ruby_reading() {
+        assert_eq!(
+            convert("タイトル\n\n吾輩《わがはい》は猫"),
+            "わがはいは猫\n"
+        );
+        assert_eq!(
+            convert("タイトル\n\n｜猫又《ねこまた》と見当《けんとう》"),
+            "ねこまたとけんとう\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_keeps_kana_base() {
+        assert_eq!(convert("タイトル\n\nさよう《ヽヽヽ》なら"), "さようなら\n");
+    }
+
+    #[test]
+    fn test_convert_drops_notes_and_keeps_numbers() {
+        let input = "タイトル\n\n第12［＃「12」は縦中横］号［＃「号」に傍点］［＃割り注］注釈［＃割り注終わり］\n［＃ここから２字下げ］\n本文［＃全角アキ］続き\n［＃ここで字下げ終わり］\n\n底本：青空文庫";
+        assert_eq!(convert(input), "第12号注釈\n\n本文　続き\n");
+    }
+
+    #[test]
+    fn test_convert_gaiji() {
+        assert_eq!(
+            convert("タイトル\n\n※［＃「丸印」、U+25CB］と※［＃「てへん＋劣」、120-8］"),
+            "○とてへん＋劣\n"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! HTML変換のスナップショットテスト
+//!
+//! `tests/snapshots/` の `NAME.txt`（青空文庫形式、UTF-8）を既定のオプションで変換し、
+//! 同じ名前の `NAME.html`（UTF-8）と比較します。改行コードの違いは無視します。
+//!
+//! `NAME.html` はこのクレートの変換結果を確認して保存したもので、
+//! Ruby版 aozora2html の出力ではありません。意図しない出力の変化を検出するためのものです。
+//!
+//! スナップショットを追加するには `NAME.txt` を置き、環境変数 `UPDATE_SNAPSHOTS=1` を
+//! 付けてテストを実行すると `NAME.html` を書き出します。書き出した内容は確認してからコミットしてください。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aozora2::html::{self, RenderOptions};
+
+/// スナップショットのディレクトリ
+fn snapshots_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+/// 改行コードをLFにそろえる
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+/// 期待値と結果を比較し、最初に異なる行を説明する
+fn first_difference(expected: &str, actual: &str) -> Option<String> {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line_no = 1;
+
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => {
+                // 行は一致していても末尾の改行が異なる場合
+                return (expected != actual).then(|| "trailing newline differs".to_string());
+            }
+            (Some(e), Some(a)) if e == a => {}
+            (e, a) => {
+                return Some(format!(
+                    "first difference at line {line_no}\n  expected: {}\n    actual: {}",
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                ));
+            }
+        }
+        line_no += 1;
+    }
+}
+
+#[test]
+fn test_html_snapshots() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut inputs: Vec<PathBuf> = fs::read_dir(snapshots_dir())
+        .expect("failed to read snapshots directory")
+        .map(|entry| entry.expect("failed to read snapshot entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "no snapshots found");
+
+    let mut failures = Vec::new();
+    for input_path in &inputs {
+        let name = input_path.file_stem().unwrap().to_string_lossy();
+        let expected_path = input_path.with_extension("html");
+        let input = fs::read_to_string(input_path).expect("failed to read snapshot input");
+        let actual = html::convert(&input, &RenderOptions::default());
+
+        if update {
+            fs::write(&expected_path, normalize_newlines(&actual))
+                .expect("failed to write snapshot");
+            continue;
+        }
+        let Ok(expected) = fs::read_to_string(&expected_path) else {
+            failures.push(format!("{name}: missing {}", expected_path.display()));
+            continue;
+        };
+
+        if let Some(diff) =
+            first_difference(&normalize_newlines(&expected), &normalize_newlines(&actual))
+        {
+            failures.push(format!("{name}: {diff}"));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} snapshots failed (run with UPDATE_SNAPSHOTS=1 to accept the new output):\n{}",
+        failures.len(),
+        inputs.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_first_difference_reports_line() {
+    assert_eq!(first_difference("a\nb\n", "a\nb\n"), None);
+    assert_eq!(
+        first_difference("a\nb\n", "a\nc\n").unwrap(),
+        "first difference at line 2\n  expected: b\n    actual: c"
+    );
+    assert_eq!(
+        first_difference("a\n", "a\nb\n").unwrap(),
+        "first difference at line 2\n  expected: <end of file>\n    actual: b"
+    );
+    assert!(first_difference("a\n", "a").is_some());
+}
@@ -0,0 +1,118 @@
+//! aozora2text - 青空文庫形式のプレーンテキスト変換のユーティリティ
+//!
+//! 変換オプションを指定する場合は [`aozora2::strip`] を使ってください。
+//!
+//! # 使用例
+//!
+//! ```
+//! let input = "題名\n\n吾輩《わがはい》は猫である。\n底本：青空文庫";
+//! assert_eq!(aozora2text::convert_str(input), "吾輩は猫である。\n");
+//! assert_eq!(aozora2text::count_chars(input.as_bytes()), 8);
+//! ```
+
+use std::io::{self, Read, Seek};
+
+use aozora2::aozora_core::char_type::count_graphemes;
+use aozora2::aozora_core::zip::read_first_txt_from_zip_reader;
+use aozora2::strip;
+
+/// 青空文庫形式のバイト列をプレーンテキストに変換
+///
+/// エンコーディング（UTF-8 / Shift_JIS）を自動判定し、本文を抽出して変換します。
+/// [`aozora2::strip::convert`] と同じです。
+pub fn convert(input: &[u8]) -> String {
+    strip::convert(input)
+}
+
+/// UTF-8の文字列をプレーンテキストに変換
+///
+/// エンコーディング判定を行わず、本文の抽出と変換だけを行います。
+///
+/// # Examples
+///
+/// ```
+/// let input = "タイトル\n著者\n\n吾輩《わがはい》は猫である\n底本：青空文庫";
+/// assert_eq!(aozora2text::convert_str(input), "吾輩は猫である\n");
+/// ```
+pub fn convert_str(input: &str) -> String {
+    strip::convert_str(input)
+}
+
+/// ZIPデータの最初の .txt ファイルをプレーンテキストに変換
+///
+/// CLIの `--zip` と同じく、CRCが不正なZIPファイルも読み込みます。
+/// 変換は [`aozora2::strip::convert`] と同じです（エンコーディング自動判定・本文抽出）。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let text = aozora2text::convert_zip(File::open("example.zip").unwrap()).unwrap();
+/// ```
+pub fn convert_zip<R: Read + Seek>(reader: R) -> io::Result<String> {
+    let bytes = read_first_txt_from_zip_reader(reader)?;
+    Ok(convert(&bytes))
+}
+
+/// 本文の文字数を数える
+///
+/// 本文をプレーンテキストに変換し（ルビは親文字のみ、外字は1文字）、
+/// 改行を除いた書記素クラスタの数を返します。
+/// 書記素クラスタの数え方は [`count_graphemes`] と同じです。
+pub fn count_chars(input: &[u8]) -> usize {
+    strip::convert(input).lines().map(count_graphemes).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_str_matches_convert() {
+        let input = "吾輩は猫である\n夏目漱石\n\n吾輩《わがはい》は猫《ねこ》である。\n名前はまだ無い［＃「無い」に傍点］。\n\n底本：「夏目漱石全集」";
+        assert_eq!(convert_str(input), convert(input.as_bytes()));
+        assert_eq!(convert_str(input), "吾輩は猫である。\n名前はまだ無い。\n");
+    }
+
+    #[test]
+    fn test_convert_zip() {
+        use std::io::{Cursor, Write};
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("work.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .write_all("題名\n\n吾輩《わがはい》は猫である。\n底本：青空文庫".as_bytes())
+            .unwrap();
+        let cursor = writer.finish().unwrap();
+
+        let text = convert_zip(Cursor::new(cursor.into_inner())).unwrap();
+        assert_eq!(text, "吾輩は猫である。\n");
+    }
+
+    #[test]
+    fn test_count_chars() {
+        let input = "題名\n著者\n\n\
+            吾輩《わがはい》は｜猫《ねこ》である［＃「である」に傍点］。\n\
+            ※［＃「丸印」、U+25CB］と※［＃「てへん＋劣」、第3水準1-84-77］と※［＃「存在しない」］\n\
+            \n\
+            底本：青空文庫";
+        // 「吾輩は猫である。」の8文字と、外字3文字と「と」2文字
+        assert_eq!(count_chars(input.as_bytes()), 13);
+    }
+
+    #[test]
+    fn test_count_combining_characters() {
+        let input = "題名\n\nか\u{309A}葛\u{E0100}\u{200D}x";
+        assert_eq!(count_chars(input.as_bytes()), 2);
+
+        // 肌色修飾子・ハングルの字母もコアの数え方と同じ
+        let body = "\u{1F44D}\u{1F3FD}\u{1100}\u{1161}\u{11A8}";
+        let input = format!("題名\n\n{body}");
+        assert_eq!(count_chars(input.as_bytes()), count_graphemes(body));
+        assert_eq!(count_chars(input.as_bytes()), 2);
+    }
+}
@@ -1,15 +1,28 @@
 //! aozora2text - 青空文庫形式をプレーンテキストに変換
 //!
 //! このコマンドは `aozora2 strip` の薄いラッパーです。
+//! `--format html` を指定すると `aozora2 html` 相当のHTML変換も行えます。
 //! 新規ユーザーは `aozora2` コマンドの使用を推奨します。
 
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
-use aozora2::aozora_core::zip::{is_zip_file, read_first_txt_from_zip};
+use aozora2::aozora_core::encoding::{decode_to_utf8, encode_from_utf8, OutputEncoding};
+use aozora2::aozora_core::input::{read_input, read_zip_volumes};
+use aozora2::html::{self, RenderOptions};
 use aozora2::strip;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum Format {
+    /// プレーンテキスト
+    #[default]
+    Text,
+    /// HTML
+    Html,
+}
 
 #[derive(Parser)]
 #[command(name = "aozora2text")]
@@ -26,48 +39,119 @@ struct Args {
     /// 入力をZIPファイルとして扱う
     #[arg(short, long)]
     zip: bool,
+
+    /// ZIPファイル内のすべての .txt ファイルをファイル名順に結合して変換
+    #[arg(long, requires = "input", conflicts_with = "zip")]
+    zip_all: bool,
+
+    /// 出力形式
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// 外字画像ディレクトリ（`--format html` のみ）
+    #[arg(long, default_value = "../../../gaiji/")]
+    gaiji_dir: String,
+
+    /// CSSファイル（カンマ区切りで複数指定可、`--format html` のみ）
+    #[arg(long, default_value = "../../aozora.css")]
+    css: String,
+
+    /// `<html>` からの完全なHTML文書（Shift_JIS）を出力（`--format html` のみ）
+    ///
+    /// 省略時は本文のHTML断片をUTF-8で出力します。
+    #[arg(long)]
+    full_document: bool,
 }
 
 fn main() -> io::Result<()> {
     let args = Args::parse();
 
-    // 入力読み込み
-    let bytes = if args.zip {
-        let path = args.input.as_ref().ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "ZIP mode requires an input file",
-            )
-        })?;
-        read_first_txt_from_zip(path)?
-    } else {
-        match &args.input {
-            Some(path) => {
-                let bytes = fs::read(path)?;
-                if is_zip_file(&bytes) {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "input appears to be a ZIP file; use --zip option",
-                    ));
-                }
-                bytes
-            }
-            None => {
-                let mut buf = Vec::new();
-                io::stdin().read_to_end(&mut buf)?;
-                buf
-            }
-        }
+    // 入力読み込み（ZIP・gzipにも対応）
+    let bytes = match args.input.as_deref() {
+        Some(path) if args.zip_all => read_zip_volumes(path)?,
+        input => read_input(input, args.zip)?,
     };
 
     // 変換
-    let output = strip::convert(&bytes);
+    let output = convert(&bytes, &args);
 
     // 出力
     match &args.output {
         Some(path) => fs::write(path, &output)?,
-        None => io::stdout().write_all(output.as_bytes())?,
+        None => io::stdout().write_all(&output)?,
     }
 
     Ok(())
 }
+
+/// 指定された形式に変換
+fn convert(bytes: &[u8], args: &Args) -> Vec<u8> {
+    match args.format {
+        Format::Text => strip::convert(bytes).into_bytes(),
+        Format::Html => {
+            let input = decode_to_utf8(bytes);
+            let css_files: Vec<String> =
+                args.css.split(',').map(|s| s.trim().to_string()).collect();
+            let options = RenderOptions::new()
+                .with_gaiji_dir(&args.gaiji_dir)
+                .with_css_files(css_files);
+
+            if args.full_document {
+                // 文書はShift_JISを宣言しているため、それに合わせて出力する
+                let output = html::convert(&input, &options);
+                encode_from_utf8(&output, OutputEncoding::ShiftJis)
+            } else {
+                html::convert_fragment(&input, &options).into_bytes()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "吾輩は猫である\n夏目漱石\n\n吾輩《わがはい》は猫である。\n名前はまだ無い。\n\n底本：「夏目漱石全集」";
+
+    fn parse_args(args: &[&str]) -> Args {
+        Args::parse_from(std::iter::once("aozora2text").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_format_text() {
+        let output = convert(FIXTURE.as_bytes(), &parse_args(&[]));
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "吾輩は猫である。\n名前はまだ無い。\n");
+    }
+
+    #[test]
+    fn test_zip_all_args() {
+        let args = parse_args(&["--zip-all", "works.zip"]);
+        assert!(args.zip_all);
+
+        // 入力ファイルが必要で、--zip とは同時に指定できない
+        let parse = |args: &[&str]| {
+            Args::try_parse_from(std::iter::once("aozora2text").chain(args.iter().copied()))
+        };
+        assert!(parse(&["--zip-all"]).is_err());
+        assert!(parse(&["--zip-all", "--zip", "works.zip"]).is_err());
+    }
+
+    #[test]
+    fn test_format_html_fragment() {
+        let output = convert(FIXTURE.as_bytes(), &parse_args(&["--format", "html"]));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("<ruby><rb>吾輩</rb>"));
+        assert!(!output.contains("<html"));
+    }
+
+    #[test]
+    fn test_format_html_full_document() {
+        let args = parse_args(&["--format", "html", "--full-document", "--css", "a.css"]);
+        let output = convert(FIXTURE.as_bytes(), &args);
+        let output = decode_to_utf8(&output);
+        assert!(output.contains("<ruby><rb>吾輩</rb>"));
+        assert!(output.contains("<html"));
+        assert!(output.contains("href=\"a.css\""));
+    }
+}